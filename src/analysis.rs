@@ -0,0 +1,79 @@
+use crate::app::{analyze_record, GameAnalysis, GameRecord, Language, Lexicon, MoveGenerator};
+use crate::error_template::AppError;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Analyses kept for the games looked at last.
+const MAX_CACHED: usize = 64;
+
+/// One per lexicon that was needed.
+type Generators = Vec<(Language, Arc<MoveGenerator>)>;
+
+/// Where an analyzed game was recorded; each store numbers its games itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnalyzedGame {
+    Result(u64),
+    Correspondence(u64),
+}
+
+/// Analyses of finished games, which do not change, and the move generator
+/// of each lexicon, built on first use. The work runs off the async threads.
+#[derive(Clone, Default)]
+pub struct Analyses {
+    generators: Arc<Mutex<Generators>>,
+    /// Oldest first.
+    done: Arc<Mutex<VecDeque<(AnalyzedGame, GameAnalysis)>>>,
+}
+
+impl Analyses {
+    /// The analysis of `game`, whose record is only fetched when it has not
+    /// been analyzed yet.
+    pub async fn analyze(
+        &self,
+        game: AnalyzedGame,
+        record: impl Future<Output = Result<GameRecord, AppError>>,
+    ) -> Result<GameAnalysis, AppError> {
+        if let Some(analysis) = self.cached(game) {
+            return Ok(analysis);
+        }
+        let record = record.await?;
+        let analyses = self.clone();
+        let analysis = tokio::task::spawn_blocking(move || {
+            analyze_record(&record, &analyses.generator(record.lexicon_language()))
+        })
+        .await
+        .map_err(|error| {
+            tracing::error!("analysis of {game:?} failed: {error}");
+            AppError::Internal
+        })?;
+
+        let mut done = self.done.lock().unwrap();
+        if !done.iter().any(|(cached, _)| *cached == game) {
+            if done.len() >= MAX_CACHED {
+                done.pop_front();
+            }
+            done.push_back((game, analysis.clone()));
+        }
+        Ok(analysis)
+    }
+
+    fn cached(&self, game: AnalyzedGame) -> Option<GameAnalysis> {
+        let done = self.done.lock().unwrap();
+        done.iter()
+            .find(|(cached, _)| *cached == game)
+            .map(|(_, analysis)| analysis.clone())
+    }
+
+    /// Built under the lock, so that concurrent first analyses wait for one
+    /// build instead of each starting their own.
+    fn generator(&self, language: Language) -> Arc<MoveGenerator> {
+        let mut generators = self.generators.lock().unwrap();
+        if let Some((_, generator)) = generators.iter().find(|(built, _)| *built == language) {
+            return generator.clone();
+        }
+        let generator = Arc::new(MoveGenerator::new(Lexicon::for_language(language)));
+        generators.push((language, generator.clone()));
+        generator
+    }
+}
@@ -0,0 +1,28 @@
+use crate::app::{check_words, Language, WordCheck};
+use crate::config::ServerConfig;
+use crate::telemetry::Metrics;
+use axum::{extract::Query, Extension, Json};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct WordQuery {
+    /// Words separated by spaces or commas.
+    words: String,
+    /// Language code of the lexicon, the default one of the server when missing.
+    lang: Option<String>,
+}
+
+/// `GET /api/words?words=chat,chien&lang=fr`, for bots and scripts that
+/// cannot call server functions.
+pub async fn words_handler(
+    Extension(config): Extension<ServerConfig>,
+    Extension(metrics): Extension<Metrics>,
+    Query(query): Query<WordQuery>,
+) -> Json<Vec<WordCheck>> {
+    let language = query
+        .lang
+        .as_deref()
+        .and_then(Language::from_code)
+        .unwrap_or(config.default_language);
+    Json(metrics.time_lookup(|| check_words(&query.words, language)))
+}
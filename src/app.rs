@@ -1,17 +1,73 @@
 use crate::error_template::{AppError, ErrorTemplate};
+use account::*;
+use analysis::*;
+use correspondence::*;
+use flashcards::*;
 use game::*;
+use i18n::*;
+use leaderboard::*;
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
+use lobby::*;
+use setup::*;
+use spectate::*;
+use stats::*;
+use study::*;
+use tournament::*;
+use words::*;
 
+mod account;
+mod analysis;
 mod board;
+mod chat;
+mod correspondence;
+mod flashcards;
 mod game;
+mod grid;
+mod i18n;
+mod leaderboard;
+mod lexicon;
+mod lobby;
 mod rack;
+mod rules;
+mod setup;
+mod spectate;
+mod stats;
+mod study;
+mod tournament;
+mod words;
+
+pub use account::{AuthError, User};
+pub use analysis::{analyze_record, GameAnalysis, MoveGenerator, TurnAnalysis, TOP_MOVES};
+pub use chat::{ChatError, ChatMessage};
+pub use correspondence::{CorrespondenceError, CorrespondenceView, GameListing, Move, TURN_LIMITS};
+pub use flashcards::{
+    bingo_deck, Flashcard, FlashcardAnswer, FlashcardDeal, FlashcardError, FlashcardStats,
+    RETRY_AFTER, REVIEW_HOURS,
+};
+pub use game::{GameRecord, GameState, SeatResult, Tile};
+pub use i18n::{fill, use_language, Language};
+pub use leaderboard::{LeaderboardEntry, RatingPoint};
+#[cfg(feature = "ssr")]
+pub use lexicon::load_word_lists;
+pub use lexicon::{normalize_word, Lexicon};
+pub use rules::{ChallengeRule, PlayError};
+pub use spectate::{GameSnapshot, LiveSummary, LiveTicket, SeatView, MAX_DELAY_ROUNDS};
+pub use study::{find_words, StudyMode, StudyWord};
+pub use tournament::{
+    Pairing, PairingSystem, Standing, TournamentError, TournamentSummary, TournamentView,
+    MAX_ROUNDS,
+};
+pub use words::{check_words, WordCheck};
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    let language = RwSignal::new(initial_language());
+    provide_context(language);
+    provide_session();
 
     view! {
 
@@ -20,8 +76,9 @@ pub fn App() -> impl IntoView {
         // id=leptos means cargo-leptos will hot-reload this stylesheet
         <Stylesheet id="leptos" href="/pkg/leptos-krabbels-6.css"/>
 
-        // sets the document title
-        <Title text="Welcome to KRABBELS"/>
+        // sets the document title and language
+        <Html lang=move || language().code()/>
+        <Title text=move || language().messages().title/>
 
         // content for this welcome page
         <Router fallback=|| {
@@ -32,19 +89,121 @@ pub fn App() -> impl IntoView {
             }
             .into_view()
         }>
+            <header class="flex justify-end gap-2 p-2">
+                <A href="/" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().lobby_title}</A>
+                <A href="/play" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().lobby_play}</A>
+                <A href="/games" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().corr_my_games}</A>
+                <A href="/tools" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().tools_title}</A>
+                <A href="/live" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().live_games}</A>
+                <A href="/tournaments" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().tournaments}</A>
+                <A href="/leaderboard" class="px-2 text-xs underline dark:text-zinc-300">{move || language().messages().leaderboard}</A>
+                <UserMenu/>
+                <LanguageToggle/>
+            </header>
             <main>
                 <Routes>
-                    <Route path="" view=HomePage/>
+                    <Route path="" view=LobbyPage/>
+                    <Route path="/play" view=PlayPage/>
+                    <Route path="/tools" view=ToolsLayout>
+                        <Route path="anagram" view=StudyPage/>
+                        <Route path="check" view=CheckWordPage/>
+                        <Route path="flashcards" view=FlashcardsPage/>
+                        <Route path="" view=|| view! { <Redirect path="/tools/anagram"/> } ssr=SsrMode::Async/>
+                    </Route>
+                    <Route path="/study" view=|| view! { <Redirect path="/tools/anagram"/> } ssr=SsrMode::Async/>
+                    <Route path="/check" view=|| view! { <Redirect path="/tools/check"/> } ssr=SsrMode::Async/>
+                    <Route path="/flashcards" view=|| view! { <Redirect path="/tools/flashcards"/> } ssr=SsrMode::Async/>
+                    <Route path="/login" view=LoginPage/>
+                    <Route path="/register" view=RegisterPage/>
+                    <Route path="/leaderboard" view=LeaderboardPage ssr=SsrMode::PartiallyBlocked/>
+                    <Route path="/player/:name" view=StatsPage ssr=SsrMode::PartiallyBlocked/>
+                    <Route path="/stats/:name" view=StatsRedirect ssr=SsrMode::Async/>
+                    <Route path="/games" view=MyGamesPage/>
+                    <Route path="/game/:id" view=GameLayout ssr=SsrMode::PartiallyBlocked>
+                        <Route path="" view=CorrespondencePage ssr=SsrMode::PartiallyBlocked/>
+                        <Route path="replay" view=ReplayPage ssr=SsrMode::PartiallyBlocked/>
+                    </Route>
+                    <Route path="/tournaments" view=TournamentsPage/>
+                    <Route path="/tournaments/:id" view=TournamentPage/>
+                    <Route path="/review/:id" view=ReviewPage ssr=SsrMode::PartiallyBlocked/>
+                    <Route path="/live" view=LiveGamesPage/>
+                    <Route path="/live/:id" view=SpectatePage/>
                 </Routes>
             </main>
         </Router>
     }
 }
 
-/// Renders the home page of your application.
+/// Tabs over the pages of one correspondence game.
+#[component]
+fn GameLayout() -> impl IntoView {
+    let language = use_language();
+    let params = use_params::<GameParams>();
+    let base = move || {
+        params.with(|params| {
+            params
+                .as_ref()
+                .map(|params| format!("/game/{}", params.id))
+                .unwrap_or_default()
+        })
+    };
+
+    view! {
+        <nav class="container mx-auto px-5 flex gap-3 dark:text-zinc-300">
+            <A href=base exact=true active_class="font-bold" class="underline">{move || language().messages().tab_game}</A>
+            <A href=move || format!("{}/replay", base()) active_class="font-bold" class="underline">{move || language().messages().tab_replay}</A>
+        </nav>
+        <Outlet/>
+    }
+}
+
+/// Tabs over the word tools.
 #[component]
-fn HomePage() -> impl IntoView {
+fn ToolsLayout() -> impl IntoView {
+    let language = use_language();
+
     view! {
-        <Game/>
+        <nav class="container mx-auto px-5 flex gap-3 dark:text-zinc-300">
+            <A href="/tools/anagram" active_class="font-bold" class="underline">{move || language().messages().study_title}</A>
+            <A href="/tools/check" active_class="font-bold" class="underline">{move || language().messages().check_title}</A>
+            <A href="/tools/flashcards" active_class="font-bold" class="underline">{move || language().messages().flashcards_title}</A>
+        </nav>
+        <Outlet/>
+    }
+}
+
+/// Player pages used to live under `/stats`.
+#[component]
+fn StatsRedirect() -> impl IntoView {
+    let params = use_params::<PlayerParams>();
+    let name = params.with_untracked(|params| {
+        params
+            .as_ref()
+            .map(|params| params.name.clone())
+            .unwrap_or_default()
+    });
+
+    view! { <Redirect path=format!("/player/{name}")/> }
+}
+
+/// The setup of a local game, then the game itself.
+#[component]
+fn PlayPage() -> impl IntoView {
+    let language = use_language();
+    let config = RwSignal::new(None::<GameConfig>);
+
+    view! {
+        {move || match config() {
+            Some(game) => view! {
+                <div class="container mx-auto px-5">
+                    <button class="underline dark:text-zinc-300" on:click=move |_| config.set(None)>
+                        {move || language().messages().setup_title}
+                    </button>
+                </div>
+                <Game config=game/>
+            }
+            .into_view(),
+            None => view! { <GameSetup on_start=move |game| config.set(Some(game))/> }.into_view(),
+        }}
     }
 }
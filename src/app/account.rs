@@ -0,0 +1,214 @@
+use super::i18n::*;
+use crate::error_template::{error_codes, AppError};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub name: String,
+}
+
+error_codes! {
+    /// Errors sent back by the account server functions. They travel as their
+    /// `Display` code and are turned back into a localized message on the page.
+    pub enum AuthError {
+        NameTaken = "name-taken" => auth_name_taken,
+        InvalidName = "invalid-name" => auth_invalid_name,
+        WeakPassword = "weak-password" => auth_weak_password,
+        InvalidEmail = "invalid-email" => auth_invalid_email,
+        InvalidCredentials = "invalid-credentials" => auth_invalid_credentials,
+        Storage = "storage" => auth_storage,
+    }
+}
+
+#[server(prefix = "/api", endpoint = "current_user")]
+pub async fn get_current_user() -> Result<Option<User>, ServerFnError> {
+    Ok(crate::auth::current_user())
+}
+
+#[server(prefix = "/api", endpoint = "register")]
+pub async fn register(
+    name: String,
+    password: String,
+    email: Option<String>,
+) -> Result<(), ServerFnError> {
+    use crate::auth::{set_session_cookie, AuthState};
+
+    let auth = expect_context::<AuthState>();
+    let token = auth
+        .register(&name, &password, email.as_deref())
+        .await
+        .map_err(|error| AppError::from(error).into_server())?;
+    set_session_cookie(Some(&token));
+    leptos_axum::redirect("/");
+    Ok(())
+}
+
+#[server(prefix = "/api", endpoint = "login")]
+pub async fn login(name: String, password: String) -> Result<(), ServerFnError> {
+    use crate::auth::{set_session_cookie, AuthState};
+
+    let auth = expect_context::<AuthState>();
+    let token = auth
+        .login(&name, &password)
+        .await
+        .map_err(|error| AppError::from(error).into_server())?;
+    set_session_cookie(Some(&token));
+    leptos_axum::redirect("/");
+    Ok(())
+}
+
+#[server(prefix = "/api", endpoint = "logout")]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use crate::auth::{session_token, set_session_cookie, AuthState};
+    use http::request::Parts;
+
+    let auth = expect_context::<AuthState>();
+    set_session_cookie(None);
+    leptos_axum::redirect("/");
+    match use_context::<Parts>().and_then(|parts| session_token(&parts.headers)) {
+        Some(token) => auth
+            .logout(&token)
+            .await
+            .map_err(|error| AppError::from(error).into_server()),
+        None => Ok(()),
+    }
+}
+
+/// Account actions shared through the context, the current user is refetched
+/// whenever one of them completes.
+#[derive(Clone, Copy)]
+pub struct Session {
+    pub register: Action<Register, Result<(), ServerFnError>>,
+    pub login: Action<Login, Result<(), ServerFnError>>,
+    pub logout: Action<Logout, Result<(), ServerFnError>>,
+    pub user: Resource<(usize, usize, usize), Option<User>>,
+}
+
+pub fn provide_session() {
+    let register = create_server_action::<Register>();
+    let login = create_server_action::<Login>();
+    let logout = create_server_action::<Logout>();
+    let user = create_blocking_resource(
+        move || (register.version()(), login.version()(), logout.version()()),
+        |_| async { get_current_user().await.ok().flatten() },
+    );
+    provide_context(Session {
+        register,
+        login,
+        logout,
+        user,
+    });
+}
+
+pub fn use_session() -> Session {
+    expect_context::<Session>()
+}
+
+/// The signed-in user, `None` for guests and while the session loads.
+pub fn use_current_user() -> Signal<Option<User>> {
+    let session = use_session();
+    Signal::derive(move || session.user.get().flatten())
+}
+
+#[component]
+pub fn UserMenu() -> impl IntoView {
+    let session = use_session();
+    let language = use_language();
+    let messages = move || language().messages();
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || match session.user.get().flatten() {
+                Some(user) => view! {
+                    <span class="px-2 text-xs dark:text-zinc-300">{fill(messages().signed_in_as, &[&user.name])}</span>
+                    <ActionForm action=session.logout class="inline">
+                        <button type="submit" class="px-2 text-xs underline dark:text-zinc-300">{messages().logout}</button>
+                    </ActionForm>
+                }
+                .into_view(),
+                None => view! {
+                    <A href="/login" class="px-2 text-xs underline dark:text-zinc-300">{messages().login}</A>
+                    <A href="/register" class="px-2 text-xs underline dark:text-zinc-300">{messages().register}</A>
+                }
+                .into_view(),
+            }}
+        </Transition>
+    }
+}
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+    let session = use_session();
+    let language = use_language();
+    let messages = move || language().messages();
+    let error = move || {
+        session
+            .login
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    view! {
+        <section class="container mx-auto max-w-sm p-5">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().login}</h1>
+            <ActionForm action=session.login>
+                <CredentialFields/>
+                <button type="submit" class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg">
+                    {move || messages().login}
+                </button>
+            </ActionForm>
+            <p role="alert">{error}</p>
+            <A href="/register" class="underline dark:text-zinc-300">{move || messages().no_account}</A>
+        </section>
+    }
+}
+
+#[component]
+pub fn RegisterPage() -> impl IntoView {
+    let session = use_session();
+    let language = use_language();
+    let messages = move || language().messages();
+    let error = move || {
+        session
+            .register
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    view! {
+        <section class="container mx-auto max-w-sm p-5">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().register}</h1>
+            <ActionForm action=session.register>
+                <CredentialFields/>
+                <label class="block my-2 dark:text-zinc-300">{move || messages().email_optional}
+                    <input type="email" name="email" autocomplete="email" class="block w-full p-1 text-zinc-800"/>
+                </label>
+                <button type="submit" class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg">
+                    {move || messages().register}
+                </button>
+            </ActionForm>
+            <p role="alert">{error}</p>
+        </section>
+    }
+}
+
+#[component]
+fn CredentialFields() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+
+    view! {
+        <label class="block my-2 dark:text-zinc-300">{move || messages().account_name}
+            <input type="text" name="name" required autocomplete="username" class="block w-full p-1 text-zinc-800"/>
+        </label>
+        <label class="block my-2 dark:text-zinc-300">{move || messages().password}
+            <input type="password" name="password" required autocomplete="current-password" class="block w-full p-1 text-zinc-800"/>
+        </label>
+    }
+}
@@ -0,0 +1,589 @@
+use super::board::*;
+use super::correspondence::*;
+use super::game::Action;
+use super::game::*;
+use super::grid::*;
+use super::i18n::*;
+use super::lexicon::*;
+use super::rules::*;
+use crate::error_template::{AppError, ErrorTemplate};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Best moves listed for each turn.
+pub const TOP_MOVES: usize = 5;
+
+/// Lexicon words as a trie, walked one square at a time by the generator.
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<char, usize>,
+    terminal: bool,
+}
+
+/// Finds every legal play of a rack on a board, built once per lexicon.
+pub struct MoveGenerator {
+    nodes: Vec<Node>,
+    lexicon: &'static Lexicon,
+}
+
+impl MoveGenerator {
+    pub fn new(lexicon: &'static Lexicon) -> Self {
+        let mut nodes = vec![Node::default()];
+        for word in lexicon.words() {
+            let mut idx = 0;
+            for c in word.chars() {
+                idx = match nodes[idx].children.get(&c) {
+                    Some(child) => *child,
+                    None => {
+                        nodes.push(Node::default());
+                        let child = nodes.len() - 1;
+                        nodes[idx].children.insert(c, child);
+                        child
+                    }
+                };
+            }
+            nodes[idx].terminal = true;
+        }
+        Self { nodes, lexicon }
+    }
+
+    pub fn lexicon(&self) -> &'static Lexicon {
+        self.lexicon
+    }
+
+    /// Every legal play of `rack`, best scoring first; a blank is tried as
+    /// every letter.
+    pub fn moves(&self, grid: &Grid, rack: &[Tile]) -> Vec<Play> {
+        let mut found = Vec::new();
+        for direction in [(0, 1), (1, 0)] {
+            for row in 1..=BOARD_SIZE {
+                for col in 1..=BOARD_SIZE {
+                    let start = (row, col);
+                    let before = (row.wrapping_sub(direction.0), col.wrapping_sub(direction.1));
+                    if grid.get(before).is_none()
+                        && self.reaches_anchor(grid, start, direction, rack.len())
+                    {
+                        let mut rack = rack.to_vec();
+                        self.extend(
+                            grid,
+                            start,
+                            direction,
+                            0,
+                            &mut rack,
+                            &mut Vec::new(),
+                            &mut found,
+                        );
+                    }
+                }
+            }
+        }
+
+        // A single tile is found along both directions, a blank once per letter.
+        let key = |placements: &[((usize, usize), Tile)]| {
+            placements
+                .iter()
+                .map(|(coord, tile)| (*coord, tile.0))
+                .collect::<Vec<_>>()
+        };
+        found.sort_by_cached_key(|placements| key(placements));
+        found.dedup();
+
+        let mut plays = found
+            .into_iter()
+            .filter_map(|placements| evaluate(grid, &placements, rack).ok())
+            .collect::<Vec<_>>();
+        plays.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| key(&a.placements).cmp(&key(&b.placements)))
+        });
+        plays
+    }
+
+    /// Whether a word starting at `start` can touch the tiles already down,
+    /// or the centre of an empty board, with `tiles` tiles at most.
+    fn reaches_anchor(
+        &self,
+        grid: &Grid,
+        start: (usize, usize),
+        direction: (usize, usize),
+        tiles: usize,
+    ) -> bool {
+        let empty = grid.is_empty();
+        let mut coord = start;
+        let mut placed = 0;
+        while Grid::in_bounds(coord) && placed < tiles {
+            if grid.get(coord).is_some() {
+                return true;
+            }
+            let anchored = if empty {
+                coord == (8, 8)
+            } else {
+                [(0, 1), (1, 0)].into_iter().any(|(dr, dc)| {
+                    grid.get((coord.0 + dr, coord.1 + dc)).is_some()
+                        || grid
+                            .get((coord.0.wrapping_sub(dr), coord.1.wrapping_sub(dc)))
+                            .is_some()
+                })
+            };
+            if anchored {
+                return true;
+            }
+            placed += 1;
+            coord = (coord.0 + direction.0, coord.1 + direction.1);
+        }
+        false
+    }
+
+    /// Walks the trie along the line, playing rack tiles on empty squares and
+    /// reading the ones already down.
+    #[allow(clippy::too_many_arguments)]
+    fn extend(
+        &self,
+        grid: &Grid,
+        coord: (usize, usize),
+        direction: (usize, usize),
+        node: usize,
+        rack: &mut Vec<Tile>,
+        placements: &mut Vec<((usize, usize), Tile)>,
+        found: &mut Vec<Vec<((usize, usize), Tile)>>,
+    ) {
+        if !Grid::in_bounds(coord) {
+            return;
+        }
+        let next = (coord.0 + direction.0, coord.1 + direction.1);
+        let ends = grid.get(next).is_none();
+        let children = &self.nodes[node].children;
+
+        if let Some(tile) = grid.get(coord) {
+            for (letter, child) in children {
+                if tile.letter() == *letter {
+                    if ends && self.nodes[*child].terminal && !placements.is_empty() {
+                        found.push(placements.clone());
+                    }
+                    self.extend(grid, next, direction, *child, rack, placements, found);
+                }
+            }
+            return;
+        }
+
+        let mut tried = Vec::new();
+        for idx in 0..rack.len() {
+            let tile = rack[idx];
+            if tried.contains(&tile) {
+                continue;
+            }
+            tried.push(tile);
+            rack.remove(idx);
+            for (letter, child) in children {
+                let placed = if tile.is_blank() {
+                    Tile::blank_as(*letter)
+                } else {
+                    Some(tile).filter(|tile| tile.0 == *letter)
+                };
+                let Some(placed) = placed else {
+                    continue;
+                };
+                if !self.fits_across(grid, coord, direction, *letter) {
+                    continue;
+                }
+                placements.push((coord, placed));
+                if ends && self.nodes[*child].terminal {
+                    found.push(placements.clone());
+                }
+                self.extend(grid, next, direction, *child, rack, placements, found);
+                placements.pop();
+            }
+            rack.insert(idx, tile);
+        }
+    }
+
+    /// Whether `letter` on `coord` forms a known word across the line, or none.
+    fn fits_across(
+        &self,
+        grid: &Grid,
+        coord: (usize, usize),
+        direction: (usize, usize),
+        letter: char,
+    ) -> bool {
+        let cross = (direction.1, direction.0);
+        let mut start = coord;
+        while grid
+            .get((start.0.wrapping_sub(cross.0), start.1.wrapping_sub(cross.1)))
+            .is_some()
+        {
+            start = (start.0.wrapping_sub(cross.0), start.1.wrapping_sub(cross.1));
+        }
+        let mut word = String::new();
+        let mut current = start;
+        loop {
+            match grid.get(current) {
+                Some(tile) => word.push(tile.letter()),
+                None if current == coord => word.push(letter),
+                None => break,
+            }
+            current = (current.0 + cross.0, current.1 + cross.1);
+        }
+        word.chars().count() == 1 || self.lexicon.contains(&word)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TurnAnalysis {
+    /// Index of the turn in the game history.
+    pub turn: usize,
+    pub player: usize,
+    pub rack: Vec<Tile>,
+    /// The board before the turn.
+    pub grid: Grid,
+    pub action: Action,
+    /// Points of the move played, challenge bonus aside.
+    pub score: usize,
+    pub best: Vec<Play>,
+    /// Points between the best move and the one played.
+    pub missed: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameAnalysis {
+    pub id: u64,
+    pub seats: Vec<SeatResult>,
+    /// Turns recorded with their rack; lost turns are left out.
+    pub turns: Vec<TurnAnalysis>,
+    /// Points left on the table by each seat.
+    pub missed: Vec<usize>,
+}
+
+/// Replays a recorded game and compares every move with the best ones the
+/// rack allowed, found by the generator of its lexicon.
+pub fn analyze_record(record: &GameRecord, generator: &MoveGenerator) -> GameAnalysis {
+    let mut grid = Grid::with_layout(record.layout());
+    let mut turns = Vec::new();
+    let mut missed = vec![0; record.seats.len()];
+    for (idx, turn) in record.history.iter().enumerate() {
+        if !turn.rack.is_empty() && turn.action != Action::LostTurn {
+            let mut best = generator.moves(&grid, &turn.rack);
+            best.truncate(TOP_MOVES);
+            let score = match &turn.action {
+                Action::Play(play) => play.score,
+                _ => 0,
+            };
+            let lost = best
+                .first()
+                .map_or(0, |play| play.score.saturating_sub(score));
+            if let Some(seat) = missed.get_mut(turn.player) {
+                *seat += lost;
+            }
+            turns.push(TurnAnalysis {
+                turn: idx,
+                player: turn.player,
+                rack: turn.rack.clone(),
+                grid,
+                action: turn.action.clone(),
+                score,
+                best,
+                missed: lost,
+            });
+        }
+        if let Action::Play(play) = &turn.action {
+            for (coord, tile) in &play.placements {
+                grid.set(*coord, Some(*tile));
+            }
+        }
+    }
+    GameAnalysis {
+        id: record.id,
+        seats: record.seats.clone(),
+        turns,
+        missed,
+    }
+}
+
+#[server(prefix = "/api", endpoint = "analyze_game")]
+pub async fn analyze_game(id: u64) -> Result<GameAnalysis, ServerFnError> {
+    use crate::analysis::{Analyses, AnalyzedGame};
+    use crate::auth::current_user;
+    use crate::ratings::ResultStore;
+
+    current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    let results = expect_context::<ResultStore>();
+    expect_context::<Analyses>()
+        .analyze(AnalyzedGame::Result(id), async {
+            results.game(id).await.ok_or(AppError::GameNotFound)
+        })
+        .await
+        .map_err(AppError::into_server)
+}
+
+/// Analysis of a correspondence game, once it is finished and every rack
+/// can be shown.
+#[server(prefix = "/api", endpoint = "analyze_correspondence")]
+pub async fn analyze_correspondence(id: u64) -> Result<GameAnalysis, ServerFnError> {
+    use crate::analysis::{Analyses, AnalyzedGame};
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    let games = expect_context::<CorrespondenceStore>();
+    expect_context::<Analyses>()
+        .analyze(AnalyzedGame::Correspondence(id), games.record(id))
+        .await
+        .map_err(AppError::into_server)
+}
+
+fn rack_text(rack: &[Tile]) -> String {
+    rack.iter()
+        .map(|tile| if tile.is_blank() { '?' } else { tile.0 })
+        .collect()
+}
+
+/// First square and words of a play, e.g. `H8 KRABS`.
+fn play_text(play: &Play) -> String {
+    let start = play
+        .placements
+        .iter()
+        .map(|(coord, _)| *coord)
+        .min()
+        .unwrap_or_default();
+    format!("{} {}", square_name(start), play.words_text())
+}
+
+/// A recorded game, by the id it was recorded under.
+#[component]
+pub fn ReviewPage() -> impl IntoView {
+    let params = use_params::<GameParams>();
+    let id =
+        Signal::derive(move || params.with(|params| params.as_ref().ok().map(|params| params.id)));
+    let analysis = create_blocking_resource(id, |id| async move {
+        match id {
+            Some(id) => analyze_game(id)
+                .await
+                .map_err(|error| AppError::from_server(&error)),
+            None => Err(AppError::GameNotFound),
+        }
+    });
+
+    view! { <GameReview id analysis/> }
+}
+
+/// A finished correspondence game, under the game it was played in.
+#[component]
+pub fn ReplayPage() -> impl IntoView {
+    let params = use_params::<GameParams>();
+    let id =
+        Signal::derive(move || params.with(|params| params.as_ref().ok().map(|params| params.id)));
+    let analysis = create_blocking_resource(id, |id| async move {
+        match id {
+            Some(id) => analyze_correspondence(id)
+                .await
+                .map_err(|error| AppError::from_server(&error)),
+            None => Err(AppError::GameNotFound),
+        }
+    });
+
+    view! { <GameReview id analysis/> }
+}
+
+/// Steps through a game turn by turn, the board showing the position
+/// before the turn with the chosen move laid on it.
+#[component]
+fn GameReview(
+    #[prop(into)] id: Signal<Option<u64>>,
+    analysis: Resource<Option<u64>, Result<GameAnalysis, AppError>>,
+) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+
+    let selected = RwSignal::new(0);
+    // The play shown on the board: the one made, or a candidate picked from the list.
+    let shown = RwSignal::new(None::<Play>);
+    let current = move || {
+        analysis
+            .get()
+            .and_then(Result::ok)
+            .and_then(|analysis| analysis.turns.get(selected()).cloned())
+    };
+    let turn_count = move || {
+        analysis.with(|analysis| {
+            analysis
+                .as_ref()
+                .and_then(|analysis| analysis.as_ref().ok())
+                .map_or(0, |analysis| analysis.turns.len())
+        })
+    };
+
+    let board = BoardStore::new();
+    let coord_signal = RwSignal::new((0, 0));
+    let rack_signal = RwSignal::new(Vec::new());
+
+    create_effect(move |_| {
+        let Some(turn) = current() else {
+            return;
+        };
+        let play = shown().or(match turn.action {
+            Action::Play(play) => Some(play),
+            _ => None,
+        });
+        let player = analysis.with(|analysis| {
+            analysis
+                .as_ref()
+                .and_then(|analysis| analysis.as_ref().ok()?.seats.get(turn.player))
+                .map(|seat| seat.name.clone())
+                .unwrap_or_default()
+        });
+        // The move shown stands out as the last one, older tiles have no origin here.
+        let origins = play.iter().flat_map(|play| {
+            play.words
+                .iter()
+                .flat_map(|word| word.coords.iter())
+                .map(|coord| TileOrigin {
+                    coord: *coord,
+                    turn: turn.turn + 1,
+                    player: player.clone(),
+                    last_move: play.placements.iter().any(|(placed, _)| placed == coord),
+                    new_word: true,
+                })
+        });
+        board.show(&turn.grid, play.as_ref(), &origins.collect::<Vec<_>>());
+        board.lock();
+    });
+
+    let select = move |idx: usize| {
+        shown.set(None);
+        selected.set(idx);
+    };
+
+    let draw_summary = move || {
+        analysis.get().and_then(Result::ok).map(|analysis| {
+            let messages = messages();
+            analysis
+                .seats
+                .iter()
+                .zip(&analysis.missed)
+                .map(|(seat, missed)| {
+                    view! {
+                        <li>
+                            {fill(messages.player_score, &[&seat.name, &seat.score.to_string()])}" — "
+                            {fill(messages.review_missed_total, &[&missed.to_string()])}
+                        </li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let draw_turn = move || {
+        let (Some(turn), Some(Ok(analysis))) = (current(), analysis.get()) else {
+            return view! {}.into_view();
+        };
+        let messages = messages();
+        let name = analysis
+            .seats
+            .get(turn.player)
+            .map(|seat| seat.name.clone())
+            .unwrap_or_default();
+        let played = match &turn.action {
+            Action::Play(play) => fill(
+                messages.turn_play,
+                &[&play_text(play), &turn.score.to_string()],
+            ),
+            Action::Withdrawn(play) => fill(messages.turn_withdrawn, &[&play_text(play)]),
+            Action::Pass => messages.turn_pass.to_string(),
+            Action::Exchange(count) => fill(messages.turn_exchange, &[&count.to_string()]),
+            Action::LostTurn => messages.turn_lost.to_string(),
+        };
+        let best = turn
+            .best
+            .iter()
+            .cloned()
+            .map(|play| {
+                let text = fill(messages.turn_play, &[&play_text(&play), &play.score.to_string()]);
+                let chosen = play.clone();
+                view! {
+                    <li>
+                        <button class="underline" class=("font-bold", move || shown().as_ref() == Some(&chosen))
+                        on:click=move |_| shown.set(Some(play.clone()))>{text}</button>
+                    </li>
+                }
+            })
+            .collect_view();
+        view! {
+            <h2 class="font-bold">{fill(messages.review_turn, &[&(turn.turn + 1).to_string(), &name])}</h2>
+            <p>{fill(messages.review_rack, &[&rack_text(&turn.rack)])}</p>
+            <p>
+                <button class="underline" on:click=move |_| shown.set(None)>{fill(messages.review_played, &[&played])}</button>
+            </p>
+            <h3 class="font-bold">{messages.review_best}</h3>
+            <ol class="list-decimal pl-5">{best}</ol>
+            <p class=("hidden", turn.best.is_empty())>{fill(messages.review_missed, &[&turn.missed.to_string()])}</p>
+            <p class=("hidden", !turn.best.is_empty())>{messages.review_no_move}</p>
+        }
+        .into_view()
+    };
+
+    let draw_turns = move || {
+        analysis.get().and_then(Result::ok).map(|analysis| {
+            let messages = messages();
+            analysis
+                .turns
+                .iter()
+                .enumerate()
+                .map(|(idx, turn)| {
+                    let name = analysis
+                        .seats
+                        .get(turn.player)
+                        .map(|seat| seat.name.clone())
+                        .unwrap_or_default();
+                    let text = fill(messages.review_turn, &[&(turn.turn + 1).to_string(), &name]);
+                    let text = format!("{text} (−{})", turn.missed);
+                    view! {
+                        <li>
+                            <button class="underline" class=("font-bold", move || selected() == idx)
+                            on:click=move |_| select(idx)>{text}</button>
+                        </li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    // A game that cannot be analysed gets an error page instead.
+    let draw_page = move || {
+        match analysis.get() {
+        Some(Err(error)) => {
+            let mut outside_errors = Errors::default();
+            outside_errors.insert_with_default_key(error);
+            view! { <ErrorTemplate outside_errors/> }.into_view()
+        }
+        _ => view! {
+            <main class="container mx-auto pt-2 lg:p-5 grid grid-rows-2 lg:grid-rows-1 lg:grid-cols-2">
+                <div class="flex justify-center">
+                    <Board board coord_signal rack_signal/>
+                </div>
+
+                <div class="pl-5 dark:text-zinc-300">
+                    <h1 class="text-2xl font-bold dark:text-yellow-100">{move || fill(messages().review_title, &[&id().unwrap_or_default().to_string()])}</h1>
+                    <ul class="my-2">{draw_summary}</ul>
+                    <div class="my-2">
+                        <button class="px-2 underline" disabled=move || selected() == 0
+                        on:click=move |_| select(selected().saturating_sub(1))>{move || messages().review_previous}</button>
+                        <button class="px-2 underline" disabled={move || selected() + 1 >= turn_count()}
+                        on:click=move |_| select(selected() + 1)>{move || messages().review_next}</button>
+                    </div>
+                    <div aria-live="polite">{draw_turn}</div>
+                    <ol class="my-3 text-sm">{draw_turns}</ol>
+                </div>
+            </main>
+        }
+        .into_view(),
+    }
+    };
+
+    view! { <Transition fallback=|| ()>{draw_page}</Transition> }
+}
@@ -1,123 +1,343 @@
+use super::game::Action;
 use super::game::*;
+use super::grid::*;
+use super::i18n::*;
+use super::lexicon::*;
+use super::rules::*;
 use leptos::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-const BOARD_SIZE: usize = 15;
-
-#[derive(Clone)]
-pub struct Cell {
-    pub coord: (usize, usize),
-    pub cell_kind: CellKind,
-    // has_tile: Option<RwSignal<Tile>>,
-    toggle: RwSignal<bool>,
-    label: RwSignal<String>,
-    pub letter_score: RwSignal<(char, usize)>,
+/// What lies on a square.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum SquareState {
+    #[default]
+    Empty,
+    /// Put down from the rack, it can still be picked up. A blank is
+    /// already given the letter it stands for, see [`Tile::blank_as`].
+    Tentative(Tile),
+    /// On the grid or in a pending play.
+    Committed(Tile),
+    /// A committed blank played as this letter.
+    BlankAs(char),
 }
 
-impl Cell {
-    pub fn new(coord: (usize, usize)) -> Self {
-        Self {
-            coord,
-            cell_kind: CellKind::Normal,
-            // has_tile: None,
-            toggle: RwSignal::new(false),
-            label: RwSignal::new(String::new()),
-            letter_score: RwSignal::new((' ', 9)),
+impl SquareState {
+    /// Committed `tile`, blanks keeping the letter they stand for.
+    pub fn committed(tile: Tile) -> Self {
+        if tile.is_blank() {
+            SquareState::BlankAs(tile.letter())
+        } else {
+            SquareState::Committed(tile)
         }
     }
 
-    fn set_cell_kind(&mut self, new_kind: CellKind) {
-        self.cell_kind = new_kind;
+    /// The letter a blank on the square stands for, `None` on any other square.
+    pub fn blank_letter(&self) -> Option<char> {
+        match self {
+            SquareState::BlankAs(letter) => Some(*letter),
+            SquareState::Tentative(tile) if tile.is_blank() => Some(tile.letter()),
+            _ => None,
+        }
     }
 
-    fn num_to_char(num: &usize) -> char {
-        ((num - 1) as u8 + b'A') as char
+    pub fn is_empty(&self) -> bool {
+        *self == SquareState::Empty
+    }
+
+    pub fn is_tentative(&self) -> bool {
+        matches!(self, SquareState::Tentative(_))
     }
 }
 
-#[derive(Clone)]
-pub enum CellKind {
-    Header(Header),
-    Normal,
-    DoubleLetter,
-    TripleLetter,
-    DoubleWord,
-    TripleWord,
+/// What one square shows, as plain data.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Cell {
+    pub state: SquareState,
+    /// Premium of the square in the layout of the game.
+    pub kind: CellKind,
+    /// Nothing can be put down or picked up, the board is only looked at.
+    pub locked: bool,
+    /// Part of a word of the move being previewed, `Some(false)` when that
+    /// word is not in the lexicon.
+    pub preview: Option<bool>,
+    /// Who put the committed tile down, if known.
+    pub origin: Option<TileOrigin>,
 }
 
-#[derive(Clone)]
-pub enum Header {
-    Zero,
-    Top,
-    Left,
+/// Every square of the board, addressed with the same 1-based `(row, col)`
+/// coordinates as [`Grid`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardState {
+    cells: [[Cell; BOARD_SIZE]; BOARD_SIZE],
 }
 
-#[component]
-fn Square(
-    cell: Cell,
-    coord_signal: RwSignal<(usize, usize)>,
-    rack_signal: RwSignal<Vec<Tile>>,
-) -> impl IntoView {
-    // let global_state = use_context::<GlobalState>().unwrap();
-    // let rack = move || global_state.global_rack.read_only().get();
-    // let rack_copy = rack.clone();
+impl Default for BoardState {
+    fn default() -> Self {
+        let mut board = Self {
+            cells: Default::default(),
+        };
+        board.set_layout(BoardLayout::Standard);
+        board
+    }
+}
 
-    let untracked_rack = RwSignal::new(Vec::new());
-    let new_untrack = move || untracked_rack.set(rack_signal.get_untracked());
+fn coords() -> impl Iterator<Item = (usize, usize)> {
+    (1..=BOARD_SIZE).flat_map(|row| (1..=BOARD_SIZE).map(move |col| (row, col)))
+}
 
-    let tile_roll = move || {
-        if untracked_rack().is_empty() {
-            cell.toggle.update(|b| *b = false);
-            (' ', 9)
-        } else {
-            cell.toggle.update(|b| *b = true);
-            (untracked_rack()[0].0, untracked_rack()[0].1)
+impl BoardState {
+    pub fn get(&self, coord: (usize, usize)) -> Option<&Cell> {
+        Grid::in_bounds(coord).then(|| &self.cells[coord.0 - 1][coord.1 - 1])
+    }
+
+    fn set_layout(&mut self, layout: BoardLayout) {
+        let kinds = layout.kinds();
+        for (row, cells) in self.cells.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                cell.kind = kinds[row][col];
+            }
         }
-    };
+    }
 
-    view! {
-        <div class="tile-inner" class=("tile-letter", move || cell.toggle.get()) on:click=move |_| {
-                if !rack_signal().is_empty() && untracked_rack().is_empty() {
-                    new_untrack()
-                }
-                else if !untracked_rack().is_empty() {
-                    untracked_rack.update(|vec| {vec.remove(0);})
+    /// Tiles placed on the board but not validated yet.
+    pub fn placed_tiles(&self) -> Vec<((usize, usize), Tile)> {
+        coords()
+            .filter_map(|coord| {
+                let SquareState::Tentative(tile) = self.get(coord)?.state else {
+                    return None;
                 };
-                cell.letter_score.set(tile_roll());
-                coord_signal.set(cell.coord);
-            }>
-                <div class=("hidden", move || cell.letter_score.with(|t| t.1) != 9)>{cell.label}/*{if rack_cell_signal().is_empty() {'*'} else {rack_cell_signal()[0].0}}*/</div>
-
-                <div class=("hidden", move || cell.letter_score.with(|t| t.1) == 9)>
-                    {move || cell.letter_score.with(|t| t.0)}
-                    <sub class=("hidden", move || cell.letter_score.with(|t| t.1) == 0)>{move || cell.letter_score.with(|t| t.1)}</sub>
-                </div>
-        </div>
+                Some((coord, tile))
+            })
+            .collect()
     }
 }
 
-#[component]
-pub fn Board(
-    board_signal: RwSignal<Vec<Cell>>,
-    coord_signal: RwSignal<(usize, usize)>,
-    rack_signal: RwSignal<Vec<Tile>>,
-) -> impl IntoView {
-    let mut board_cells: Vec<Cell> = Vec::new();
-    for row in 0..BOARD_SIZE + 1 {
-        for col in 0..BOARD_SIZE + 1 {
-            let new_cell = Cell::new((row, col));
-            board_cells.push(new_cell);
-        }
-    }
-
-    for c in &mut board_cells {
-        match c.coord {
-            (0, 0) => c.set_cell_kind(CellKind::Header(Header::Zero)),
-            (0, _) => c.set_cell_kind(CellKind::Header(Header::Top)),
-            (_, 0) => c.set_cell_kind(CellKind::Header(Header::Left)),
-            (8, 8) => {
-                c.set_cell_kind(CellKind::DoubleWord);
-                c.label.set("★".to_string())
+/// The board as one signal per square, so placing a tile re-renders that
+/// square only. The pages read a plain [`BoardState`], built when asked for,
+/// to validate a move.
+#[derive(Clone, Copy)]
+pub struct BoardStore {
+    cells: StoredValue<[[RwSignal<Cell>; BOARD_SIZE]; BOARD_SIZE]>,
+    /// The tentative squares in board order, kept apart so the move preview
+    /// does not depend on the other squares.
+    placed: RwSignal<Vec<((usize, usize), Tile)>>,
+}
+
+impl BoardStore {
+    pub fn new() -> Self {
+        let board = BoardState::default();
+        Self {
+            cells: store_value(board.cells.map(|row| row.map(RwSignal::new))),
+            placed: RwSignal::new(Vec::new()),
+        }
+    }
+
+    pub fn snapshot_untracked(&self) -> BoardState {
+        BoardState {
+            cells: self
+                .cells
+                .get_value()
+                .map(|row| row.map(|cell| cell.get_untracked())),
+        }
+    }
+
+    /// Tiles placed on the board but not validated yet, tracked.
+    pub fn placed_tiles(&self) -> Vec<((usize, usize), Tile)> {
+        self.placed.get()
+    }
+
+    fn signal(&self, coord: (usize, usize)) -> Option<RwSignal<Cell>> {
+        Grid::in_bounds(coord).then(|| {
+            self.cells
+                .with_value(|cells| cells[coord.0 - 1][coord.1 - 1])
+        })
+    }
+
+    /// Keeps `placed` in step with the state of one square.
+    fn sync_placed(&self, coord: (usize, usize), state: &SquareState) {
+        let tile = match state {
+            SquareState::Tentative(tile) => Some(*tile),
+            _ => None,
+        };
+        let placed = self.placed.with_untracked(|placed| {
+            placed
+                .iter()
+                .find(|(placed, _)| *placed == coord)
+                .map(|(_, tile)| *tile)
+        });
+        if placed == tile {
+            return;
+        }
+        self.placed.update(|placed| {
+            placed.retain(|(placed, _)| *placed != coord);
+            if let Some(tile) = tile {
+                placed.push((coord, tile));
+                placed.sort_by_key(|(coord, _)| *coord);
+            }
+        });
+    }
+
+    /// One square, only notifying its readers when that square changes.
+    pub fn cell(&self, coord: (usize, usize)) -> Signal<Cell> {
+        self.signal(coord)
+            .map_or_else(|| Signal::derive(Cell::default), Signal::from)
+    }
+
+    pub fn update_cell(&self, coord: (usize, usize), f: impl FnOnce(&mut Cell)) {
+        if let Some(cell) = self.signal(coord) {
+            cell.update(f);
+            cell.with_untracked(|cell| self.sync_placed(coord, &cell.state));
+        }
+    }
+
+    /// Gives every square the cell `f` makes of it, notifying only the
+    /// squares that change.
+    fn update_each(&self, f: impl Fn((usize, usize), &Cell) -> Cell) {
+        batch(|| {
+            for coord in coords() {
+                let Some(signal) = self.signal(coord) else {
+                    continue;
+                };
+                let cell = signal.with_untracked(|cell| {
+                    let updated = f(coord, cell);
+                    (updated != *cell).then_some(updated)
+                });
+                if let Some(cell) = cell {
+                    self.sync_placed(coord, &cell.state);
+                    signal.set(cell);
+                }
             }
+        });
+    }
+
+    /// Mirrors the committed tiles and a pending play on the layout of
+    /// `grid`, and clears every other square.
+    pub fn show(&self, grid: &Grid, pending: Option<&Play>, origins: &[TileOrigin]) {
+        let kinds = grid.layout().kinds();
+        self.update_each(|coord, _| {
+            let pending_tile = pending.and_then(|play| {
+                play.placements
+                    .iter()
+                    .find(|(placed, _)| *placed == coord)
+                    .map(|(_, tile)| *tile)
+            });
+            let tile = grid.get(coord).or(pending_tile);
+            Cell {
+                state: tile.map_or(SquareState::Empty, SquareState::committed),
+                kind: kinds[coord.0 - 1][coord.1 - 1],
+                locked: false,
+                preview: None,
+                origin: origins.iter().find(|origin| origin.coord == coord).cloned(),
+            }
+        });
+    }
+
+    /// Locks every square, for boards that are only looked at.
+    pub fn lock(&self) {
+        self.update_each(|_, cell| Cell {
+            locked: true,
+            ..cell.clone()
+        });
+    }
+
+    /// Marks the squares of the previewed words, only touching the squares
+    /// `previous` or `marks` name and that change.
+    fn show_preview(&self, previous: &[((usize, usize), bool)], marks: &[((usize, usize), bool)]) {
+        batch(|| {
+            for (coord, _) in previous.iter().chain(marks) {
+                let Some(signal) = self.signal(*coord) else {
+                    continue;
+                };
+                let preview = marks
+                    .iter()
+                    .filter(|(marked, _)| marked == coord)
+                    .map(|(_, known)| *known)
+                    .min();
+                if signal.with_untracked(|cell| cell.preview != preview) {
+                    signal.update(|cell| cell.preview = preview);
+                }
+            }
+        });
+    }
+}
+
+fn row_letter(row: usize) -> char {
+    ((row - 1) as u8 + b'A') as char
+}
+
+/// Who put a committed tile down, and when.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileOrigin {
+    pub coord: (usize, usize),
+    /// Counted from 1, passes and exchanges included.
+    pub turn: usize,
+    pub player: String,
+    /// Put down by the last move that placed tiles.
+    pub last_move: bool,
+    /// Part of a word formed by that last move.
+    pub new_word: bool,
+}
+
+/// Origins of every tile placed in `history`, `names` being indexed by seat.
+pub fn tile_origins(history: &[Turn], names: &[String]) -> Vec<TileOrigin> {
+    let last = history
+        .iter()
+        .rposition(|turn| matches!(turn.action, Action::Play(_)));
+    let mut origins = Vec::new();
+    for (idx, turn) in history.iter().enumerate() {
+        let Action::Play(play) = &turn.action else {
+            continue;
+        };
+        let player = names.get(turn.player).cloned().unwrap_or_default();
+        for (coord, _) in &play.placements {
+            origins.push(TileOrigin {
+                coord: *coord,
+                turn: idx + 1,
+                player: player.clone(),
+                last_move: Some(idx) == last,
+                new_word: false,
+            });
+        }
+    }
+    if let Some(Action::Play(play)) = last.map(|idx| &history[idx].action) {
+        for origin in &mut origins {
+            origin.new_word = play
+                .words
+                .iter()
+                .any(|word| word.coords.contains(&origin.coord));
+        }
+    }
+    origins
+}
+
+/// Row letter and column number, e.g. `H8`.
+pub fn square_name(coord: (usize, usize)) -> String {
+    format!("{}{}", row_letter(coord.0), coord.1)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum CellKind {
+    Header(Header),
+    #[default]
+    Normal,
+    DoubleLetter,
+    TripleLetter,
+    DoubleWord,
+    TripleWord,
+}
+
+impl CellKind {
+    /// Premium layout of the standard board, headers included.
+    pub fn at(coord: (usize, usize)) -> Self {
+        match coord {
+            (0, 0) => CellKind::Header(Header::Zero),
+            (0, _) => CellKind::Header(Header::Top),
+            (_, 0) => CellKind::Header(Header::Left),
+            (8, 8) => CellKind::DoubleWord,
             (1, 4)
             | (1, 12)
             | (3, 7)
@@ -141,10 +361,7 @@ pub fn Board(
             | (13, 7)
             | (13, 9)
             | (15, 4)
-            | (15, 12) => {
-                c.set_cell_kind(CellKind::DoubleLetter);
-                c.label.set("LD".to_string())
-            }
+            | (15, 12) => CellKind::DoubleLetter,
             (2, 6)
             | (2, 10)
             | (6, 2)
@@ -156,10 +373,7 @@ pub fn Board(
             | (10, 10)
             | (10, 14)
             | (14, 6)
-            | (14, 10) => {
-                c.set_cell_kind(CellKind::TripleLetter);
-                c.label.set("LT".to_string())
-            }
+            | (14, 10) => CellKind::TripleLetter,
             (2, 2)
             | (2, 14)
             | (3, 3)
@@ -175,52 +389,408 @@ pub fn Board(
             | (13, 3)
             | (13, 13)
             | (14, 2)
-            | (14, 14) => {
-                c.set_cell_kind(CellKind::DoubleWord);
-                c.label.set("MD".to_string())
-            }
+            | (14, 14) => CellKind::DoubleWord,
             (1, 1) | (1, 8) | (1, 15) | (8, 1) | (8, 15) | (15, 1) | (15, 8) | (15, 15) => {
-                c.set_cell_kind(CellKind::TripleWord);
-                c.label.set("MT".to_string())
+                CellKind::TripleWord
             }
-            _ => c.set_cell_kind(CellKind::Normal),
+            _ => CellKind::Normal,
         }
     }
 
-    let draw_cells = move || {
-        board_signal()
-            .into_iter()
-            .map(|cell| match cell.cell_kind {
-                CellKind::Header(Header::Zero) => {
-                    view! {<div class="tile-header label-xs">"krabs"</div>}
-                }
-                CellKind::Header(Header::Top) => view! {<div class="tile-header">{cell.coord.1}</div>},
-                CellKind::Header(Header::Left) => {
-                    view! {<div class="tile-header">{Cell::num_to_char(&cell.coord.0)}</div>}
-                }
-                CellKind::DoubleLetter => {
-                    view! {<div class="tile bg-cyan-200"><Square cell coord_signal rack_signal/></div>}
-                }
-                CellKind::TripleLetter => {
-                    view! {<div class="tile bg-blue-400"><Square cell coord_signal rack_signal/></div>}
-                }
-                CellKind::DoubleWord => {
-                    view! {<div class="tile bg-rose-200"><Square cell coord_signal rack_signal/></div>}
-                }
-                CellKind::TripleWord => {
-                    view! {<div class="tile bg-orange-600"><Square cell coord_signal rack_signal/></div>}
+    pub fn is_premium(&self) -> bool {
+        matches!(
+            self,
+            CellKind::DoubleLetter
+                | CellKind::TripleLetter
+                | CellKind::DoubleWord
+                | CellKind::TripleWord
+        )
+    }
+
+    pub fn description(&self, messages: &'static Messages) -> Option<&'static str> {
+        match self {
+            CellKind::DoubleLetter => Some(messages.premium_double_letter),
+            CellKind::TripleLetter => Some(messages.premium_triple_letter),
+            CellKind::DoubleWord => Some(messages.premium_double_word),
+            CellKind::TripleWord => Some(messages.premium_triple_word),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Header {
+    Zero,
+    Top,
+    Left,
+}
+
+/// Where the premium squares are, chosen when the game is set up.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum BoardLayout {
+    #[default]
+    Standard,
+    /// The premium squares of the standard board dealt again from this seed.
+    Shuffled(u64),
+}
+
+impl BoardLayout {
+    pub fn kind(&self, coord: (usize, usize)) -> CellKind {
+        match self {
+            BoardLayout::Standard => CellKind::at(coord),
+            BoardLayout::Shuffled(_) if Grid::in_bounds(coord) => {
+                self.kinds()[coord.0 - 1][coord.1 - 1]
+            }
+            BoardLayout::Shuffled(_) => CellKind::at(coord),
+        }
+    }
+
+    /// Every square at once, indexed by row and column less one.
+    pub fn kinds(&self) -> [[CellKind; BOARD_SIZE]; BOARD_SIZE] {
+        let dealt = match self {
+            BoardLayout::Standard => HashMap::new(),
+            BoardLayout::Shuffled(seed) => deal_premiums(*seed),
+        };
+        let mut kinds = [[CellKind::Normal; BOARD_SIZE]; BOARD_SIZE];
+        for coord in coords() {
+            kinds[coord.0 - 1][coord.1 - 1] = dealt
+                .get(&eighth(coord))
+                .copied()
+                .unwrap_or_else(|| CellKind::at(coord));
+        }
+        kinds
+    }
+}
+
+/// The square of the top left eighth of the board that `coord` mirrors.
+fn eighth(coord: (usize, usize)) -> (usize, usize) {
+    let fold = |n: usize| n.min(BOARD_SIZE + 1 - n);
+    let (row, col) = (fold(coord.0), fold(coord.1));
+    (row.min(col), row.max(col))
+}
+
+/// Shuffles the premiums of the top left eighth, the rest of the board
+/// mirroring it. Squares mirrored as many times are shuffled together, so
+/// the board keeps as many premiums of each kind, and the centre stays put.
+fn deal_premiums(seed: u64) -> HashMap<(usize, usize), CellKind> {
+    let centre = BOARD_SIZE / 2 + 1;
+    let diagonal = (1..centre).map(|n| (n, n)).collect::<Vec<_>>();
+    let middle = (1..centre).map(|n| (n, centre)).collect::<Vec<_>>();
+    let others = (1..centre)
+        .flat_map(|row| (row + 1..centre).map(move |col| (row, col)))
+        .collect::<Vec<_>>();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut dealt = HashMap::new();
+    for squares in [diagonal, middle, others] {
+        let mut kinds = squares
+            .iter()
+            .map(|coord| CellKind::at(*coord))
+            .collect::<Vec<_>>();
+        kinds.shuffle(&mut rng);
+        dealt.extend(squares.into_iter().zip(kinds));
+    }
+    dealt
+}
+
+/// Short text printed on an empty premium square.
+fn square_label(coord: (usize, usize), kind: CellKind) -> &'static str {
+    match kind {
+        CellKind::DoubleWord if coord == (8, 8) => "★",
+        CellKind::DoubleLetter => "LD",
+        CellKind::TripleLetter => "LT",
+        CellKind::DoubleWord => "MD",
+        CellKind::TripleWord => "MT",
+        _ => "",
+    }
+}
+
+/// Asks which letter a blank being put down stands for.
+fn ask_blank_letter(language: Language) -> Option<Tile> {
+    let answer = window()
+        .prompt_with_message(language.messages().blank_prompt)
+        .ok()??;
+    let letter = normalize_word(answer.trim());
+    let mut letters = letter.chars();
+    match (letters.next(), letters.next()) {
+        (Some(letter), None) => Tile::blank_as(letter),
+        _ => None,
+    }
+}
+
+#[component]
+fn Square(
+    coord: (usize, usize),
+    board: BoardStore,
+    coord_signal: RwSignal<(usize, usize)>,
+    rack_signal: RwSignal<Vec<Tile>>,
+) -> impl IntoView {
+    let language = use_language();
+    let cell = board.cell(coord);
+    let has_tile = move || cell.with(|cell| !cell.state.is_empty());
+
+    // Clicking the square again offers the next tile of the rack, then none.
+    let cycle = RwSignal::new(Vec::<Tile>::new());
+    let place = move || {
+        let state = cell.with_untracked(|cell| (!cell.locked).then_some(cell.state));
+        match state {
+            Some(SquareState::Empty) => cycle.set(rack_signal.get_untracked()),
+            Some(SquareState::Tentative(_)) => cycle.update(|tiles| {
+                if !tiles.is_empty() {
+                    tiles.remove(0);
                 }
-                _ => view! {<div class="tile"><Square cell coord_signal rack_signal/></div>},
+            }),
+            _ => return,
+        }
+        // A blank goes down as the letter asked for; without one, the next
+        // tile is offered instead.
+        let shown = loop {
+            let Some(tile) = cycle.with_untracked(|tiles| tiles.first().copied()) else {
+                break SquareState::Empty;
+            };
+            if tile != Tile::BLANK {
+                break SquareState::Tentative(tile);
+            }
+            if let Some(blank) = ask_blank_letter(language.get_untracked()) {
+                break SquareState::Tentative(blank);
+            }
+            cycle.update(|tiles| {
+                tiles.remove(0);
+            });
+        };
+        board.update_cell(coord, |cell| cell.state = shown);
+        coord_signal.set(coord);
+    };
+
+    // Arrow keys move the selection; the selected square is the only tab stop.
+    let navigate = move |ev: ev::KeyboardEvent| {
+        let (row, col) = coord;
+        let next = match ev.key().as_str() {
+            "ArrowUp" => (row.saturating_sub(1).max(1), col),
+            "ArrowDown" => ((row + 1).min(BOARD_SIZE), col),
+            "ArrowLeft" => (row, col.saturating_sub(1).max(1)),
+            "ArrowRight" => (row, (col + 1).min(BOARD_SIZE)),
+            "Home" => (row, 1),
+            "End" => (row, BOARD_SIZE),
+            "Enter" | " " => {
+                ev.prevent_default();
+                place();
+                return;
+            }
+            _ => return,
+        };
+        ev.prevent_default();
+        coord_signal.set(next);
+    };
+
+    let selected = move || {
+        let selected = coord_signal();
+        if Grid::in_bounds(selected) {
+            selected == coord
+        } else {
+            coord == (8, 8)
+        }
+    };
+
+    let node_ref = create_node_ref::<html::Div>();
+    create_effect(move |_| {
+        if coord_signal() == coord {
+            if let Some(node) = node_ref.get_untracked() {
+                let _ = node.focus();
+            }
+        }
+    });
+
+    let kind = move || cell.with(|cell| cell.kind);
+    let placed = move || {
+        cell.with(|cell| {
+            cell.origin.as_ref().map(|origin| {
+                fill(
+                    language().messages().square_placed,
+                    &[&origin.turn.to_string(), &origin.player],
+                )
             })
-            .collect_view()
+        })
+    };
+    let aria_label = move || {
+        let messages = language().messages();
+        let mut parts = vec![square_name(coord)];
+        if let Some(premium) = kind().description(messages) {
+            parts.push(premium.to_string());
+        }
+        let state = cell.with(|cell| cell.state);
+        parts.push(match (state.blank_letter(), state) {
+            (Some(letter), _) => fill(messages.square_blank_as, &[&letter.to_string()]),
+            (None, SquareState::Tentative(Tile(letter, score)))
+            | (None, SquareState::Committed(Tile(letter, score))) => fill(
+                messages.square_tile,
+                &[&letter.to_string(), &score.to_string()],
+            ),
+            (None, _) => messages.square_empty.to_string(),
+        });
+        if let Some(placed) = placed() {
+            parts.push(placed);
+        }
+        parts.join(", ")
+    };
+    let origin_is = move |flag: fn(&TileOrigin) -> bool| {
+        cell.with(|cell| cell.origin.as_ref().is_some_and(flag))
     };
 
-    board_signal.set(board_cells);
+    // Blanks show no points, and the letter they stand for in lowercase.
+    let draw_state = move || {
+        let state = cell.with(|cell| cell.state);
+        match (state.blank_letter(), state) {
+            (Some(letter), _) => letter.to_ascii_lowercase().into_view(),
+            (None, SquareState::Tentative(Tile(letter, score)))
+            | (None, SquareState::Committed(Tile(letter, score))) => {
+                view! { {letter}<sub>{score}</sub> }.into_view()
+            }
+            (None, _) => square_label(coord, kind()).into_view(),
+        }
+    };
+
+    let premium = move || match kind() {
+        CellKind::DoubleLetter => "tile premium-dl",
+        CellKind::TripleLetter => "tile premium-tl",
+        CellKind::DoubleWord => "tile premium-dw",
+        CellKind::TripleWord => "tile premium-tw",
+        _ => "tile",
+    };
 
     view! {
+        <div class=premium role="none">
+        <div class="tile-inner" class=("tile-letter", has_tile)
+            class=("tile-tentative", move || cell.with(|cell| cell.state.is_tentative()))
+            class=("tile-blank", move || cell.with(|cell| cell.state.blank_letter().is_some()))
+            class=("tile-word", move || cell.with(|cell| cell.preview == Some(true)))
+            class=("tile-word-unknown", move || cell.with(|cell| cell.preview == Some(false)))
+            class=("tile-last-move", move || origin_is(|origin| origin.last_move))
+            class=("tile-new-word", move || origin_is(|origin| origin.new_word))
+            title=placed
+            node_ref=node_ref
+            role="gridcell"
+            tabindex=move || if selected() { 0 } else { -1 }
+            aria-selected=move || selected().to_string()
+            aria-readonly=move || cell.with(|cell| cell.locked).to_string()
+            aria-label=aria_label
+            on:click=move |_| place()
+            on:keydown=navigate>
+                <div aria-hidden="true">{draw_state}</div>
+        </div>
+        </div>
+    }
+}
 
-        <div class="grid gap-0 board lg:board-lg border-0">{draw_cells()}</div>
+/// The squares are laid out once; what they show comes from `board`.
+#[component]
+pub fn Board(
+    board: BoardStore,
+    coord_signal: RwSignal<(usize, usize)>,
+    rack_signal: RwSignal<Vec<Tile>>,
+) -> impl IntoView {
+    let language = use_language();
+
+    let draw_cell = move |coord: (usize, usize)| {
+        match CellKind::at(coord) {
+            CellKind::Header(Header::Zero) => {
+                view! {<div class="tile-header label-xs" role="columnheader" aria-hidden="true">"krabs"</div>}.into_view()
+            }
+            CellKind::Header(Header::Top) => view! {<div class="tile-header" role="columnheader">{coord.1}</div>}.into_view(),
+            CellKind::Header(Header::Left) => {
+                view! {<div class="tile-header" role="rowheader">{row_letter(coord.0)}</div>}.into_view()
+            }
+            _ => view! {<Square coord board coord_signal rack_signal/>},
+        }
+    };
+
+    // Rows only exist for assistive technologies, `contents` keeps the CSS grid flat.
+    let draw_cells = (0..=BOARD_SIZE)
+        .map(|row| {
+            let cells = (0..=BOARD_SIZE)
+                .map(|col| draw_cell((row, col)))
+                .collect_view();
+            view! {<div class="contents" role="row">{cells}</div>}
+        })
+        .collect_view();
+
+    view! {
+
+        <div class="grid gap-0 board lg:board-lg border-0" role="grid"
+            aria-label=move || language().messages().board
+            aria-rowcount=BOARD_SIZE + 1 aria-colcount=BOARD_SIZE + 1>{draw_cells}</div>
+
+    }
+}
 
+/// Evaluates the tiles placed but not validated yet every time one moves:
+/// the words they form are highlighted on the board and the score or the
+/// reason the move is refused is announced. Nothing is committed.
+#[component]
+pub fn MovePreview(
+    board: BoardStore,
+    /// Committed tiles and the rack being played from, `None` while no move can be made.
+    #[prop(into)]
+    position: Signal<Option<(Grid, Vec<Tile>)>>,
+    /// Picks the lexicon the words are looked up in.
+    #[prop(into)]
+    lexicon_language: Signal<Language>,
+) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+
+    let preview = create_memo(move |_| {
+        let placements = board.placed_tiles();
+        if placements.is_empty() {
+            return None;
+        }
+        let (grid, rack) = position()?;
+        let lexicon = Lexicon::for_language(lexicon_language());
+        Some(evaluate(&grid, &placements, &rack).map(|play| {
+            let unknown = play.unknown_words(lexicon);
+            (play, unknown)
+        }))
+    });
+
+    create_effect(move |previous: Option<Vec<_>>| {
+        let marks = match preview() {
+            Some(Ok((play, unknown))) => play
+                .words
+                .iter()
+                .flat_map(|word| {
+                    let known = !unknown.contains(&word.text);
+                    word.coords.iter().map(move |coord| (*coord, known))
+                })
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        board.show_preview(previous.as_deref().unwrap_or_default(), &marks);
+        marks
+    });
+
+    let draw_preview = move || {
+        let messages = messages();
+        match preview() {
+            Some(Ok((play, unknown))) => {
+                let refused = (!unknown.is_empty()).then(|| {
+                    format!(
+                        "❌ {}",
+                        PlayError::UnknownWords(unknown).message(language())
+                    )
+                });
+                view! {
+                    <p>{fill(messages.preview_score, &[&play.words_text(), &play.score.to_string()])}</p>
+                    <p class=("hidden", !play.bingo)>{messages.bingo}</p>
+                    <p>{refused}</p>
+                }
+                .into_view()
+            }
+            Some(Err(error)) => view! { <p>"❌ "{error.message(language())}</p> }.into_view(),
+            None => view! {}.into_view(),
+        }
+    };
+
+    view! {
+        <div role="status" aria-live="polite" class="dark:text-zinc-300">{draw_preview}</div>
     }
 }
 
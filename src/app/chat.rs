@@ -0,0 +1,145 @@
+use super::account::*;
+use super::i18n::*;
+use super::spectate::*;
+use crate::error_template::{error_codes, AppError};
+use leptos::server_fn::codec::Json;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub author: String,
+    pub text: String,
+    /// Seconds since the Unix epoch.
+    pub sent_at: u64,
+}
+
+error_codes! {
+    /// Errors sent back by the chat server functions, as their `Display` code.
+    pub enum ChatError {
+        UnknownGame = "unknown-game" => chat_unknown_game,
+        NotSignedIn = "not-signed-in" => chat_not_signed_in,
+        Empty = "empty" => chat_empty,
+        TooLong = "too-long" => chat_too_long,
+        TooFast = "too-fast" => chat_too_fast,
+        Storage = "storage" => chat_storage,
+    }
+}
+
+/// Where a chat takes place: around a live game, open to its spectators, or
+/// in a correspondence game, between its players.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChatRoom {
+    Live(u64),
+    Correspondence(u64),
+}
+
+#[server(prefix = "/api", endpoint = "chat", input = Json)]
+pub async fn get_chat(room: ChatRoom) -> Result<Vec<ChatMessage>, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+    use crate::live::LiveGames;
+
+    match room {
+        ChatRoom::Live(id) => expect_context::<LiveGames>().chat(id).await,
+        ChatRoom::Correspondence(id) => {
+            let user = current_user()
+                .ok_or(AppError::from(ChatError::NotSignedIn))
+                .map_err(AppError::into_server)?;
+            expect_context::<CorrespondenceStore>()
+                .chat(id, &user.name)
+                .await
+        }
+    }
+    .map_err(|error| AppError::from(error).into_server())
+}
+
+#[server(prefix = "/api", endpoint = "send_chat", input = Json)]
+pub async fn send_chat(room: ChatRoom, text: String) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::chat::ChatGuard;
+    use crate::correspondence::CorrespondenceStore;
+    use crate::live::LiveGames;
+
+    let user = current_user()
+        .ok_or(AppError::from(ChatError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    let message = expect_context::<ChatGuard>()
+        .message(&user.name, &text)
+        .map_err(|error| AppError::from(error).into_server())?;
+    match room {
+        ChatRoom::Live(id) => expect_context::<LiveGames>().post(id, message).await,
+        ChatRoom::Correspondence(id) => {
+            expect_context::<CorrespondenceStore>()
+                .post_chat(id, &user.name, message)
+                .await
+        }
+    }
+    .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Messages of a game, refreshed like the spectator view. Only signed-in
+/// users can write.
+#[component]
+pub fn ChatPanel(#[prop(into)] room: Signal<Option<ChatRoom>>) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let current_user = use_current_user();
+    let tick = refresh_ticker();
+    let send = create_server_action::<SendChat>();
+    let chat = create_resource(
+        move || (room(), tick(), send.version()()),
+        |(room, _, _)| async move {
+            match room {
+                Some(room) => get_chat(room).await.unwrap_or_default(),
+                None => Vec::new(),
+            }
+        },
+    );
+    let draft = RwSignal::new(String::new());
+
+    let error = move || {
+        send.value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    let submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        if let Some(room) = room.get_untracked() {
+            send.dispatch(SendChat {
+                room,
+                text: draft.get_untracked(),
+            });
+            draft.set(String::new());
+        }
+    };
+
+    let draw_messages = move || {
+        chat.get().map(|chat| {
+            chat.into_iter()
+                .map(|message| {
+                    view! {
+                        <li><span class="font-bold">{message.author}</span>" : "{message.text}</li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    view! {
+        <section class="my-3 dark:text-zinc-300" class=("hidden", move || room().is_none()) aria-label=move || messages().chat>
+            <h3 class="font-bold">{move || messages().chat}</h3>
+            <ol class="max-h-40 overflow-y-auto text-sm">
+                <Transition fallback=|| ()>{draw_messages}</Transition>
+            </ol>
+            <form on:submit=submit class=("hidden", move || current_user().is_none())>
+                <input type="text" class="p-1 text-zinc-800" maxlength="300" aria-label=move || messages().chat_message
+                prop:value=draft on:input=move |ev| draft.set(event_target_value(&ev))/>
+                <button type="submit" class="px-2 underline">{move || messages().chat_send}</button>
+            </form>
+            <p role="alert">{error}</p>
+        </section>
+    }
+}
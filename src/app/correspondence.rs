@@ -0,0 +1,595 @@
+use super::board::*;
+use super::chat::*;
+use super::game::Action;
+use super::game::*;
+use super::grid::*;
+use super::i18n::*;
+use super::rack::*;
+use super::rules::*;
+use super::spectate::*;
+use crate::error_template::{error_codes, AppError, ErrorTemplate};
+use leptos::server_fn::codec::Json;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// Time allowed per move, in hours.
+pub const TURN_LIMITS: [u64; 4] = [24, 72, 168, 336];
+
+/// What a player sends for their turn in a correspondence game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Move {
+    Play(Vec<((usize, usize), Tile)>),
+    Exchange(Vec<Tile>),
+    Pass,
+    Accept,
+    Challenge,
+}
+
+/// A correspondence game as one of its players sees it: the other racks and
+/// the bag order stay on the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CorrespondenceView {
+    pub id: u64,
+    pub seat: usize,
+    /// Every seat, with empty racks.
+    pub players: Vec<SeatView>,
+    /// In the order the player last arranged it.
+    pub rack: Vec<Tile>,
+    pub grid: Grid,
+    pub current: usize,
+    pub waiting_on: usize,
+    pub pending: Option<Play>,
+    pub history: Vec<Turn>,
+    pub bag: usize,
+    pub unseen: Vec<Tile>,
+    pub rule: ChallengeRule,
+    /// Decides the lexicon.
+    pub language: Language,
+    pub finished: bool,
+    pub forfeited: Option<usize>,
+    /// The second seat has not accepted the game yet, or declined it when
+    /// finished.
+    pub invited: bool,
+    /// Seconds left before the seat we wait on forfeits, or before the
+    /// invitation lapses.
+    pub time_left: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameListing {
+    pub id: u64,
+    pub players: Vec<SeatView>,
+    /// See [`CorrespondenceView::invited`].
+    pub invited: bool,
+    /// The player has to move, or to answer the invitation.
+    pub your_turn: bool,
+    pub waiting_on: String,
+    pub finished: bool,
+    pub time_left: u64,
+}
+
+error_codes! {
+    /// Errors sent back by the correspondence server functions, as their `Display` code.
+    pub enum CorrespondenceError {
+        UnknownGame = "unknown-game" => corr_unknown_game,
+        UnknownOpponent = "unknown-opponent" => corr_unknown_opponent,
+        NotYourTurn = "not-your-turn" => corr_not_your_turn,
+        TooManyGames = "too-many-games" => corr_too_many_games,
+        Storage = "storage" => corr_storage,
+    }
+}
+
+/// The `:id` of the routes under `/game`.
+#[derive(Params, Clone, PartialEq)]
+pub struct GameParams {
+    pub id: u64,
+}
+
+/// Hours and minutes, e.g. `26 h 05`.
+pub fn format_time_left(seconds: u64) -> String {
+    format!("{} h {:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+#[server(prefix = "/api", endpoint = "create_correspondence")]
+pub async fn create_correspondence(
+    opponent: String,
+    rule: usize,
+    hours: u64,
+) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    let rule = ChallengeRule::ALL.get(rule).copied().unwrap_or_default();
+    let id = expect_context::<CorrespondenceStore>()
+        .invite(&user.name, &opponent, rule, hours, initial_language())
+        .await
+        .map_err(|error| AppError::from(error).into_server())?;
+    leptos_axum::redirect(&format!("/game/{id}"));
+    Ok(())
+}
+
+#[server(prefix = "/api", endpoint = "my_games")]
+pub async fn list_correspondence() -> Result<Vec<GameListing>, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    Ok(expect_context::<CorrespondenceStore>()
+        .list(&user.name)
+        .await)
+}
+
+#[server(prefix = "/api", endpoint = "correspondence")]
+pub async fn get_correspondence(id: u64) -> Result<CorrespondenceView, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    expect_context::<CorrespondenceStore>()
+        .view(id, &user.name)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// The outer error is about access, the inner one a rule broken by the move.
+#[server(prefix = "/api", endpoint = "play_correspondence", input = Json)]
+pub async fn play_correspondence(
+    id: u64,
+    play: Move,
+) -> Result<Result<(), PlayError>, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+    use crate::telemetry::Metrics;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    let outcome = expect_context::<CorrespondenceStore>()
+        .play(id, &user.name, play)
+        .await
+        .map_err(|error| AppError::from(error).into_server())?;
+    if outcome.is_ok() {
+        expect_context::<Metrics>().record_move();
+    }
+    Ok(outcome)
+}
+
+/// Accepts or declines the invitation to the game.
+#[server(prefix = "/api", endpoint = "answer_invitation")]
+pub async fn answer_invitation(id: u64, accept: bool) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    expect_context::<CorrespondenceStore>()
+        .answer(id, &user.name, accept)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Saves the order the player gave their rack. Tiles left out, being on the
+/// board, keep their place after the others.
+#[server(prefix = "/api", endpoint = "arrange_correspondence", input = Json)]
+pub async fn arrange_correspondence(id: u64, rack: Vec<Tile>) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::correspondence::CorrespondenceStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    expect_context::<CorrespondenceStore>()
+        .arrange(id, &user.name, &rack)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Correspondence games of the signed-in user, kept up to date.
+#[component]
+pub fn CorrespondenceList() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let tick = refresh_ticker();
+    let games = create_resource(tick, |_| list_correspondence());
+
+    let draw_games = move || {
+        games.get().map(|games| match games {
+            Ok(games) if !games.is_empty() => games
+                .into_iter()
+                .map(|game| {
+                    let messages = messages();
+                    let players = game
+                        .players
+                        .iter()
+                        .map(|seat| fill(messages.player_score, &[&seat.name, &seat.score.to_string()]))
+                        .collect::<Vec<_>>()
+                        .join(" — ");
+                    let status = if game.finished && game.invited {
+                        messages.corr_declined.to_string()
+                    } else if game.finished {
+                        messages.game_over.to_string()
+                    } else if game.invited && game.your_turn {
+                        fill(messages.corr_invited, &[&game.players[0].name, &format_time_left(game.time_left)])
+                    } else if game.invited {
+                        fill(messages.corr_awaiting_answer, &[&game.waiting_on])
+                    } else if game.your_turn {
+                        fill(messages.corr_your_turn, &[&format_time_left(game.time_left)])
+                    } else {
+                        fill(messages.corr_their_turn, &[&game.waiting_on])
+                    };
+                    view! {
+                        <li class=("font-bold", game.your_turn && !game.finished)>
+                            <A href=format!("/game/{}", game.id) class="underline">{players}</A>" : "{status}
+                        </li>
+                    }
+                })
+                .collect_view(),
+            Ok(_) => view! { <li>{messages().corr_no_games}</li> }.into_view(),
+            Err(error) => view! { <li>{AppError::from_server(&error).message(language())}</li> }.into_view(),
+        })
+    };
+
+    view! {
+        <ul class="my-3"><Transition fallback=|| ()>{draw_games}</Transition></ul>
+    }
+}
+
+#[component]
+pub fn MyGamesPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let create = create_server_action::<CreateCorrespondence>();
+
+    let error = move || {
+        create
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    let rule_options = move || {
+        ChallengeRule::ALL
+            .into_iter()
+            .enumerate()
+            .map(
+                |(idx, rule)| view! { <option value=idx>{move || rule.label(language())}</option> },
+            )
+            .collect_view()
+    };
+
+    let limit_options = move || {
+        TURN_LIMITS
+            .into_iter()
+            .map(|hours| view! { <option value=hours>{move || fill(messages().corr_hours, &[&hours.to_string()])}</option> })
+            .collect_view()
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().corr_my_games}</h1>
+            <CorrespondenceList/>
+
+            <h2 class="font-bold">{move || messages().corr_new_game}</h2>
+            <ActionForm action=create>
+                <label class="block my-2">{move || messages().corr_opponent}
+                    <input type="text" name="opponent" required class="block p-1 text-zinc-800"/>
+                </label>
+                <label class="block my-2">{move || messages().challenge_rule}
+                    <select name="rule" class="text-zinc-800">{rule_options}</select>
+                </label>
+                <label class="block my-2">{move || messages().corr_turn_limit}
+                    <select name="hours" class="text-zinc-800">{limit_options}</select>
+                </label>
+                <button type="submit" class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800">
+                    {move || messages().corr_start}
+                </button>
+            </ActionForm>
+            <p role="alert">{error}</p>
+        </section>
+    }
+}
+
+/// A correspondence game, refreshed until the opponent moves. Tiles are
+/// placed on the board from the rack as in a local game.
+#[component]
+pub fn CorrespondencePage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let params = use_params::<GameParams>();
+    let id = move || params.with(|params| params.as_ref().ok().map(|params| params.id));
+
+    let send = create_action(move |play: &Move| {
+        let play = play.clone();
+        let id = id();
+        async move {
+            match id {
+                Some(id) => play_correspondence(id, play).await,
+                None => Err(ServerFnError::ServerError(
+                    AppError::GameNotFound.to_string(),
+                )),
+            }
+        }
+    });
+    // The order is a convenience: when it cannot be saved the page keeps it
+    // until the next turn.
+    let arrange = create_action(move |rack: &Vec<Tile>| {
+        let rack = rack.clone();
+        let id = id();
+        async move {
+            if let Some(id) = id {
+                let _ = arrange_correspondence(id, rack).await;
+            }
+        }
+    });
+    let answer = create_action(move |accept: &bool| {
+        let accept = *accept;
+        let id = id();
+        async move {
+            match id {
+                Some(id) => answer_invitation(id, accept).await,
+                None => Err(ServerFnError::ServerError(
+                    AppError::GameNotFound.to_string(),
+                )),
+            }
+        }
+    });
+    let tick = refresh_ticker();
+    let game = create_blocking_resource(
+        move || (id(), tick(), send.version()(), answer.version()()),
+        |(id, _, _, _)| async move {
+            match id {
+                Some(id) => get_correspondence(id)
+                    .await
+                    .map_err(|error| AppError::from_server(&error)),
+                None => Err(AppError::GameNotFound),
+            }
+        },
+    );
+    let view_signal = Signal::derive(move || game.get().and_then(Result::ok));
+
+    let board = BoardStore::new();
+    let coord_signal = RwSignal::new((0, 0));
+    let rack_signal = RwSignal::new(Vec::new());
+    let exchange_signal = RwSignal::new(String::new());
+
+    // Only a new turn resets the board and rack, not every refresh.
+    let turn_key = create_memo(move |_| {
+        view_signal.with(|game| {
+            game.as_ref().map(|game| {
+                (
+                    game.id,
+                    game.history.len(),
+                    game.pending.is_some(),
+                    game.finished,
+                )
+            })
+        })
+    });
+    create_effect(move |_| {
+        turn_key.track();
+        let Some(game) = view_signal.get_untracked() else {
+            return;
+        };
+        rack_signal.set(game.rack.clone());
+        let names = game
+            .players
+            .iter()
+            .map(|seat| seat.name.clone())
+            .collect::<Vec<_>>();
+        board.show(
+            &game.grid,
+            game.pending.as_ref(),
+            &tile_origins(&game.history, &names),
+        );
+    });
+
+    let my_turn = move || {
+        view_signal.with(|game| {
+            game.as_ref()
+                .is_some_and(|game| !game.finished && !game.invited && game.waiting_on == game.seat)
+        })
+    };
+    let invited = move || {
+        view_signal.with(|game| {
+            game.as_ref()
+                .is_some_and(|game| !game.finished && game.invited && game.waiting_on == game.seat)
+        })
+    };
+    let pending =
+        move || view_signal.with(|game| game.as_ref().is_some_and(|game| game.pending.is_some()));
+
+    let on_arrange = Callback::new(move |action| {
+        rack_signal.update(|rack| arrange_tiles(rack, action));
+        arrange.dispatch(rack_signal.get_untracked());
+    });
+
+    let validate = move || {
+        let placements = board.snapshot_untracked().placed_tiles();
+        send.dispatch(Move::Play(placements));
+    };
+
+    let exchange = move || {
+        let mut rack = rack_signal.get_untracked();
+        let tiles = exchange_signal()
+            .to_uppercase()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| {
+                let letter = if c == '?' { ' ' } else { c };
+                match rack.iter().position(|tile| tile.0 == letter) {
+                    Some(idx) => rack.remove(idx),
+                    None => Tile(letter, 0),
+                }
+            })
+            .collect::<Vec<_>>();
+        exchange_signal.set(String::new());
+        send.dispatch(Move::Exchange(tiles));
+    };
+
+    let draw_result = move || match send.value()() {
+        Some(Ok(Err(error))) => Some(format!("❌ {}", error.message(language()))),
+        Some(Err(error)) => Some(format!(
+            "❌ {}",
+            AppError::from_server(&error).message(language())
+        )),
+        _ => answer.value()()
+            .and_then(Result::err)
+            .map(|error| format!("❌ {}", AppError::from_server(&error).message(language()))),
+    };
+
+    let draw_status = move || {
+        let messages = messages();
+        match game.get() {
+            Some(Ok(game)) => {
+                let players = game
+                    .players
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, seat)| {
+                        view! {
+                            <li class=("font-bold", idx == game.waiting_on && !game.finished)>
+                                {fill(messages.player_score, &[&seat.name, &seat.score.to_string()])}
+                            </li>
+                        }
+                    })
+                    .collect_view();
+                let status = if game.finished && game.invited {
+                    messages.corr_declined.to_string()
+                } else if game.finished {
+                    match game.forfeited {
+                        Some(seat) => fill(messages.corr_forfeited, &[&game.players[seat].name]),
+                        None => messages.game_over.to_string(),
+                    }
+                } else if game.invited && game.waiting_on == game.seat {
+                    fill(
+                        messages.corr_invited,
+                        &[&game.players[0].name, &format_time_left(game.time_left)],
+                    )
+                } else if game.invited {
+                    fill(
+                        messages.corr_awaiting_answer,
+                        &[&game.players[game.waiting_on].name],
+                    )
+                } else if game.waiting_on == game.seat {
+                    fill(
+                        messages.corr_your_turn,
+                        &[&format_time_left(game.time_left)],
+                    )
+                } else {
+                    fill(
+                        messages.corr_their_turn,
+                        &[&game.players[game.waiting_on].name],
+                    )
+                };
+                view! {
+                    <ul>{players}</ul>
+                    <p>{status}</p>
+                }
+                .into_view()
+            }
+            _ => view! {}.into_view(),
+        }
+    };
+
+    // A game that cannot be loaded gets an error page instead.
+    let failed = move || game.with(|game| matches!(game, Some(Err(_))));
+    let draw_error = move || {
+        game.get().and_then(Result::err).map(|error| {
+            let mut outside_errors = Errors::default();
+            outside_errors.insert_with_default_key(error);
+            view! { <ErrorTemplate outside_errors/> }
+        })
+    };
+
+    let draw_history = move || {
+        let messages = messages();
+        view_signal.with(|game| {
+            game.as_ref().map(|game| {
+                game.history
+                    .iter()
+                    .map(|turn| {
+                        let description = match &turn.action {
+                            Action::Play(play) => fill(messages.turn_play, &[&play.words_text(), &turn.score.to_string()]),
+                            Action::Withdrawn(play) => fill(messages.turn_withdrawn, &[&play.words_text()]),
+                            Action::Pass => messages.turn_pass.to_string(),
+                            Action::Exchange(count) => fill(messages.turn_exchange, &[&count.to_string()]),
+                            Action::LostTurn => messages.turn_lost.to_string(),
+                        };
+                        view! { <li>{game.players[turn.player].name.clone()}" : "{description}</li> }
+                    })
+                    .collect_view()
+            })
+        })
+    };
+
+    view! {
+        <Transition fallback=|| ()>{draw_error}</Transition>
+        <main class="container mx-auto pt-2 lg:p-5 grid grid-rows-2 lg:grid-rows-1 lg:grid-cols-2" class=("hidden", failed)>
+            <div class="flex justify-center">
+                <Board board coord_signal rack_signal/>
+            </div>
+
+            <div class="pl-5 dark:text-zinc-300">
+                <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().corr_game}</h1>
+                <div role="status" aria-live="polite">
+                    <Transition fallback=|| ()>{draw_status}</Transition>
+                    <p>{draw_result}</p>
+                </div>
+
+                <MovePreview board
+                    position=Signal::derive(move || view_signal.with(|game| {
+                        game.as_ref()
+                            .filter(|game| !game.finished && game.waiting_on == game.seat && game.pending.is_none())
+                            .map(|game| (game.grid, game.rack.clone()))
+                    }))
+                    lexicon_language=Signal::derive(move || view_signal.with(|game| game.as_ref().map(|game| game.language).unwrap_or_default()))/>
+
+                <Rack rack_signal
+                    bag_count=Signal::derive(move || view_signal.with(|game| game.as_ref().map_or(0, |game| game.bag)))
+                    unseen_signal=Signal::derive(move || view_signal.with(|game| game.as_ref().map(|game| game.unseen.clone()).unwrap_or_default()))
+                    on_arrange/>
+
+                <div class=("hidden", move || !invited())>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| answer.dispatch(true)>{move || messages().corr_accept}</button>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| answer.dispatch(false)>{move || messages().corr_decline}</button>
+                </div>
+
+                <div class=("hidden", move || !my_turn() || !pending())>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| send.dispatch(Move::Accept)>{move || messages().accept}</button>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| send.dispatch(Move::Challenge)>{move || messages().challenge}</button>
+                </div>
+
+                <div class=("hidden", move || !my_turn() || pending())>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| validate()>{move || messages().validate}</button>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| send.dispatch(Move::Pass)>{move || messages().pass}</button>
+                    <label>{move || messages().exchange_letters}
+                        <input type="text" class="w-24 p-1 text-zinc-800" maxlength="7" prop:value=exchange_signal
+                        on:input=move |ev| exchange_signal.set(event_target_value(&ev))/>
+                    </label>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                    on:click=move |_| exchange()>{move || messages().exchange}</button>
+                </div>
+
+                <h3 class="font-bold">{move || messages().history}</h3>
+                <ol>{draw_history}</ol>
+                <ChatPanel room=Signal::derive(move || id().map(ChatRoom::Correspondence))/>
+            </div>
+        </main>
+    }
+}
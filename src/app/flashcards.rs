@@ -0,0 +1,323 @@
+use super::game::*;
+use super::i18n::*;
+use super::lexicon::*;
+use super::rules::*;
+use crate::error_template::{error_codes, AppError};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+/// Hours before a card is due again, by level. Level 0 is a card never
+/// answered; a wrong answer sends a card back to level 1.
+pub const REVIEW_HOURS: [u64; 6] = [0, 0, 24, 72, 168, 720];
+/// Seconds before a missed card comes back.
+pub const RETRY_AFTER: u64 = 600;
+
+/// Bingo lengths: the whole rack, or the rack through one letter on the board.
+pub const BINGO_LENGTHS: [usize; 2] = [RACK_SIZE, RACK_SIZE + 1];
+
+/// The words hidden behind one alphagram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BingoCard {
+    /// Letters of the words in alphabetical order.
+    pub alphagram: String,
+    pub words: Vec<String>,
+    /// Chance of drawing exactly these letters from a full bag, blanks aside.
+    pub probability: f64,
+}
+
+/// What the player is shown: the words stay on the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Flashcard {
+    pub alphagram: String,
+    pub solutions: usize,
+    pub probability: f64,
+    pub level: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct FlashcardStats {
+    /// Cards at each level, from 1 up.
+    pub levels: Vec<usize>,
+    pub due: usize,
+    pub unseen: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FlashcardDeal {
+    /// `None` once every card is learned and none is due.
+    pub card: Option<Flashcard>,
+    pub stats: FlashcardStats,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FlashcardAnswer {
+    pub correct: bool,
+    pub words: Vec<String>,
+    pub missed: Vec<String>,
+    /// Typed words that are not anagrams of the card.
+    pub wrong: Vec<String>,
+    pub level: usize,
+}
+
+error_codes! {
+    /// Errors sent back by the flashcard server functions, as their `Display` code.
+    pub enum FlashcardError {
+        NotSignedIn = "not-signed-in" => flashcards_sign_in,
+        UnknownCard = "unknown-card" => flashcards_unknown_card,
+        Storage = "storage" => flashcards_storage,
+    }
+}
+
+pub fn alphagram(word: &str) -> String {
+    let mut letters = normalize_word(word).chars().collect::<Vec<_>>();
+    letters.sort_unstable();
+    letters.into_iter().collect()
+}
+
+fn choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k).fold(1.0, |ways, i| ways * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Every alphagram of `length` letters with at least one word, most likely
+/// to be drawn first. Each deck is built on first use, and is empty for a
+/// length that is not one of [`BINGO_LENGTHS`].
+pub fn bingo_deck(language: Language, length: usize) -> &'static [BingoCard] {
+    static DECKS: [[OnceLock<Vec<BingoCard>>; BINGO_LENGTHS.len()]; Language::ALL.len()] =
+        [const { [const { OnceLock::new() }; BINGO_LENGTHS.len()] }; Language::ALL.len()];
+
+    let language_idx = Language::ALL.iter().position(|listed| *listed == language);
+    let length_idx = BINGO_LENGTHS.iter().position(|listed| *listed == length);
+    match language_idx.zip(length_idx) {
+        Some((language_idx, length_idx)) => {
+            DECKS[language_idx][length_idx].get_or_init(|| build_bingo_deck(language, length))
+        }
+        None => &[],
+    }
+}
+
+fn build_bingo_deck(language: Language, length: usize) -> Vec<BingoCard> {
+    let bag = Bag::new(language).0;
+    let mut counts = HashMap::new();
+    for tile in &bag {
+        *counts.entry(tile.0).or_insert(0) += 1;
+    }
+    let draws = choose(bag.len(), length);
+
+    let mut words = BTreeMap::<String, Vec<String>>::new();
+    for word in Lexicon::for_language(language).words() {
+        if word.chars().count() == length {
+            words
+                .entry(alphagram(word))
+                .or_default()
+                .push(word.to_string());
+        }
+    }
+
+    let mut deck = words
+        .into_iter()
+        .map(|(alphagram, mut words)| {
+            words.sort();
+            let mut needed = HashMap::new();
+            for c in alphagram.chars() {
+                *needed.entry(c).or_insert(0) += 1;
+            }
+            let ways = needed
+                .iter()
+                .map(|(c, k)| choose(counts.get(c).copied().unwrap_or_default(), *k))
+                .product::<f64>();
+            BingoCard {
+                alphagram,
+                words,
+                probability: ways / draws,
+            }
+        })
+        .collect::<Vec<_>>();
+    deck.sort_by(|a, b| {
+        b.probability
+            .total_cmp(&a.probability)
+            .then(a.alphagram.cmp(&b.alphagram))
+    });
+    deck
+}
+
+#[server(prefix = "/api", endpoint = "next_flashcard")]
+pub async fn next_flashcard(
+    length: usize,
+    language: String,
+) -> Result<FlashcardDeal, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::flashcards::FlashcardStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(FlashcardError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    let language = Language::from_code(&language).unwrap_or_default();
+    let length = if BINGO_LENGTHS.contains(&length) {
+        length
+    } else {
+        BINGO_LENGTHS[0]
+    };
+    Ok(expect_context::<FlashcardStore>()
+        .deal(&user.name, language, length)
+        .await)
+}
+
+#[server(prefix = "/api", endpoint = "answer_flashcard")]
+pub async fn answer_flashcard(
+    language: String,
+    alphagram: String,
+    answer: String,
+) -> Result<FlashcardAnswer, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::flashcards::FlashcardStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(FlashcardError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    let language = Language::from_code(&language).unwrap_or_default();
+    expect_context::<FlashcardStore>()
+        .answer(&user.name, language, &alphagram, &answer)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Deals alphagrams of bingo length; the player types every anagram they
+/// know and the card comes back later the better it is known.
+#[component]
+pub fn FlashcardsPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let length = RwSignal::new(BINGO_LENGTHS[0]);
+    let draft = RwSignal::new(String::new());
+    let answer = create_action(move |(alphagram, text): &(String, String)| {
+        answer_flashcard(
+            language.get_untracked().code().to_string(),
+            alphagram.clone(),
+            text.clone(),
+        )
+    });
+    // Dealing again only after "next" keeps the answered card on screen.
+    let next = RwSignal::new(0);
+    let deal = create_resource(
+        move || (length(), language(), next()),
+        |(length, language, _)| next_flashcard(length, language.code().to_string()),
+    );
+    let card = Signal::derive(move || deal.get().and_then(Result::ok).and_then(|deal| deal.card));
+
+    let submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        if let Some(card) = card.get_untracked() {
+            answer.dispatch((card.alphagram, draft.get_untracked()));
+        }
+    };
+    let go_next = move |_| {
+        draft.set(String::new());
+        answer.value().set(None);
+        next.update(|next| *next += 1);
+    };
+
+    let draw_card = move || {
+        deal.get().map(|deal| match deal {
+            Ok(FlashcardDeal { card: Some(card), .. }) => {
+                let messages = messages();
+                let tiles = card
+                    .alphagram
+                    .chars()
+                    .map(|c| view! { <span class="inline-block w-8 p-1 m-1 text-center font-bold bg-yellow-100 text-zinc-800 rounded">{c}</span> })
+                    .collect_view();
+                view! {
+                    <div class="text-2xl" aria-label=card.alphagram.clone()>{tiles}</div>
+                    <p>{fill(messages.flashcards_solutions, &[&card.solutions.to_string()])}</p>
+                    <p class="text-sm">{fill(messages.flashcards_odds, &[&format!("{:.0}", 1.0 / card.probability.max(f64::MIN_POSITIVE))])}</p>
+                }
+                .into_view()
+            }
+            Ok(_) => view! { <p>{messages().flashcards_done}</p> }.into_view(),
+            Err(error) => view! { <p>{AppError::from_server(&error).message(language())}</p> }.into_view(),
+        })
+    };
+
+    let draw_stats = move || {
+        deal.get().and_then(Result::ok).map(|deal| {
+            let messages = messages();
+            let levels = deal
+                .stats
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(idx, count)| fill(messages.flashcards_level, &[&(idx + 1).to_string(), &count.to_string()]))
+                .collect::<Vec<_>>()
+                .join(" · ");
+            view! {
+                <p class="text-sm">{levels}</p>
+                <p class="text-sm">{fill(messages.flashcards_due, &[&deal.stats.due.to_string(), &deal.stats.unseen.to_string()])}</p>
+            }
+        })
+    };
+
+    let draw_answer = move || {
+        answer.value().get().map(|result| match result {
+            Ok(result) => {
+                let messages = messages();
+                let verdict = if result.correct {
+                    format!("✅ {}", messages.flashcards_correct)
+                } else {
+                    format!("❌ {}", messages.flashcards_incorrect)
+                };
+                view! {
+                    <p class="font-bold">{verdict}</p>
+                    <p>{fill(messages.flashcards_words, &[&result.words.join(", ")])}</p>
+                    <p class=("hidden", result.missed.is_empty())>{fill(messages.flashcards_missed, &[&result.missed.join(", ")])}</p>
+                    <p class=("hidden", result.wrong.is_empty())>{fill(messages.flashcards_wrong, &[&result.wrong.join(", ")])}</p>
+                }
+                .into_view()
+            }
+            Err(error) => view! { <p>{AppError::from_server(&error).message(language())}</p> }.into_view(),
+        })
+    };
+
+    let length_options = move || {
+        BINGO_LENGTHS
+            .into_iter()
+            .map(|option| {
+                view! {
+                    <label class="px-2">
+                        <input type="radio" name="length" prop:checked=move || length() == option
+                        on:change=move |_| {
+                            answer.value().set(None);
+                            length.set(option);
+                        }/>
+                        " "{move || fill(messages().flashcards_length, &[&option.to_string()])}
+                    </label>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().flashcards_title}</h1>
+            <p>{move || messages().flashcards_help}</p>
+            <fieldset class="my-2">{length_options}</fieldset>
+            <Transition fallback=|| ()>
+                {draw_card}
+                <form on:submit=submit class="my-3" class=("hidden", move || card().is_none())>
+                    <input type="text" class="p-1 text-zinc-800 font-mono uppercase w-80" aria-label=move || messages().flashcards_answer
+                    placeholder=move || messages().flashcards_answer prop:value=draft on:input=move |ev| draft.set(event_target_value(&ev))/>
+                    <button type="submit" class="px-2 underline" class=("hidden", move || answer.value().with(Option::is_some))>
+                        {move || messages().flashcards_check}
+                    </button>
+                    <button type="button" class="px-2 underline" class=("hidden", move || answer.value().with(Option::is_none))
+                    on:click=go_next>{move || messages().flashcards_next}</button>
+                </form>
+                <div aria-live="polite">{draw_answer}</div>
+                {draw_stats}
+            </Transition>
+        </section>
+    }
+}
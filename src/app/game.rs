@@ -1,49 +1,137 @@
+use super::account::*;
+use super::analysis::*;
 use super::board::*;
+use super::chat::*;
+use super::grid::*;
+use super::i18n::*;
+use super::leaderboard::*;
+use super::lexicon::*;
 use super::rack::*;
+use super::rules::*;
+use super::setup::*;
+use super::spectate::*;
 use leptos::*;
+use leptos_router::*;
 use rand::prelude::*;
 use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Clone, PartialEq)]
+/// A letter and its points. A blank is the tile worth none: [`Tile::BLANK`]
+/// on the rack and, once put down, the letter it stands for in lowercase,
+/// as game records write it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Tile(pub char, pub usize);
 
+impl Tile {
+    pub const BLANK: Tile = Tile(' ', 0);
+
+    /// The blank put down as `letter`, `None` when that is not a letter.
+    pub fn blank_as(letter: char) -> Option<Tile> {
+        letter
+            .is_ascii_alphabetic()
+            .then(|| Tile(letter.to_ascii_lowercase(), 0))
+    }
+
+    /// A blank, on the rack or on the board.
+    pub fn is_blank(&self) -> bool {
+        self.1 == 0
+    }
+
+    /// The letter words are spelled with, in capitals whatever the tile.
+    pub fn letter(&self) -> char {
+        self.0.to_ascii_uppercase()
+    }
+
+    /// The tile as it came out of the bag: a blank put down is a blank again.
+    pub fn drawn(&self) -> Tile {
+        if self.is_blank() {
+            Tile::BLANK
+        } else {
+            *self
+        }
+    }
+}
+
 pub struct Bag(pub Vec<Tile>);
 
 impl Bag {
-    pub fn new() -> Self {
+    pub fn new(language: Language) -> Self {
         let mut bag = Vec::new();
 
-        bag.extend((0..9).map(|_| Tile('A', 1)));
-        bag.extend((0..2).map(|_| Tile('B', 3)));
-        bag.extend((0..2).map(|_| Tile('C', 3)));
-        bag.extend((0..3).map(|_| Tile('D', 2)));
-        bag.extend((0..15).map(|_| Tile('E', 1)));
-        bag.extend((0..2).map(|_| Tile('F', 4)));
-        bag.extend((0..2).map(|_| Tile('G', 2)));
-        bag.extend((0..2).map(|_| Tile('H', 4)));
-        bag.extend((0..8).map(|_| Tile('I', 1)));
-        bag.extend((0..1).map(|_| Tile('J', 8)));
-        bag.extend((0..1).map(|_| Tile('K', 10)));
-        bag.extend((0..5).map(|_| Tile('L', 1)));
-        bag.extend((0..3).map(|_| Tile('M', 2)));
-        bag.extend((0..6).map(|_| Tile('N', 1)));
-        bag.extend((0..6).map(|_| Tile('O', 1)));
-        bag.extend((0..2).map(|_| Tile('P', 3)));
-        bag.extend((0..1).map(|_| Tile('Q', 8)));
-        bag.extend((0..6).map(|_| Tile('R', 1)));
-        bag.extend((0..6).map(|_| Tile('S', 1)));
-        bag.extend((0..6).map(|_| Tile('T', 1)));
-        bag.extend((0..6).map(|_| Tile('U', 1)));
-        bag.extend((0..2).map(|_| Tile('V', 4)));
-        bag.extend((0..1).map(|_| Tile('W', 10)));
-        bag.extend((0..1).map(|_| Tile('X', 10)));
-        bag.extend((0..1).map(|_| Tile('Y', 10)));
-        bag.extend((0..1).map(|_| Tile('Z', 10)));
-        bag.extend((0..2).map(|_| Tile(' ', 0)));
+        match language {
+            Language::French => {
+                bag.extend((0..9).map(|_| Tile('A', 1)));
+                bag.extend((0..2).map(|_| Tile('B', 3)));
+                bag.extend((0..2).map(|_| Tile('C', 3)));
+                bag.extend((0..3).map(|_| Tile('D', 2)));
+                bag.extend((0..15).map(|_| Tile('E', 1)));
+                bag.extend((0..2).map(|_| Tile('F', 4)));
+                bag.extend((0..2).map(|_| Tile('G', 2)));
+                bag.extend((0..2).map(|_| Tile('H', 4)));
+                bag.extend((0..8).map(|_| Tile('I', 1)));
+                bag.extend((0..1).map(|_| Tile('J', 8)));
+                bag.extend((0..1).map(|_| Tile('K', 10)));
+                bag.extend((0..5).map(|_| Tile('L', 1)));
+                bag.extend((0..3).map(|_| Tile('M', 2)));
+                bag.extend((0..6).map(|_| Tile('N', 1)));
+                bag.extend((0..6).map(|_| Tile('O', 1)));
+                bag.extend((0..2).map(|_| Tile('P', 3)));
+                bag.extend((0..1).map(|_| Tile('Q', 8)));
+                bag.extend((0..6).map(|_| Tile('R', 1)));
+                bag.extend((0..6).map(|_| Tile('S', 1)));
+                bag.extend((0..6).map(|_| Tile('T', 1)));
+                bag.extend((0..6).map(|_| Tile('U', 1)));
+                bag.extend((0..2).map(|_| Tile('V', 4)));
+                bag.extend((0..1).map(|_| Tile('W', 10)));
+                bag.extend((0..1).map(|_| Tile('X', 10)));
+                bag.extend((0..1).map(|_| Tile('Y', 10)));
+                bag.extend((0..1).map(|_| Tile('Z', 10)));
+            }
+            Language::English => {
+                bag.extend((0..9).map(|_| Tile('A', 1)));
+                bag.extend((0..2).map(|_| Tile('B', 3)));
+                bag.extend((0..2).map(|_| Tile('C', 3)));
+                bag.extend((0..4).map(|_| Tile('D', 2)));
+                bag.extend((0..12).map(|_| Tile('E', 1)));
+                bag.extend((0..2).map(|_| Tile('F', 4)));
+                bag.extend((0..3).map(|_| Tile('G', 2)));
+                bag.extend((0..2).map(|_| Tile('H', 4)));
+                bag.extend((0..9).map(|_| Tile('I', 1)));
+                bag.extend((0..1).map(|_| Tile('J', 8)));
+                bag.extend((0..1).map(|_| Tile('K', 5)));
+                bag.extend((0..4).map(|_| Tile('L', 1)));
+                bag.extend((0..2).map(|_| Tile('M', 3)));
+                bag.extend((0..6).map(|_| Tile('N', 1)));
+                bag.extend((0..8).map(|_| Tile('O', 1)));
+                bag.extend((0..2).map(|_| Tile('P', 3)));
+                bag.extend((0..1).map(|_| Tile('Q', 10)));
+                bag.extend((0..6).map(|_| Tile('R', 1)));
+                bag.extend((0..4).map(|_| Tile('S', 1)));
+                bag.extend((0..6).map(|_| Tile('T', 1)));
+                bag.extend((0..4).map(|_| Tile('U', 1)));
+                bag.extend((0..2).map(|_| Tile('V', 4)));
+                bag.extend((0..2).map(|_| Tile('W', 4)));
+                bag.extend((0..1).map(|_| Tile('X', 8)));
+                bag.extend((0..2).map(|_| Tile('Y', 4)));
+                bag.extend((0..1).map(|_| Tile('Z', 10)));
+            }
+        }
+
+        bag.extend((0..2).map(|_| Tile::BLANK));
 
         Self(bag)
     }
 
+    /// Points of each letter in the tile set of `language`; the blank is `' '`.
+    pub fn values(language: Language) -> HashMap<char, usize> {
+        Self::new(language)
+            .0
+            .into_iter()
+            .map(|tile| (tile.0, tile.1))
+            .collect()
+    }
+
     pub fn draw_tiles(amount: u8, bag: &mut Vec<Tile>) -> Vec<Tile> {
         let mut rack = Vec::new();
         let mut rng = thread_rng();
@@ -60,153 +148,1131 @@ impl Bag {
     }
 }
 
-#[component]
-pub fn Game() -> impl IntoView {
-    let empty_board = Vec::from([Cell::new((0, 0))]);
-    let mut bag = Bag::new().0;
-
-    let board_signal = RwSignal::new(empty_board);
-    let valid_signal = RwSignal::new(false);
-    let played_signal = RwSignal::new(false);
-    let score_signal = RwSignal::new(0);
-    let word_signal = RwSignal::new(String::from(""));
-    let in_rack_signal = RwSignal::new(false);
-    let adjacent_signal = RwSignal::new(false);
-    let scrabble_signal = RwSignal::new(false);
-    let coord_signal = RwSignal::new((0, 0));
-    let coord_x_signal = move || coord_signal.with(|coord| coord.0);
-    let coord_y_signal = move || coord_signal.with(|coord| coord.1);
-    let bag_signal = RwSignal::new(bag.to_vec());
-    let rack_signal = RwSignal::new(Vec::new());
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Player {
+    pub name: String,
+    pub score: usize,
+    pub rack: Vec<Tile>,
+    /// Set after a wrong double challenge: the next turn is skipped.
+    pub loses_turn: bool,
+    /// Name of the signed-in account playing this seat, if any.
+    pub account: Option<String>,
+}
 
-    let mut pick_tiles = move || {
-        // let vec_size: u8 = rack_signal.with(|vec| vec.len() as u8);
-        // amount.set(7 - vec_size);
-        rack_signal.set(Bag::draw_tiles(7, &mut bag));
-        bag_signal.set(bag.to_vec())
-    };
+impl Player {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            score: 0,
+            rack: Vec::new(),
+            loses_turn: false,
+            account: None,
+        }
+    }
+
+    pub fn arrange_rack(&mut self, action: RackAction) {
+        arrange_tiles(&mut self.rack, action);
+    }
+}
+
+pub const VOWELS: &str = "AEIOUY";
+
+pub fn arrange_tiles(rack: &mut Vec<Tile>, action: RackAction) {
+    match action {
+        RackAction::Shuffle => rack.shuffle(&mut thread_rng()),
+        RackAction::Alphabetical => rack.sort_by_key(|tile| (tile.is_blank(), tile.0)),
+        RackAction::VowelsFirst => {
+            rack.sort_by_key(|tile| (tile.is_blank(), !VOWELS.contains(tile.0), tile.0))
+        }
+        RackAction::Move(from, to) => {
+            if from < rack.len() && to < rack.len() {
+                let tile = rack.remove(from);
+                rack.insert(to, tile);
+            }
+        }
+    }
+}
+
+/// Reordering requested from the rack, blanks always go last when sorting.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RackAction {
+    Shuffle,
+    Alphabetical,
+    VowelsFirst,
+    /// Moves the tile at the first index to the second one.
+    Move(usize, usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Play(Play),
+    /// The play was challenged and contained an unknown word.
+    Withdrawn(Play),
+    Pass,
+    /// Number of tiles swapped with the bag.
+    Exchange(usize),
+    /// Turn skipped after a wrong double challenge.
+    LostTurn,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    pub player: usize,
+    pub action: Action,
+    pub score: usize,
+    pub challenged_by: Option<usize>,
+    /// Rack held before the turn, empty in games recorded without it.
+    #[serde(default)]
+    pub rack: Vec<Tile>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeatResult {
+    pub name: String,
+    pub account: Option<String>,
+    pub score: usize,
+}
+
+/// A finished game as it is stored on the server; `Turn::player` indexes `seats`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Assigned by the server when the game is recorded.
+    pub id: u64,
+    /// Seconds since the Unix epoch, set by the server.
+    pub finished_at: u64,
+    pub language: Language,
+    pub seats: Vec<SeatResult>,
+    pub history: Vec<Turn>,
+    /// Filled in by the server from the live game.
+    #[serde(default)]
+    pub chat: Vec<ChatMessage>,
+    /// Seat that ran out of time.
+    #[serde(default)]
+    pub forfeited: Option<usize>,
+    /// Whether it counted in the ratings, which only games played on the
+    /// server do.
+    #[serde(default)]
+    pub rated: bool,
+    /// How a local game was set up; other games have none.
+    #[serde(default)]
+    pub config: Option<GameConfig>,
+}
 
-    fn are_adjacent(coords: &[(usize, usize)]) -> bool {
-        let mut sorted_coords = coords.to_vec();
-        sorted_coords.sort();
+impl GameRecord {
+    pub fn layout(&self) -> BoardLayout {
+        self.config
+            .as_ref()
+            .map_or(BoardLayout::Standard, |config| config.layout)
+    }
 
-        for i in 0..sorted_coords.len() - 1 {
-            let (row1, col1) = sorted_coords[i];
-            let (row2, col2) = sorted_coords[i + 1];
+    /// Language of the words allowed in the game.
+    pub fn lexicon_language(&self) -> Language {
+        self.config
+            .as_ref()
+            .map_or(self.language, |config| config.lexicon)
+    }
 
-            if row1 == row2 && (col1 + 1 == col2 || col1 == col2 + 1) {
-                continue; // Adjacent horizontally
-            } else if col1 == col2 && (row1 + 1 == row2 || row1 == row2 + 1) {
-                continue; // Adjacent vertically
+    /// Result of `seat` against `other`: 1 for a win, 0.5 for a draw, 0 for a loss.
+    pub fn outcome(&self, seat: usize, other: usize) -> f64 {
+        match self.forfeited {
+            Some(loser) if loser == seat => 0.0,
+            Some(loser) if loser == other => 1.0,
+            _ => match self.seats[seat].score.cmp(&self.seats[other].score) {
+                std::cmp::Ordering::Greater => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Less => 0.0,
+            },
+        }
+    }
+}
+
+/// Consecutive scoreless turns that end the game.
+const SCORELESS_TURNS: usize = 6;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameState {
+    pub players: Vec<Player>,
+    pub current: usize,
+    pub bag: Vec<Tile>,
+    pub grid: Grid,
+    pub history: Vec<Turn>,
+    /// A play waiting for the opponent to accept or challenge it.
+    pub pending: Option<Play>,
+    pub rule: ChallengeRule,
+    /// Decides the tile distribution.
+    pub language: Language,
+    pub finished: bool,
+    /// How the game was set up; games saved before setups existed have none.
+    #[serde(default)]
+    pub config: Option<GameConfig>,
+    /// Seconds left to each seat, empty without a time control.
+    #[serde(default)]
+    pub clocks: Vec<u64>,
+    /// Seat that ran out of time.
+    #[serde(default)]
+    pub forfeited: Option<usize>,
+}
+
+impl GameState {
+    /// Human players on the standard board, without a clock.
+    pub fn new(names: &[&str], rule: ChallengeRule, language: Language) -> Self {
+        Self::from_config(&GameConfig {
+            seats: names
+                .iter()
+                .map(|name| SeatConfig {
+                    name: name.to_string(),
+                    kind: SeatKind::Human,
+                })
+                .collect(),
+            rule,
+            ..GameConfig::new(language)
+        })
+    }
+
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self {
+            players: config
+                .seats
+                .iter()
+                .map(|seat| Player::new(&seat.name))
+                .collect(),
+            current: 0,
+            bag: Bag::new(config.language).0,
+            grid: Grid::with_layout(config.layout),
+            history: Vec::new(),
+            pending: None,
+            rule: config.rule,
+            language: config.language,
+            finished: false,
+            config: Some(config.clone()),
+            clocks: config
+                .time
+                .seconds()
+                .map(|seconds| vec![seconds; config.seats.len()])
+                .unwrap_or_default(),
+            forfeited: None,
+        }
+    }
+
+    pub fn seat_kind(&self, seat: usize) -> SeatKind {
+        self.config
+            .as_ref()
+            .and_then(|config| config.seats.get(seat))
+            .map_or(SeatKind::Human, |seat| seat.kind)
+    }
+
+    /// Language of the words allowed, the one of the tiles for games saved
+    /// without a setup.
+    pub fn lexicon_language(&self) -> Language {
+        self.config
+            .as_ref()
+            .map_or(self.language, |config| config.lexicon)
+    }
+
+    /// Nothing has been drawn or played yet.
+    pub fn is_fresh(&self) -> bool {
+        self.history.is_empty() && self.players.iter().all(|player| player.rack.is_empty())
+    }
+
+    pub fn seat_account(&mut self, seat: usize, user: Option<User>) {
+        if let (Some(player), Some(user)) = (self.players.get_mut(seat), user) {
+            player.name = user.name.clone();
+            player.account = Some(user.name);
+        }
+    }
+
+    /// Gives the seat back to a guest called `name`.
+    pub fn seat_guest(&mut self, seat: usize, name: &str) {
+        if let Some(player) = self.players.get_mut(seat) {
+            player.name = name.to_string();
+            player.account = None;
+        }
+    }
+
+    /// Seats with the highest score once the game is over, leaving out
+    /// the one that ran out of time.
+    pub fn winners(&self) -> Vec<&Player> {
+        let contenders = || {
+            self.players
+                .iter()
+                .enumerate()
+                .filter(|(seat, _)| Some(*seat) != self.forfeited)
+                .map(|(_, player)| player)
+        };
+        let best = contenders().map(|player| player.score).max();
+        contenders()
+            .filter(|player| Some(player.score) == best)
+            .collect()
+    }
+
+    /// A clock runs once the first tiles are drawn and until the game ends.
+    pub fn clock_running(&self) -> bool {
+        !self.clocks.is_empty() && !self.finished && !self.is_fresh()
+    }
+
+    /// Takes `seconds` off the clock of the seat expected to act, which
+    /// loses the game when its time is up.
+    pub fn tick(&mut self, seconds: u64) {
+        if !self.clock_running() {
+            return;
+        }
+        let seat = self.waiting_on();
+        if let Some(left) = self.clocks.get_mut(seat) {
+            *left = left.saturating_sub(seconds);
+            if *left == 0 {
+                self.pending = None;
+                self.forfeited = Some(seat);
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Moves for a computer seat expected to act: a pending play is only
+    /// challenged when it holds an unknown word, otherwise the best scoring
+    /// move is played, the whole rack exchanged or the turn passed.
+    pub fn play_computer(&mut self, generator: &MoveGenerator) {
+        if self.finished || self.seat_kind(self.waiting_on()) != SeatKind::Computer {
+            return;
+        }
+        if let Some(play) = &self.pending {
+            if play.unknown_words(generator.lexicon()).is_empty() {
+                self.accept();
             } else {
-                return false;
+                self.challenge(generator.lexicon());
             }
+            return;
+        }
+        self.refill_rack();
+        let rack = self.current_player().rack.clone();
+        let best = generator.moves(&self.grid, &rack).into_iter().next();
+        let played =
+            best.is_some_and(|play| self.submit(&play.placements, generator.lexicon()).is_ok());
+        if !played && self.exchange(&rack).is_err() {
+            self.pass();
         }
-        true
     }
 
-    let validate = move || {
-        let mut word = String::from("");
-        let mut cells_coords = Vec::new();
-        let mut in_rack = true;
-        let mut scrabble = false;
-        let mut rack_letters = rack_signal()
-            .into_iter()
-            .map(|tile| tile.0)
-            .collect::<Vec<_>>();
-        for cell in board_signal() {
-            if (cell.letter_score)().1 != 9 {
-                word.push((cell.letter_score)().0);
-                cells_coords.push(cell.coord);
-                if let Some(idx) = rack_letters
-                    .iter()
-                    .position(|l| *l == (cell.letter_score)().0)
-                {
-                    rack_letters.remove(idx);
-                } else {
-                    in_rack = false;
+    pub fn current_player(&self) -> &Player {
+        &self.players[self.current]
+    }
+
+    /// The opponent allowed to challenge a pending play.
+    pub fn challenger(&self) -> usize {
+        (self.current + 1) % self.players.len()
+    }
+
+    /// Seat expected to act: the challenger while a play is pending.
+    pub fn waiting_on(&self) -> usize {
+        if self.pending.is_some() {
+            self.challenger()
+        } else {
+            self.current
+        }
+    }
+
+    pub fn arrange_rack(&mut self, action: RackAction) {
+        self.players[self.current].arrange_rack(action);
+    }
+
+    /// The human seat whose rack is on screen: the current one, or the last
+    /// human to play while a computer seat moves. `None` without one.
+    pub fn shown_seat(&self) -> Option<usize> {
+        let seats = self.players.len();
+        (0..seats)
+            .map(|back| (self.current + seats - back) % seats)
+            .find(|seat| self.seat_kind(*seat) == SeatKind::Human)
+    }
+
+    /// Tiles the seat on screen cannot see: the bag plus the opponents'
+    /// racks. A computer's rack never counts as seen.
+    pub fn unseen_tiles(&self) -> Vec<Tile> {
+        self.shown_seat()
+            .map_or_else(Vec::new, |seat| self.unseen_tiles_for(seat))
+    }
+
+    pub fn unseen_tiles_for(&self, seat: usize) -> Vec<Tile> {
+        let mut unseen = Bag::new(self.language).0;
+        let on_board = (1..=BOARD_SIZE)
+            .flat_map(|row| (1..=BOARD_SIZE).map(move |col| (row, col)))
+            .filter_map(|coord| self.grid.get(coord))
+            .map(|tile| tile.drawn());
+        for tile in on_board.chain(self.players[seat].rack.iter().copied()) {
+            if let Some(idx) = unseen.iter().position(|t| *t == tile) {
+                unseen.remove(idx);
+            }
+        }
+        unseen
+    }
+
+    pub fn refill_rack(&mut self) {
+        let player = &mut self.players[self.current];
+        let missing = RACK_SIZE.saturating_sub(player.rack.len()) as u8;
+        player.rack.extend(Bag::draw_tiles(missing, &mut self.bag));
+    }
+
+    pub fn submit(
+        &mut self,
+        placements: &[((usize, usize), Tile)],
+        lexicon: &Lexicon,
+    ) -> Result<Play, PlayError> {
+        if self.finished {
+            return Err(PlayError::GameOver);
+        }
+        let play = evaluate(&self.grid, placements, &self.current_player().rack)?;
+        if self.rule.allows_challenge() {
+            self.pending = Some(play.clone());
+        } else {
+            let unknown = play.unknown_words(lexicon);
+            if !unknown.is_empty() {
+                return Err(PlayError::UnknownWords(unknown));
+            }
+            self.commit(play.clone(), None);
+        }
+        Ok(play)
+    }
+
+    pub fn accept(&mut self) {
+        if let Some(play) = self.pending.take() {
+            self.commit(play, None);
+        }
+    }
+
+    /// Returns `true` when the challenge is upheld and the play withdrawn.
+    pub fn challenge(&mut self, lexicon: &Lexicon) -> bool {
+        let Some(play) = self.pending.take() else {
+            return false;
+        };
+        let challenger = self.challenger();
+        if !play.unknown_words(lexicon).is_empty() {
+            self.end_turn(Turn {
+                player: self.current,
+                action: Action::Withdrawn(play),
+                score: 0,
+                challenged_by: Some(challenger),
+                rack: self.current_player().rack.clone(),
+            });
+            return true;
+        }
+        match self.rule {
+            ChallengeRule::Double => self.players[challenger].loses_turn = true,
+            ChallengeRule::Single => self.players[self.current].score += WRONG_CHALLENGE_BONUS,
+            ChallengeRule::Void => (),
+        }
+        self.commit(play, Some(challenger));
+        false
+    }
+
+    pub fn pass(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.pending = None;
+        self.end_turn(Turn {
+            player: self.current,
+            action: Action::Pass,
+            score: 0,
+            challenged_by: None,
+            rack: self.current_player().rack.clone(),
+        });
+    }
+
+    /// Puts `tiles` back in the bag after drawing their replacements; the bag
+    /// must still hold a full rack.
+    pub fn exchange(&mut self, tiles: &[Tile]) -> Result<(), PlayError> {
+        if self.finished {
+            return Err(PlayError::GameOver);
+        }
+        if tiles.is_empty() {
+            return Err(PlayError::NoTiles);
+        }
+        if self.bag.len() < RACK_SIZE {
+            return Err(PlayError::BagTooSmall);
+        }
+        let held = self.current_player().rack.clone();
+        let mut rack = held.clone();
+        for tile in tiles {
+            match rack.iter().position(|t| t == tile) {
+                Some(idx) => {
+                    rack.remove(idx);
                 }
+                None => return Err(PlayError::NotInRack),
             }
         }
-        if rack_letters.is_empty() {
-            scrabble = true
+        rack.extend(Bag::draw_tiles(tiles.len() as u8, &mut self.bag));
+        self.bag.extend_from_slice(tiles);
+        self.players[self.current].rack = rack;
+        self.pending = None;
+        self.end_turn(Turn {
+            player: self.current,
+            action: Action::Exchange(tiles.len()),
+            score: 0,
+            challenged_by: None,
+            rack: held,
+        });
+        Ok(())
+    }
+
+    fn commit(&mut self, play: Play, challenged_by: Option<usize>) {
+        let player = &mut self.players[self.current];
+        let held = player.rack.clone();
+        for (coord, tile) in &play.placements {
+            self.grid.set(*coord, Some(*tile));
+            if let Some(idx) = player.rack.iter().position(|t| *t == tile.drawn()) {
+                player.rack.remove(idx);
+            }
+        }
+        player.score += play.score;
+        let score = match (self.rule, challenged_by) {
+            (ChallengeRule::Single, Some(_)) => play.score + WRONG_CHALLENGE_BONUS,
+            _ => play.score,
         };
-        word_signal.set(word);
-        in_rack_signal.set(in_rack);
+        self.refill_rack();
+        self.end_turn(Turn {
+            player: self.current,
+            action: Action::Play(play),
+            score,
+            challenged_by,
+            rack: held,
+        });
+    }
+
+    fn end_turn(&mut self, turn: Turn) {
+        self.history.push(turn);
+        self.current = (self.current + 1) % self.players.len();
+        while self.players[self.current].loses_turn {
+            self.players[self.current].loses_turn = false;
+            self.history.push(Turn {
+                player: self.current,
+                action: Action::LostTurn,
+                score: 0,
+                challenged_by: None,
+                rack: self.players[self.current].rack.clone(),
+            });
+            self.current = (self.current + 1) % self.players.len();
+        }
+        self.check_end();
+    }
 
-        let adjacent = are_adjacent(&cells_coords);
-        if adjacent {
-            adjacent_signal.set(true);
+    /// Ends the game once the bag is empty and a rack went out, or after six
+    /// scoreless turns; unplayed tiles are deducted and go to whoever went out.
+    fn check_end(&mut self) {
+        let went_out = if self.bag.is_empty() {
+            self.players
+                .iter()
+                .position(|player| player.rack.is_empty())
         } else {
-            adjacent_signal.set(false);
+            None
+        };
+        let scoreless = self.history.len() >= SCORELESS_TURNS
+            && self
+                .history
+                .iter()
+                .rev()
+                .take(SCORELESS_TURNS)
+                .all(|turn| turn.score == 0);
+        if went_out.is_none() && !scoreless {
+            return;
         }
 
-        let mut score = 0;
-        for cell in board_signal() {
-            if (cell.letter_score)().1 != 9 {
-                match cell.cell_kind {
-                    CellKind::DoubleLetter => score += (cell.letter_score)().1 * 2,
-                    CellKind::TripleLetter => score += (cell.letter_score)().1 * 3,
-                    _ => score += (cell.letter_score)().1,
-                }
+        let mut leftovers = 0;
+        for player in &mut self.players {
+            let left = player.rack.iter().map(|tile| tile.1).sum::<usize>();
+            player.score = player.score.saturating_sub(left);
+            leftovers += left;
+        }
+        if let Some(out) = went_out {
+            self.players[out].score += leftovers;
+        }
+        self.finished = true;
+    }
+
+    pub fn record(&self) -> GameRecord {
+        GameRecord {
+            id: 0,
+            finished_at: 0,
+            language: self.language,
+            seats: self
+                .players
+                .iter()
+                .map(|player| SeatResult {
+                    name: player.name.clone(),
+                    account: player.account.clone(),
+                    score: player.score,
+                })
+                .collect(),
+            history: self.history.clone(),
+            chat: Vec::new(),
+            forfeited: self.forfeited,
+            rated: false,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Pause before a computer seat moves, so that the previous move can be seen.
+const COMPUTER_DELAY: u64 = 800;
+
+/// Minutes and seconds left on a clock.
+fn format_clock(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// A local game, every human seat playing on this device.
+#[component]
+pub fn Game(config: GameConfig) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let current_user = use_current_user();
+    let lexicon = Lexicon::for_language(config.lexicon);
+    let generator = store_value(
+        config
+            .has_computer()
+            .then(|| MoveGenerator::new(Lexicon::for_language(config.lexicon))),
+    );
+
+    let board = BoardStore::new();
+    let state_signal = RwSignal::new({
+        let mut state = GameState::from_config(&config);
+        if config.seats[0].kind == SeatKind::Human {
+            state.seat_account(0, current_user.get_untracked());
+        }
+        state
+    });
+    let result_signal = RwSignal::new(None::<Result<Play, PlayError>>);
+    let coord_signal = RwSignal::new((0, 0));
+    let coord_x_signal = move || coord_signal.with(|coord| coord.0);
+    let coord_y_signal = move || coord_signal.with(|coord| coord.1);
+    let bag_count = Signal::derive(move || state_signal.with(|state| state.bag.len()));
+    let rack_signal = RwSignal::new(Vec::new());
+    let unseen_signal = create_memo(move |_| state_signal.with(|state| state.unseen_tiles()));
+    let pending_signal = move || state_signal.with(|state| state.pending.is_some());
+    let started_signal = move || state_signal.with(|state| !state.history.is_empty());
+    let finished_signal = move || state_signal.with(|state| state.finished);
+    let live_ticket = RwSignal::new(None::<LiveTicket>);
+    let record_action = create_action(move |record: &GameRecord| {
+        record_game(record.clone(), live_ticket.get_untracked())
+    });
+    let publish_action = create_action(move |snapshot: &GameSnapshot| {
+        let snapshot = snapshot.clone();
+        async move {
+            if let Ok(ticket) = publish_game(live_ticket.get_untracked(), snapshot).await {
+                live_ticket.set(Some(ticket));
             }
         }
-        for cell in board_signal() {
-            if (cell.letter_score)().1 != 9 {
-                match cell.cell_kind {
-                    CellKind::DoubleWord => score *= 2,
-                    CellKind::TripleWord => score *= 3,
-                    _ => (),
+    });
+
+    // Copies the game state back into the rack and the board cells; the
+    // rack of a computer seat is not shown.
+    let sync = move || {
+        state_signal.with_untracked(|state| {
+            let rack = match state.seat_kind(state.current) {
+                SeatKind::Human => state.current_player().rack.clone(),
+                SeatKind::Computer => Vec::new(),
+            };
+            rack_signal.set(rack);
+            let names = state
+                .players
+                .iter()
+                .map(|player| player.name.clone())
+                .collect::<Vec<_>>();
+            board.show(
+                &state.grid,
+                state.pending.as_ref(),
+                &tile_origins(&state.history, &names),
+            );
+        })
+    };
+
+    // The signed-in user takes the first seat of a game that has not started yet.
+    create_effect(move |_| {
+        let user = current_user();
+        state_signal.update(|state| {
+            if state.is_fresh() && state.seat_kind(0) == SeatKind::Human {
+                state.seat_account(0, user);
+            }
+        });
+    });
+
+    // Computer seats move on their own whenever a turn ends on them.
+    let turn_key = create_memo(move |_| {
+        state_signal.with(|state| {
+            (
+                state.history.len(),
+                state.pending.is_some(),
+                state.finished,
+                state.waiting_on(),
+            )
+        })
+    });
+    create_effect(move |_| {
+        turn_key.track();
+        let computer = state_signal.with_untracked(|state| {
+            !state.finished && state.seat_kind(state.waiting_on()) == SeatKind::Computer
+        });
+        if computer {
+            set_timeout(
+                move || {
+                    let moved = generator.try_with_value(|generator| {
+                        generator.as_ref().and_then(|generator| {
+                            state_signal.try_update(|state| state.play_computer(generator))
+                        })
+                    });
+                    if moved.flatten().is_some() {
+                        result_signal.set(None);
+                        sync()
+                    }
+                },
+                std::time::Duration::from_millis(COMPUTER_DELAY),
+            );
+        }
+    });
+
+    // Clocks only run in the browser, counting down once a second.
+    #[cfg(feature = "hydrate")]
+    if config.time != TimeControl::Unlimited {
+        let interval = set_interval_with_handle(
+            move || {
+                if state_signal.with_untracked(GameState::clock_running) {
+                    state_signal.update(|state| state.tick(1));
                 }
+            },
+            std::time::Duration::from_secs(1),
+        );
+        if let Ok(interval) = interval {
+            on_cleanup(move || interval.clear());
+        }
+    }
+
+    // A finished game is reported once by a signed-in player to update the ratings.
+    create_effect(move |recorded: Option<bool>| {
+        if recorded == Some(true) {
+            return true;
+        }
+        let user = current_user();
+        let record = state_signal.with(|state| {
+            let seated = state.players.iter().any(|player| {
+                player.account.is_some()
+                    && player.account == user.as_ref().map(|user| user.name.clone())
+            });
+            (state.finished && seated).then(|| state.record())
+        });
+        match record {
+            Some(record) => {
+                record_action.dispatch(record);
+                true
             }
+            None => false,
         }
-        if scrabble {
-            score += 50
+    });
+
+    // Every committed move is published for the spectators once the game has
+    // started and someone is signed in. Moves made while a publication is
+    // on its way go out as one once it is back.
+    create_effect(move |published: Option<usize>| {
+        let turns = state_signal.with(|state| state.history.len());
+        let in_flight = publish_action.pending().get();
+        let signed_in = current_user.with_untracked(Option::is_some);
+        if turns > 0 && published != Some(turns) && !in_flight && signed_in {
+            publish_action.dispatch(state_signal.with_untracked(|state| GameSnapshot::from(state)));
+            return turns;
         }
-        score_signal.set(score);
-        scrabble_signal.set(scrabble);
+        published.unwrap_or_default()
+    });
 
-        if in_rack && adjacent {
-            valid_signal.set(true);
-        } else {
-            valid_signal.set(false);
+    let pick_tiles = move || {
+        state_signal.update(|state| state.refill_rack());
+        sync()
+    };
+
+    let validate = move || {
+        let placements = board.snapshot_untracked().placed_tiles();
+        let mut result = None;
+        state_signal.update(|state| result = Some(state.submit(&placements, lexicon)));
+        if matches!(result, Some(Ok(_))) {
+            sync()
+        }
+        result_signal.set(result);
+    };
+
+    let accept = move || {
+        state_signal.update(|state| state.accept());
+        sync()
+    };
+
+    let challenge = move || {
+        state_signal.update(|state| {
+            state.challenge(lexicon);
+        });
+        sync()
+    };
+
+    let exchange_signal = RwSignal::new(String::new());
+    let exchange = move || {
+        let result = state_signal.try_update(|state| {
+            let mut rack = state.current_player().rack.clone();
+            let tiles = exchange_signal()
+                .to_uppercase()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| {
+                    let letter = if c == '?' { ' ' } else { c };
+                    match rack.iter().position(|tile| tile.0 == letter) {
+                        Some(idx) => rack.remove(idx),
+                        None => Tile(letter, 0),
+                    }
+                })
+                .collect::<Vec<_>>();
+            state.exchange(&tiles)
+        });
+        match result {
+            Some(Err(error)) => result_signal.set(Some(Err(error))),
+            _ => {
+                exchange_signal.set(String::new());
+                result_signal.set(None);
+                sync()
+            }
+        }
+    };
+
+    let pass = move || {
+        state_signal.update(|state| state.pass());
+        result_signal.set(None);
+        sync()
+    };
+
+    let draw_result = move || match result_signal() {
+        Some(Ok(play)) => view! {
+            <p>{fill(messages().word_valid, &[&play.words_text()])}</p>
+            <p>{fill(messages().your_score, &[&play.score.to_string()])}</p>
+            <p class=("hidden", !play.bingo)>{messages().bingo}</p>
+        }
+        .into_view(),
+        Some(Err(error)) => view! {
+            <p>"❌ "{error.message(language())}</p>
+        }
+        .into_view(),
+        None => view! {}.into_view(),
+    };
+
+    let draw_players = move || {
+        state_signal.with(|state| {
+            state
+                .players
+                .iter()
+                .enumerate()
+                .map(|(idx, player)| {
+                    let clock = state.clocks.get(idx).map(|left| format!(" — {}", format_clock(*left)));
+                    view! {
+                        <li class=("font-bold", idx == state.current)>
+                            {fill(messages().player_score, &[&player.name, &player.score.to_string()])}{clock}
+                        </li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let draw_history = move || {
+        let messages = messages();
+        state_signal.with(|state| {
+            state
+                .history
+                .iter()
+                .map(|turn| {
+                    let name = state.players[turn.player].name.clone();
+                    let description = match &turn.action {
+                        Action::Play(play) => fill(
+                            messages.turn_play,
+                            &[&play.words_text(), &turn.score.to_string()],
+                        ),
+                        Action::Withdrawn(play) => {
+                            fill(messages.turn_withdrawn, &[&play.words_text()])
+                        }
+                        Action::Pass => messages.turn_pass.to_string(),
+                        Action::Exchange(count) => {
+                            fill(messages.turn_exchange, &[&count.to_string()])
+                        }
+                        Action::LostTurn => messages.turn_lost.to_string(),
+                    };
+                    let challenge = match (&turn.action, turn.challenged_by) {
+                        (Action::Play(_), Some(by)) => {
+                            fill(messages.challenge_rejected, &[&state.players[by].name])
+                        }
+                        (Action::Withdrawn(_), Some(by)) => {
+                            fill(messages.challenged_by, &[&state.players[by].name])
+                        }
+                        _ => String::new(),
+                    };
+                    view! {
+                        <li>{name}" : "{description}{challenge}</li>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let draw_game_over = move || {
+        if !finished_signal() {
+            return view! {}.into_view();
         }
+        let winners = state_signal.with(|state| {
+            state
+                .winners()
+                .iter()
+                .map(|player| player.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        let recorded = record_action.value()().map(|result| match result {
+            Ok(record) => view! {
+                {messages().game_recorded}" "
+                <A href=format!("/review/{}", record.id) class="underline">{messages().review_link}</A>
+            }
+            .into_view(),
+            Err(_) => messages().game_not_recorded.into_view(),
+        });
+        let time_out = state_signal.with(|state| {
+            state
+                .forfeited
+                .map(|seat| fill(messages().time_out, &[&state.players[seat].name]))
+        });
+        view! {
+            <p class="font-bold">{messages().game_over}" "{time_out}" "{fill(messages().winner, &[&winners])}</p>
+            <p>{recorded}</p>
+        }
+        .into_view()
+    };
+
+    let challenger_name =
+        move || state_signal.with(|state| state.players[state.challenger()].name.clone());
 
-        played_signal.set(true);
+    let rule = config.rule;
+    let computer_opponent = config.seats[1].kind == SeatKind::Computer;
+    let guest_name = config.seats[1].name.clone();
+    let human_signal = move |seat: fn(&GameState) -> usize| {
+        state_signal.with(|state| state.seat_kind(seat(state)) == SeatKind::Human)
+    };
+
+    let thinking = move || {
+        state_signal.with(|state| {
+            let seat = state.waiting_on();
+            (!state.finished && state.seat_kind(seat) == SeatKind::Computer)
+                .then(|| fill(messages().computer_thinking, &[&state.players[seat].name]))
+        })
     };
 
     view! {
 
         <main class="container mx-auto pt-2 lg:p-5 grid grid-rows-2 lg:grid-rows-1 lg:grid-cols-2">
             <div class="flex justify-center">
-                <Board board_signal coord_signal rack_signal/>
+                <Board board coord_signal rack_signal/>
             </div>
 
             <div class="pl-5">
                 <h1 class="hidden lg:block p-5 text-4xl font-bold dark:text-yellow-100 text-center">"KRABBELS"</h1>
-                <h2 class="text-xs text-center p-2 mb-5 border-b-2 border-black dark:border-white dark:text-white">"A study project to learn further RUST, LEPTOS framework and TAILDWIND css."</h2>
-
-                <div class=("hidden", move || !played_signal())>
-                    <p class=("hidden", move || !valid_signal())>"✅ Le mot "<strong>{word_signal}</strong>" est correct !"</p>
-                    <p class=("hidden", move || valid_signal())>"❌ Le mot "<strong>{word_signal}</strong>" est incorrect."</p>
-                    <p class=("hidden", move || !valid_signal())>"Votre score est de "<strong>{score_signal}</strong>" points."</p>
-                    <p>"Mot dans le chevalet? " {in_rack_signal}</p>
-                    <p>"Lettres adjacentes? " {adjacent_signal}</p>
-                    <p>"Scrabble? " {scrabble_signal} <span class=("hidden", move || !scrabble_signal())>"🥳"</span></p>
+                <h2 class="text-xs text-center p-2 mb-5 border-b-2 border-black dark:border-white dark:text-white">{move || messages().subtitle}</h2>
+
+                <p class="dark:text-zinc-300">{move || messages().challenge_rule}{move || rule.label(language())}</p>
+
+                <label class="block dark:text-zinc-300" class=("hidden", computer_opponent)>{move || messages().opponent_account}
+                    <input type="text" class="block p-1 text-zinc-800" prop:disabled=started_signal on:change=move |ev| {
+                        let name = event_target_value(&ev).trim().to_string();
+                        state_signal.update(|state| {
+                            if name.is_empty() {
+                                state.seat_guest(1, &guest_name);
+                            } else {
+                                state.seat_account(1, Some(User { name }));
+                            }
+                        });
+                    }/>
+                </label>
+
+                <ul class="dark:text-zinc-300">{draw_players}</ul>
+
+                <p role="status" aria-live="polite" class="dark:text-zinc-300">{thinking}</p>
+
+                <div role="status" aria-live="polite" class="dark:text-zinc-300">{draw_game_over}</div>
+
+                <div role="status" aria-live="polite">{draw_result}</div>
+
+                <MovePreview board
+                    position=Signal::derive(move || state_signal.with(|state| {
+                        let human = state.seat_kind(state.current) == SeatKind::Human;
+                        (!state.finished && state.pending.is_none() && human).then(|| (state.grid, state.current_player().rack.clone()))
+                    }))
+                    lexicon_language=Signal::derive(move || state_signal.with(GameState::lexicon_language))/>
+
+                <div class=("hidden", move || !pending_signal() || !human_signal(GameState::challenger))>
+                    <p role="status" aria-live="polite">{move || if pending_signal() { fill(messages().pending, &[&challenger_name()]) } else { String::new() }}</p>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {accept()}>{move || messages().accept}</button>
+
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {challenge()}>{move || messages().challenge}</button>
                 </div>
 
+                <Rack rack_signal bag_count unseen_signal on_arrange=move |action| {
+                    state_signal.update(|state| state.arrange_rack(action));
+                    sync()
+                }/>
+                <ChatPanel room=Signal::derive(move || live_ticket().map(|ticket| ChatRoom::Live(ticket.id)))/>
+                <p>{move || fill(messages().selected_square, &[&coord_x_signal().to_string(), &coord_y_signal().to_string()])}</p>
 
-                <Rack rack_signal bag_signal/>
-                <p>"Case sélectionnée: ("{coord_x_signal}":"{coord_y_signal}")"</p>
+                <div class=("hidden", move || pending_signal() || finished_signal() || !human_signal(|state| state.current))>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {pick_tiles()}>{move || messages().draw_tiles}</button>
 
-                <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
-                on:click=move |_| {pick_tiles()}>Piocher des lettres</button>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {validate()}>{move || messages().validate}</button>
 
-                <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
-                on:click=move |_| {validate()}>Valider le coup</button>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {pass()}>{move || messages().pass}</button>
+
+                    <label class="dark:text-zinc-300">{move || messages().exchange_letters}
+                        <input type="text" class="w-24 p-1 text-zinc-800" maxlength="7" prop:value=exchange_signal
+                        on:input=move |ev| exchange_signal.set(event_target_value(&ev))/>
+                    </label>
+                    <button class="p-3 m-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg"
+                    on:click=move |_| {exchange()}>{move || messages().exchange}</button>
+                </div>
+
+                {move || live_ticket().map(|ticket| view! {
+                    <A href=format!("/live/{}", ticket.id) class="underline dark:text-zinc-300">{messages().spectate_link}</A>
+                })}
+
+                <h3 class="dark:text-zinc-300 font-bold">{move || messages().history}</h3>
+                <ol class="dark:text-zinc-300">{draw_history}</ol>
 
             </div>
 
         </main>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAT: [((usize, usize), Tile); 3] = [
+        ((8, 7), Tile('C', 3)),
+        ((8, 8), Tile('A', 1)),
+        ((8, 9), Tile('T', 1)),
+    ];
+
+    fn game(rule: ChallengeRule) -> GameState {
+        let mut state = GameState::new(&["Ann", "Bob"], rule, Language::English);
+        for player in &mut state.players {
+            player.rack = Bag::draw_tiles(RACK_SIZE as u8, &mut state.bag);
+        }
+        state.players[0].rack[..3].copy_from_slice(&CAT.map(|(_, tile)| tile));
+        state
+    }
+
+    #[test]
+    fn unseen_tiles_follow_the_human_seat() {
+        let mut config = GameConfig::new(Language::English);
+        config.seats[1].kind = SeatKind::Computer;
+        let mut state = GameState::from_config(&config);
+        for player in &mut state.players {
+            player.rack = Bag::draw_tiles(RACK_SIZE as u8, &mut state.bag);
+        }
+        let human = state.unseen_tiles_for(0);
+        assert_eq!(state.unseen_tiles(), human);
+        state.current = 1;
+        assert_eq!(state.shown_seat(), Some(0));
+        assert_eq!(state.unseen_tiles(), human);
+
+        config.seats[0].kind = SeatKind::Computer;
+        let state = GameState::from_config(&config);
+        assert_eq!(state.shown_seat(), None);
+        assert!(state.unseen_tiles().is_empty());
+    }
+
+    #[test]
+    fn accepted_play_scores_and_refills() {
+        let mut state = game(ChallengeRule::Double);
+        let play = state.submit(&CAT, &Lexicon::from_words("")).unwrap();
+        assert_eq!(state.pending, Some(play));
+        assert_eq!(state.waiting_on(), 1);
+        state.accept();
+        assert_eq!(state.players[0].score, 10);
+        assert_eq!(state.players[0].rack.len(), RACK_SIZE);
+        assert_eq!(state.grid.get((8, 8)), Some(Tile('A', 1)));
+        assert_eq!(state.current, 1);
+    }
+
+    #[test]
+    fn upheld_challenge_withdraws_the_play() {
+        let mut state = game(ChallengeRule::Double);
+        let rack = state.players[0].rack.clone();
+        state.submit(&CAT, &Lexicon::from_words("")).unwrap();
+        assert!(state.challenge(&Lexicon::from_words("DOG")));
+        assert!(matches!(state.history[0].action, Action::Withdrawn(_)));
+        assert_eq!(state.history[0].challenged_by, Some(1));
+        assert_eq!(state.players[0].score, 0);
+        assert_eq!(state.players[0].rack, rack);
+        assert!(state.grid.is_empty());
+        assert_eq!(state.current, 1);
+    }
+
+    #[test]
+    fn wrong_double_challenge_loses_a_turn() {
+        let mut state = game(ChallengeRule::Double);
+        state.submit(&CAT, &Lexicon::from_words("")).unwrap();
+        assert!(!state.challenge(&Lexicon::from_words("CAT")));
+        assert_eq!(state.players[0].score, 10);
+        assert_eq!(state.history[1].action, Action::LostTurn);
+        assert_eq!(state.current, 0);
+    }
+
+    #[test]
+    fn wrong_single_challenge_gives_a_bonus() {
+        let mut state = game(ChallengeRule::Single);
+        state.submit(&CAT, &Lexicon::from_words("")).unwrap();
+        assert!(!state.challenge(&Lexicon::from_words("CAT")));
+        assert_eq!(state.players[0].score, 10 + WRONG_CHALLENGE_BONUS);
+        assert_eq!(state.history[0].score, 10 + WRONG_CHALLENGE_BONUS);
+        assert_eq!(state.current, 1);
+    }
+
+    #[test]
+    fn void_rule_refuses_unknown_words() {
+        let mut state = game(ChallengeRule::Void);
+        assert_eq!(
+            state.submit(&CAT, &Lexicon::from_words("DOG")),
+            Err(PlayError::UnknownWords(vec!["CAT".to_string()]))
+        );
+        assert!(state.pending.is_none());
+        assert!(state.submit(&CAT, &Lexicon::from_words("CAT")).is_ok());
+        assert_eq!(state.players[0].score, 10);
+    }
+
+    #[test]
+    fn exchange_swaps_tiles_with_the_bag() {
+        let mut state = game(ChallengeRule::Double);
+        let bag = state.bag.len();
+        let swapped = state.players[0].rack[..2].to_vec();
+        state.exchange(&swapped).unwrap();
+        assert_eq!(state.players[0].rack.len(), RACK_SIZE);
+        assert_eq!(state.bag.len(), bag);
+        assert_eq!(state.history[0].action, Action::Exchange(2));
+        assert_eq!(state.history[0].score, 0);
+        assert_eq!(state.current, 1);
+    }
+
+    #[test]
+    fn exchange_needs_held_tiles_and_a_full_bag() {
+        let mut state = game(ChallengeRule::Double);
+        assert_eq!(state.exchange(&[]), Err(PlayError::NoTiles));
+        let held = state.players[0].rack.clone();
+        let missing = Bag::new(Language::English)
+            .0
+            .into_iter()
+            .find(|tile| !held.contains(tile))
+            .unwrap();
+        assert_eq!(state.exchange(&[missing]), Err(PlayError::NotInRack));
+        state.bag.truncate(RACK_SIZE - 1);
+        assert_eq!(state.exchange(&held[..1]), Err(PlayError::BagTooSmall));
+        assert!(state.history.is_empty());
+    }
+}
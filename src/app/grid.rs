@@ -0,0 +1,54 @@
+use super::board::*;
+use super::game::*;
+use serde::{Deserialize, Serialize};
+
+pub const BOARD_SIZE: usize = 15;
+
+/// Plain snapshot of the committed tiles, addressed with the same 1-based
+/// `(row, col)` coordinates as the board cells.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Grid {
+    squares: [[Option<Tile>; BOARD_SIZE]; BOARD_SIZE],
+    /// Grids saved before layouts could be chosen are standard ones.
+    #[serde(default)]
+    layout: BoardLayout,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Self::with_layout(BoardLayout::Standard)
+    }
+
+    pub fn with_layout(layout: BoardLayout) -> Self {
+        Self {
+            squares: [[None; BOARD_SIZE]; BOARD_SIZE],
+            layout,
+        }
+    }
+
+    pub fn layout(&self) -> BoardLayout {
+        self.layout
+    }
+
+    pub fn in_bounds(coord: (usize, usize)) -> bool {
+        (1..=BOARD_SIZE).contains(&coord.0) && (1..=BOARD_SIZE).contains(&coord.1)
+    }
+
+    pub fn get(&self, coord: (usize, usize)) -> Option<Tile> {
+        if Self::in_bounds(coord) {
+            self.squares[coord.0 - 1][coord.1 - 1]
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, coord: (usize, usize), tile: Option<Tile>) {
+        if Self::in_bounds(coord) {
+            self.squares[coord.0 - 1][coord.1 - 1] = tile;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.squares.iter().flatten().all(|square| square.is_none())
+    }
+}
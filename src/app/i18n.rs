@@ -0,0 +1,984 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    French,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::French, Language::English];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::French => "fr",
+            Language::English => "en",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::French => "Français",
+            Language::English => "English",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code.trim().split(['-', '_']).next()?.to_lowercase();
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code() == primary)
+    }
+
+    /// Picks the supported language with the highest `q` weight in an
+    /// `Accept-Language` header, `None` when none is accepted.
+    #[cfg(feature = "ssr")]
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut best: Option<(Language, f32)> = None;
+        for range in accept_language.split(',') {
+            let mut parts = range.split(';');
+            let Some(language) = parts.next().and_then(Language::from_code) else {
+                continue;
+            };
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if weight <= 0.0 {
+                continue;
+            }
+            if best.is_none_or(|(_, w)| weight > w) {
+                best = Some((language, weight));
+            }
+        }
+        best.map(|(language, _)| language)
+    }
+
+    pub fn messages(&self) -> &'static Messages {
+        match self {
+            Language::French => &FRENCH,
+            Language::English => &ENGLISH,
+        }
+    }
+}
+
+/// Message catalog. `{0}`, `{1}`… are replaced by [`fill`].
+pub struct Messages {
+    pub title: &'static str,
+    pub subtitle: &'static str,
+    pub language: &'static str,
+    pub player_name: &'static str,
+    pub player_score: &'static str,
+    pub challenge_rule: &'static str,
+    pub rule_void: &'static str,
+    pub rule_double: &'static str,
+    pub rule_single: &'static str,
+    pub word_valid: &'static str,
+    pub your_score: &'static str,
+    pub preview_score: &'static str,
+    pub bingo: &'static str,
+    pub pending: &'static str,
+    pub accept: &'static str,
+    pub challenge: &'static str,
+    pub selected_square: &'static str,
+    pub draw_tiles: &'static str,
+    pub validate: &'static str,
+    pub pass: &'static str,
+    pub exchange: &'static str,
+    pub exchange_letters: &'static str,
+    pub history: &'static str,
+    pub turn_play: &'static str,
+    pub turn_withdrawn: &'static str,
+    pub turn_pass: &'static str,
+    pub turn_lost: &'static str,
+    pub turn_exchange: &'static str,
+    pub challenge_rejected: &'static str,
+    pub challenged_by: &'static str,
+    pub tiles_in_bag: &'static str,
+    pub rack: &'static str,
+    pub unseen_tiles: &'static str,
+    pub vowels_consonants: &'static str,
+    pub shuffle: &'static str,
+    pub sort_alphabetical: &'static str,
+    pub sort_vowels_first: &'static str,
+    pub board: &'static str,
+    pub login: &'static str,
+    pub logout: &'static str,
+    pub register: &'static str,
+    pub signed_in_as: &'static str,
+    pub account_name: &'static str,
+    pub password: &'static str,
+    pub email_optional: &'static str,
+    pub no_account: &'static str,
+    pub game_over: &'static str,
+    pub winner: &'static str,
+    pub opponent_account: &'static str,
+    pub game_recorded: &'static str,
+    pub game_not_recorded: &'static str,
+    pub leaderboard: &'static str,
+    pub rank: &'static str,
+    pub rating: &'static str,
+    pub games_played: &'static str,
+    pub average_score: &'static str,
+    pub leaderboard_empty: &'static str,
+    pub stats_title: &'static str,
+    pub stats_unknown_player: &'static str,
+    pub stats_points: &'static str,
+    pub stats_win_rate: &'static str,
+    pub stats_average_move: &'static str,
+    pub stats_bingo_rate: &'static str,
+    pub stats_best_word: &'static str,
+    pub stats_best_move: &'static str,
+    pub stats_average_exchanged: &'static str,
+    pub stats_scores_chart: &'static str,
+    pub stats_rating_chart: &'static str,
+    pub stats_premiums_chart: &'static str,
+    pub live_games: &'static str,
+    pub live_empty: &'static str,
+    pub spectating: &'static str,
+    pub live_show_racks: &'static str,
+    pub live_delay: &'static str,
+    pub live_delay_rounds: &'static str,
+    pub spectate_link: &'static str,
+    pub chat: &'static str,
+    pub chat_message: &'static str,
+    pub chat_send: &'static str,
+    pub chat_unknown_game: &'static str,
+    pub chat_not_signed_in: &'static str,
+    pub chat_empty: &'static str,
+    pub chat_too_long: &'static str,
+    pub chat_too_fast: &'static str,
+    pub chat_storage: &'static str,
+    pub corr_my_games: &'static str,
+    pub corr_game: &'static str,
+    pub corr_new_game: &'static str,
+    pub corr_opponent: &'static str,
+    pub corr_turn_limit: &'static str,
+    pub corr_hours: &'static str,
+    pub corr_start: &'static str,
+    pub corr_no_games: &'static str,
+    pub corr_your_turn: &'static str,
+    pub corr_their_turn: &'static str,
+    pub corr_forfeited: &'static str,
+    pub corr_invited: &'static str,
+    pub corr_awaiting_answer: &'static str,
+    pub corr_declined: &'static str,
+    pub corr_accept: &'static str,
+    pub corr_decline: &'static str,
+    pub corr_unknown_game: &'static str,
+    pub corr_unknown_opponent: &'static str,
+    pub corr_too_many_games: &'static str,
+    pub corr_not_your_turn: &'static str,
+    pub corr_storage: &'static str,
+    pub notify_new_game_subject: &'static str,
+    pub notify_declined_subject: &'static str,
+    pub notify_turn_subject: &'static str,
+    pub notify_turn_body: &'static str,
+    pub notify_over_subject: &'static str,
+    pub notify_over_body: &'static str,
+    pub tournaments: &'static str,
+    pub tournament_none: &'static str,
+    pub tournament_new: &'static str,
+    pub tournament_create: &'static str,
+    pub tournament_name: &'static str,
+    pub tournament_system: &'static str,
+    pub tournament_swiss: &'static str,
+    pub tournament_round_robin: &'static str,
+    pub tournament_swiss_rounds: &'static str,
+    pub tournament_players: &'static str,
+    pub tournament_round: &'static str,
+    pub tournament_round_number: &'static str,
+    pub tournament_progress: &'static str,
+    pub tournament_organized_by: &'static str,
+    pub tournament_join: &'static str,
+    pub tournament_pair: &'static str,
+    pub tournament_standings: &'static str,
+    pub tournament_points: &'static str,
+    pub tournament_spread: &'static str,
+    pub tournament_buchholz: &'static str,
+    pub tournament_table: &'static str,
+    pub tournament_first: &'static str,
+    pub tournament_second: &'static str,
+    pub tournament_game: &'static str,
+    pub tournament_result: &'static str,
+    pub tournament_record: &'static str,
+    pub tournament_bye: &'static str,
+    pub tournament_unknown: &'static str,
+    pub tournament_invalid_name: &'static str,
+    pub tournament_invalid_rounds: &'static str,
+    pub tournament_not_organizer: &'static str,
+    pub tournament_started: &'static str,
+    pub tournament_not_enough_players: &'static str,
+    pub tournament_round_in_progress: &'static str,
+    pub tournament_finished: &'static str,
+    pub tournament_unknown_pairing: &'static str,
+    pub tournament_result_synced: &'static str,
+    pub check_title: &'static str,
+    pub check_help: &'static str,
+    pub check_words: &'static str,
+    pub check_submit: &'static str,
+    pub check_valid: &'static str,
+    pub check_invalid: &'static str,
+    pub check_inflections: &'static str,
+    pub check_failed: &'static str,
+    pub study_title: &'static str,
+    pub study_help: &'static str,
+    pub study_anagrams: &'static str,
+    pub study_subwords: &'static str,
+    pub study_pattern: &'static str,
+    pub study_query: &'static str,
+    pub study_search: &'static str,
+    pub study_count: &'static str,
+    pub study_nothing: &'static str,
+    pub study_word: &'static str,
+    pub study_length: &'static str,
+    pub study_score: &'static str,
+    pub flashcards_title: &'static str,
+    pub flashcards_help: &'static str,
+    pub flashcards_length: &'static str,
+    pub flashcards_solutions: &'static str,
+    pub flashcards_odds: &'static str,
+    pub flashcards_done: &'static str,
+    pub flashcards_level: &'static str,
+    pub flashcards_due: &'static str,
+    pub flashcards_correct: &'static str,
+    pub flashcards_incorrect: &'static str,
+    pub flashcards_words: &'static str,
+    pub flashcards_missed: &'static str,
+    pub flashcards_wrong: &'static str,
+    pub flashcards_answer: &'static str,
+    pub flashcards_check: &'static str,
+    pub flashcards_next: &'static str,
+    pub flashcards_sign_in: &'static str,
+    pub flashcards_unknown_card: &'static str,
+    pub flashcards_storage: &'static str,
+    pub review_title: &'static str,
+    pub review_link: &'static str,
+    pub review_turn: &'static str,
+    pub review_rack: &'static str,
+    pub review_played: &'static str,
+    pub review_best: &'static str,
+    pub review_missed: &'static str,
+    pub review_missed_total: &'static str,
+    pub review_no_move: &'static str,
+    pub review_previous: &'static str,
+    pub review_next: &'static str,
+    pub auth_name_taken: &'static str,
+    pub auth_invalid_name: &'static str,
+    pub auth_weak_password: &'static str,
+    pub auth_invalid_email: &'static str,
+    pub auth_invalid_credentials: &'static str,
+    pub auth_storage: &'static str,
+    pub premium_double_letter: &'static str,
+    pub premium_triple_letter: &'static str,
+    pub premium_double_word: &'static str,
+    pub premium_triple_word: &'static str,
+    pub square_empty: &'static str,
+    pub square_blank_as: &'static str,
+    pub blank_prompt: &'static str,
+    pub square_tile: &'static str,
+    pub square_placed: &'static str,
+    pub error_no_tiles: &'static str,
+    pub error_occupied: &'static str,
+    pub error_same_square: &'static str,
+    pub error_not_in_rack: &'static str,
+    pub error_blank_without_letter: &'static str,
+    pub error_not_in_line: &'static str,
+    pub error_has_gap: &'static str,
+    pub error_off_center: &'static str,
+    pub error_not_connected: &'static str,
+    pub error_too_short: &'static str,
+    pub error_unknown_words: &'static str,
+    pub error_game_over: &'static str,
+    pub error_bag_too_small: &'static str,
+    pub setup_title: &'static str,
+    pub setup_players: &'static str,
+    pub setup_seat: &'static str,
+    pub setup_add_player: &'static str,
+    pub setup_remove_player: &'static str,
+    pub setup_tiles: &'static str,
+    pub setup_lexicon: &'static str,
+    pub setup_layout: &'static str,
+    pub setup_time: &'static str,
+    pub setup_start: &'static str,
+    pub seat_human: &'static str,
+    pub seat_computer: &'static str,
+    pub computer_name: &'static str,
+    pub computer_thinking: &'static str,
+    pub layout_standard: &'static str,
+    pub layout_shuffled: &'static str,
+    pub time_unlimited: &'static str,
+    pub time_minutes: &'static str,
+    pub time_out: &'static str,
+    pub lobby_title: &'static str,
+    pub lobby_play: &'static str,
+    pub tools_title: &'static str,
+    pub tab_game: &'static str,
+    pub tab_replay: &'static str,
+    pub error_title: &'static str,
+    pub error_not_found: &'static str,
+    pub error_game_in_progress: &'static str,
+    pub error_not_signed_in: &'static str,
+    pub error_not_a_player: &'static str,
+    pub error_lexicon: &'static str,
+    pub error_storage: &'static str,
+    pub error_internal: &'static str,
+}
+
+static FRENCH: Messages = Messages {
+    title: "Bienvenue sur KRABBELS",
+    subtitle: "Un projet d'étude pour approfondir RUST, le framework LEPTOS et TAILWIND css.",
+    language: "Langue",
+    player_name: "Joueur {0}",
+    player_score: "{0} : {1} points",
+    challenge_rule: "Contestation : ",
+    rule_void: "Sans contestation",
+    rule_double: "Double contestation",
+    rule_single: "Contestation à 5 points",
+    word_valid: "✅ Le mot {0} est correct !",
+    your_score: "Votre score est de {0} points.",
+    preview_score: "Coup prévu : {0} pour {1} points",
+    bingo: "Scrabble ! 🥳",
+    pending: "En attente : {0} peut contester le coup.",
+    accept: "Accepter",
+    challenge: "Contester",
+    selected_square: "Case sélectionnée: ({0}:{1})",
+    draw_tiles: "Piocher des lettres",
+    validate: "Valider le coup",
+    pass: "Passer",
+    exchange: "Échanger",
+    exchange_letters: "Lettres à échanger (? pour un joker) ",
+    history: "Historique",
+    turn_play: "{0} ({1} points)",
+    turn_withdrawn: "{0} retiré après contestation",
+    turn_pass: "passe",
+    turn_lost: "perd son tour",
+    turn_exchange: "échange {0} lettres",
+    challenge_rejected: " — contestation de {0} rejetée",
+    challenged_by: " — contesté par {0}",
+    tiles_in_bag: "{0} lettres dans le sac.",
+    rack: "Chevalet",
+    unseen_tiles: "Lettres non vues",
+    vowels_consonants: "{0} voyelles, {1} consonnes",
+    shuffle: "Mélanger",
+    sort_alphabetical: "Trier A-Z",
+    sort_vowels_first: "Voyelles d'abord",
+    board: "Plateau",
+    login: "Connexion",
+    logout: "Déconnexion",
+    register: "Créer un compte",
+    signed_in_as: "Connecté : {0}",
+    account_name: "Nom",
+    password: "Mot de passe",
+    email_optional: "E-mail (facultatif, pour les parties par correspondance)",
+    no_account: "Pas encore de compte ?",
+    game_over: "Partie terminée.",
+    winner: "Vainqueur : {0}",
+    opponent_account: "Compte de l'adversaire (partie classée)",
+    game_recorded: "Partie enregistrée, hors classement.",
+    game_not_recorded: "Le résultat n'a pas pu être enregistré.",
+    leaderboard: "Classement",
+    rank: "Rang",
+    rating: "Cote",
+    games_played: "Parties",
+    average_score: "Score moyen",
+    leaderboard_empty: "Aucune partie classée pour l'instant.",
+    stats_title: "Statistiques de {0}",
+    stats_unknown_player: "Aucune partie enregistrée pour ce joueur.",
+    stats_points: "{0} ({1} points)",
+    stats_win_rate: "Victoires",
+    stats_average_move: "Score moyen par coup",
+    stats_bingo_rate: "Scrabbles par coup",
+    stats_best_word: "Meilleur mot",
+    stats_best_move: "Meilleur coup",
+    stats_average_exchanged: "Lettres par échange",
+    stats_scores_chart: "Score par partie",
+    stats_rating_chart: "Évolution de la cote",
+    stats_premiums_chart: "Cases bonus les plus utilisées",
+    live_games: "Parties en direct",
+    live_empty: "Aucune partie en cours.",
+    spectating: "Spectateur",
+    live_show_racks: "Montrer les chevalets",
+    live_delay: "Retard : ",
+    live_delay_rounds: "{0} tours de table",
+    spectate_link: "Lien spectateur",
+    chat: "Discussion",
+    chat_message: "Message",
+    chat_send: "Envoyer",
+    chat_unknown_game: "Cette partie n'est plus en ligne.",
+    chat_not_signed_in: "Connectez-vous pour écrire.",
+    chat_empty: "Le message est vide.",
+    chat_too_long: "Le message est trop long.",
+    chat_too_fast: "Trop de messages, patientez quelques secondes.",
+    chat_storage: "Le serveur n'a pas pu enregistrer le message.",
+    corr_my_games: "Mes parties",
+    corr_game: "Partie par correspondance",
+    corr_new_game: "Nouvelle partie par correspondance",
+    corr_opponent: "Adversaire (nom du compte)",
+    corr_turn_limit: "Temps par coup",
+    corr_hours: "{0} h",
+    corr_start: "Inviter",
+    corr_no_games: "Aucune partie en cours.",
+    corr_your_turn: "À vous de jouer ({0} restantes)",
+    corr_their_turn: "En attente de {0}",
+    corr_forfeited: "{0} a perdu au temps.",
+    corr_invited: "{0} vous invite ({1} pour répondre)",
+    corr_awaiting_answer: "En attente de la réponse de {0}",
+    corr_declined: "Invitation refusée ou expirée.",
+    corr_accept: "Accepter",
+    corr_decline: "Refuser",
+    corr_unknown_game: "Cette partie n'existe pas.",
+    corr_unknown_opponent: "Aucun autre compte ne porte ce nom.",
+    corr_too_many_games: "Vous avez trop de parties en cours pour en commencer une autre.",
+    corr_not_your_turn: "Ce n'est pas à vous de jouer.",
+    corr_storage: "Le serveur n'a pas pu enregistrer la partie.",
+    notify_new_game_subject: "{0} vous invite à une partie",
+    notify_declined_subject: "{0} n'a pas accepté votre invitation",
+    notify_turn_subject: "À vous de jouer contre {0}",
+    notify_turn_body: "{0} a joué. Vous avez {1} heures pour répondre :\n{2}",
+    notify_over_subject: "Partie terminée contre {0}",
+    notify_over_body: "La partie est terminée : {0}.\n{1}",
+    tournaments: "Tournois",
+    tournament_none: "Aucun tournoi pour l'instant.",
+    tournament_new: "Nouveau tournoi",
+    tournament_create: "Créer le tournoi",
+    tournament_name: "Nom",
+    tournament_system: "Appariements",
+    tournament_swiss: "Système suisse",
+    tournament_round_robin: "Toutes rondes",
+    tournament_swiss_rounds: "Rondes (système suisse)",
+    tournament_players: "Joueurs",
+    tournament_round: "Ronde",
+    tournament_round_number: "Ronde {0}",
+    tournament_progress: "ronde {0} sur {1}",
+    tournament_organized_by: "organisé par {0}",
+    tournament_join: "Inscrire",
+    tournament_pair: "Apparier la ronde suivante",
+    tournament_standings: "Classement",
+    tournament_points: "Points",
+    tournament_spread: "Écart",
+    tournament_buchholz: "Buchholz",
+    tournament_table: "Table",
+    tournament_first: "Joueur 1",
+    tournament_second: "Joueur 2",
+    tournament_game: "Partie",
+    tournament_result: "Résultat",
+    tournament_record: "Enregistrer",
+    tournament_bye: "Exempt",
+    tournament_unknown: "Ce tournoi n'existe pas.",
+    tournament_invalid_name: "Le nom doit contenir 1 à 60 caractères.",
+    tournament_invalid_rounds: "Un tournoi suisse compte de 1 à 15 rondes.",
+    tournament_not_organizer: "Seul l'organisateur peut faire cela.",
+    tournament_started: "Le tournoi a déjà commencé.",
+    tournament_not_enough_players: "Il faut au moins deux joueurs.",
+    tournament_round_in_progress: "Tous les résultats de la ronde ne sont pas connus.",
+    tournament_finished: "Le tournoi est terminé.",
+    tournament_unknown_pairing: "Cette table n'existe pas.",
+    tournament_result_synced: "Ce résultat vient de la partie jouée et ne peut pas être changé.",
+    check_title: "Vérifier un mot",
+    check_help: "Un ou plusieurs mots, séparés par des espaces ou des virgules, cherchés dans le lexique ({0}).",
+    check_words: "Mots à vérifier",
+    check_submit: "Vérifier",
+    check_valid: "valide",
+    check_invalid: "non valide",
+    check_inflections: "Autres formes : {0}",
+    check_failed: "La vérification a échoué.",
+    study_title: "Entraînement",
+    study_help: "Tirage avec « ? » pour les jokers, ou motif comme C?A?E.",
+    study_anagrams: "Anagrammes",
+    study_subwords: "Sous-tirages",
+    study_pattern: "Motif",
+    study_query: "Tirage ou motif",
+    study_search: "Chercher",
+    study_count: "{0} mots trouvés",
+    study_nothing: "Aucun mot trouvé.",
+    study_word: "Mot",
+    study_length: "Lettres",
+    study_score: "Points",
+    flashcards_title: "Cartes de bingo",
+    flashcards_help: "Trouvez tous les mots formés avec ces lettres. Les cartes bien connues reviennent moins souvent.",
+    flashcards_length: "{0} lettres",
+    flashcards_solutions: "Mots à trouver : {0}",
+    flashcards_odds: "Tirage : 1 chance sur {0}",
+    flashcards_done: "Aucune carte à revoir pour le moment.",
+    flashcards_level: "Niveau {0} : {1}",
+    flashcards_due: "{0} à revoir, {1} nouvelles",
+    flashcards_correct: "Bravo, tout est trouvé !",
+    flashcards_incorrect: "Pas tout à fait.",
+    flashcards_words: "Solutions : {0}",
+    flashcards_missed: "Oubliés : {0}",
+    flashcards_wrong: "En trop : {0}",
+    flashcards_answer: "Vos mots, séparés par des espaces",
+    flashcards_check: "Vérifier",
+    flashcards_next: "Carte suivante",
+    flashcards_sign_in: "Connectez-vous pour suivre vos progrès.",
+    flashcards_unknown_card: "Cette carte n'existe pas.",
+    flashcards_storage: "Impossible d'enregistrer vos progrès.",
+    review_title: "Analyse de la partie {0}",
+    review_link: "Analyser la partie",
+    review_turn: "Coup {0} — {1}",
+    review_rack: "Chevalet : {0}",
+    review_played: "Joué : {0}",
+    review_best: "Meilleurs coups",
+    review_missed: "{0} points laissés sur la table",
+    review_missed_total: "{0} points laissés sur la table au total",
+    review_no_move: "Aucun coup possible avec ce chevalet.",
+    review_previous: "Coup précédent",
+    review_next: "Coup suivant",
+    auth_name_taken: "Ce nom est déjà pris.",
+    auth_invalid_name: "Le nom doit contenir 3 à 20 lettres, chiffres, « - » ou « _ ».",
+    auth_weak_password: "Le mot de passe doit contenir au moins 8 caractères.",
+    auth_invalid_email: "L'adresse e-mail n'est pas valide.",
+    auth_invalid_credentials: "Nom ou mot de passe incorrect.",
+    auth_storage: "Le serveur n'a pas pu enregistrer le compte.",
+    premium_double_letter: "lettre compte double",
+    premium_triple_letter: "lettre compte triple",
+    premium_double_word: "mot compte double",
+    premium_triple_word: "mot compte triple",
+    square_empty: "vide",
+    square_blank_as: "joker joué comme {0}",
+    blank_prompt: "Quelle lettre le joker remplace-t-il ?",
+    square_tile: "{0}, {1} points",
+    square_placed: "posée au coup {0} par {1}",
+    error_no_tiles: "Aucune lettre n'a été posée.",
+    error_occupied: "Une case est déjà occupée.",
+    error_same_square: "Deux lettres ont été posées sur la même case.",
+    error_not_in_rack: "Les lettres ne sont pas dans le chevalet.",
+    error_blank_without_letter: "Un joker a été posé sans lettre.",
+    error_not_in_line: "Les lettres ne sont pas alignées.",
+    error_has_gap: "Le mot contient un trou.",
+    error_off_center: "Le premier mot doit passer par la case centrale.",
+    error_not_connected: "Le mot ne touche aucune lettre déjà posée.",
+    error_too_short: "Un mot doit contenir au moins deux lettres.",
+    error_unknown_words: "Mot inconnu : {0}",
+    error_game_over: "La partie est terminée.",
+    error_bag_too_small: "Il ne reste pas assez de lettres dans le sac pour échanger.",
+    setup_title: "Nouvelle partie",
+    setup_players: "Joueurs",
+    setup_seat: "Place {0}",
+    setup_add_player: "Ajouter un joueur",
+    setup_remove_player: "Retirer",
+    setup_tiles: "Jeu de lettres : ",
+    setup_lexicon: "Dictionnaire : ",
+    setup_layout: "Plateau : ",
+    setup_time: "Temps par joueur : ",
+    setup_start: "Commencer",
+    seat_human: "Humain",
+    seat_computer: "Ordinateur",
+    computer_name: "Ordinateur {0}",
+    computer_thinking: "{0} réfléchit…",
+    layout_standard: "Classique",
+    layout_shuffled: "Cases bonus mélangées",
+    time_unlimited: "Illimité",
+    time_minutes: "{0} min",
+    time_out: "{0} a dépassé son temps.",
+    lobby_title: "Salon",
+    lobby_play: "Nouvelle partie locale",
+    tools_title: "Outils",
+    tab_game: "Partie",
+    tab_replay: "Revoir",
+    error_title: "Erreur {0}",
+    error_not_found: "Cette page n'existe pas.",
+    error_game_in_progress: "Cette partie n'est pas terminée, elle ne peut pas encore être rejouée.",
+    error_not_signed_in: "Connectez-vous pour voir cette page.",
+    error_not_a_player: "Vous ne jouez pas dans cette partie.",
+    error_lexicon: "Le lexique ({0}) n'a pas pu être chargé.",
+    error_storage: "Le serveur a rencontré un problème, réessayez plus tard.",
+    error_internal: "Une erreur inattendue est survenue, réessayez plus tard.",
+};
+
+static ENGLISH: Messages = Messages {
+    title: "Welcome to KRABBELS",
+    subtitle: "A study project to learn further RUST, LEPTOS framework and TAILWIND css.",
+    language: "Language",
+    player_name: "Player {0}",
+    player_score: "{0}: {1} points",
+    challenge_rule: "Challenge: ",
+    rule_void: "Void (no challenge)",
+    rule_double: "Double challenge",
+    rule_single: "Single challenge (5 points)",
+    word_valid: "✅ The word {0} is valid!",
+    your_score: "Your score is {0} points.",
+    preview_score: "Projected move: {0} for {1} points",
+    bingo: "Bingo! 🥳",
+    pending: "Waiting: {0} may challenge the play.",
+    accept: "Accept",
+    challenge: "Challenge",
+    selected_square: "Selected square: ({0}:{1})",
+    draw_tiles: "Draw tiles",
+    validate: "Submit move",
+    pass: "Pass",
+    exchange: "Exchange",
+    exchange_letters: "Tiles to exchange (? for a blank) ",
+    history: "History",
+    turn_play: "{0} ({1} points)",
+    turn_withdrawn: "{0} withdrawn after a challenge",
+    turn_pass: "passes",
+    turn_lost: "loses their turn",
+    turn_exchange: "exchanges {0} tiles",
+    challenge_rejected: " — challenge by {0} rejected",
+    challenged_by: " — challenged by {0}",
+    tiles_in_bag: "{0} tiles in the bag.",
+    rack: "Rack",
+    unseen_tiles: "Unseen tiles",
+    vowels_consonants: "{0} vowels, {1} consonants",
+    shuffle: "Shuffle",
+    sort_alphabetical: "Sort A-Z",
+    sort_vowels_first: "Vowels first",
+    board: "Board",
+    login: "Sign in",
+    logout: "Sign out",
+    register: "Create an account",
+    signed_in_as: "Signed in as {0}",
+    account_name: "Name",
+    password: "Password",
+    email_optional: "Email (optional, for correspondence games)",
+    no_account: "No account yet?",
+    game_over: "Game over.",
+    winner: "Winner: {0}",
+    opponent_account: "Opponent's account (rated game)",
+    game_recorded: "Game saved, unrated.",
+    game_not_recorded: "The result could not be recorded.",
+    leaderboard: "Leaderboard",
+    rank: "Rank",
+    rating: "Rating",
+    games_played: "Games",
+    average_score: "Average score",
+    leaderboard_empty: "No rated games yet.",
+    stats_title: "Statistics for {0}",
+    stats_unknown_player: "No recorded games for this player.",
+    stats_points: "{0} ({1} points)",
+    stats_win_rate: "Win rate",
+    stats_average_move: "Average move score",
+    stats_bingo_rate: "Bingos per move",
+    stats_best_word: "Best word",
+    stats_best_move: "Highest-scoring move",
+    stats_average_exchanged: "Tiles per exchange",
+    stats_scores_chart: "Score per game",
+    stats_rating_chart: "Rating history",
+    stats_premiums_chart: "Most used premium squares",
+    live_games: "Live games",
+    live_empty: "No game in progress.",
+    spectating: "Spectating",
+    live_show_racks: "Show the racks",
+    live_delay: "Delay: ",
+    live_delay_rounds: "{0} rounds",
+    spectate_link: "Spectator link",
+    chat: "Chat",
+    chat_message: "Message",
+    chat_send: "Send",
+    chat_unknown_game: "This game is no longer live.",
+    chat_not_signed_in: "Sign in to write.",
+    chat_empty: "The message is empty.",
+    chat_too_long: "The message is too long.",
+    chat_too_fast: "Too many messages, wait a few seconds.",
+    chat_storage: "The server could not save the message.",
+    corr_my_games: "My games",
+    corr_game: "Correspondence game",
+    corr_new_game: "New correspondence game",
+    corr_opponent: "Opponent (account name)",
+    corr_turn_limit: "Time per move",
+    corr_hours: "{0} h",
+    corr_start: "Invite",
+    corr_no_games: "No games yet.",
+    corr_your_turn: "Your turn ({0} left)",
+    corr_their_turn: "Waiting for {0}",
+    corr_forfeited: "{0} ran out of time.",
+    corr_invited: "{0} invited you ({1} left to answer)",
+    corr_awaiting_answer: "Waiting for {0} to answer",
+    corr_declined: "Invitation declined or expired.",
+    corr_accept: "Accept",
+    corr_decline: "Decline",
+    corr_unknown_game: "This game does not exist.",
+    corr_unknown_opponent: "No other account has this name.",
+    corr_too_many_games: "You have too many games in progress to start another one.",
+    corr_not_your_turn: "It is not your turn.",
+    corr_storage: "The server could not save the game.",
+    notify_new_game_subject: "{0} invited you to a game",
+    notify_declined_subject: "{0} did not accept your invitation",
+    notify_turn_subject: "Your turn against {0}",
+    notify_turn_body: "{0} has moved. You have {1} hours to answer:\n{2}",
+    notify_over_subject: "Game over against {0}",
+    notify_over_body: "The game is over: {0}.\n{1}",
+    tournaments: "Tournaments",
+    tournament_none: "No tournaments yet.",
+    tournament_new: "New tournament",
+    tournament_create: "Create tournament",
+    tournament_name: "Name",
+    tournament_system: "Pairings",
+    tournament_swiss: "Swiss system",
+    tournament_round_robin: "Round robin",
+    tournament_swiss_rounds: "Rounds (Swiss system)",
+    tournament_players: "Players",
+    tournament_round: "Round",
+    tournament_round_number: "Round {0}",
+    tournament_progress: "round {0} of {1}",
+    tournament_organized_by: "organized by {0}",
+    tournament_join: "Register",
+    tournament_pair: "Pair next round",
+    tournament_standings: "Standings",
+    tournament_points: "Points",
+    tournament_spread: "Spread",
+    tournament_buchholz: "Buchholz",
+    tournament_table: "Table",
+    tournament_first: "Player 1",
+    tournament_second: "Player 2",
+    tournament_game: "Game",
+    tournament_result: "Result",
+    tournament_record: "Record",
+    tournament_bye: "Bye",
+    tournament_unknown: "This tournament does not exist.",
+    tournament_invalid_name: "The name must have 1 to 60 characters.",
+    tournament_invalid_rounds: "A Swiss tournament has 1 to 15 rounds.",
+    tournament_not_organizer: "Only the organizer can do this.",
+    tournament_started: "The tournament has already started.",
+    tournament_not_enough_players: "At least two players are needed.",
+    tournament_round_in_progress: "Some results of the round are still missing.",
+    tournament_finished: "The tournament is over.",
+    tournament_unknown_pairing: "This table does not exist.",
+    tournament_result_synced: "This result comes from the game played and cannot be changed.",
+    check_title: "Check a word",
+    check_help: "One or more words, separated by spaces or commas, looked up in the lexicon ({0}).",
+    check_words: "Words to check",
+    check_submit: "Check",
+    check_valid: "valid",
+    check_invalid: "not valid",
+    check_inflections: "Other forms: {0}",
+    check_failed: "The check failed.",
+    study_title: "Study",
+    study_help: "A rack with “?” for blanks, or a pattern such as C?A?E.",
+    study_anagrams: "Anagrams",
+    study_subwords: "Subwords",
+    study_pattern: "Pattern",
+    study_query: "Rack or pattern",
+    study_search: "Search",
+    study_count: "{0} words found",
+    study_nothing: "No words found.",
+    study_word: "Word",
+    study_length: "Letters",
+    study_score: "Score",
+    flashcards_title: "Bingo flashcards",
+    flashcards_help:
+        "Find every word spelled with these letters. Cards you know well come back less often.",
+    flashcards_length: "{0} letters",
+    flashcards_solutions: "Words to find: {0}",
+    flashcards_odds: "Draw odds: 1 in {0}",
+    flashcards_done: "No card to review for now.",
+    flashcards_level: "Level {0}: {1}",
+    flashcards_due: "{0} due, {1} new",
+    flashcards_correct: "Well done, you found them all!",
+    flashcards_incorrect: "Not quite.",
+    flashcards_words: "Solutions: {0}",
+    flashcards_missed: "Missed: {0}",
+    flashcards_wrong: "Not on the card: {0}",
+    flashcards_answer: "Your words, separated by spaces",
+    flashcards_check: "Check",
+    flashcards_next: "Next card",
+    flashcards_sign_in: "Sign in to keep track of your progress.",
+    flashcards_unknown_card: "This card does not exist.",
+    flashcards_storage: "Your progress could not be saved.",
+    review_title: "Analysis of game {0}",
+    review_link: "Analyse the game",
+    review_turn: "Turn {0} — {1}",
+    review_rack: "Rack: {0}",
+    review_played: "Played: {0}",
+    review_best: "Best moves",
+    review_missed: "{0} points left on the table",
+    review_missed_total: "{0} points left on the table in all",
+    review_no_move: "No move was possible with this rack.",
+    review_previous: "Previous turn",
+    review_next: "Next turn",
+    auth_name_taken: "This name is already taken.",
+    auth_invalid_name: "Names need 3 to 20 letters, digits, '-' or '_'.",
+    auth_weak_password: "Passwords need at least 8 characters.",
+    auth_invalid_email: "The email address is not valid.",
+    auth_invalid_credentials: "Wrong name or password.",
+    auth_storage: "The server could not save the account.",
+    premium_double_letter: "double letter score",
+    premium_triple_letter: "triple letter score",
+    premium_double_word: "double word score",
+    premium_triple_word: "triple word score",
+    square_empty: "empty",
+    square_blank_as: "blank played as {0}",
+    blank_prompt: "Which letter does the blank stand for?",
+    square_tile: "{0}, {1} points",
+    square_placed: "placed on turn {0} by {1}",
+    error_no_tiles: "No tile has been placed.",
+    error_occupied: "A square is already taken.",
+    error_same_square: "Two tiles were placed on the same square.",
+    error_not_in_rack: "The tiles are not on your rack.",
+    error_blank_without_letter: "A blank was put down without a letter.",
+    error_not_in_line: "The tiles are not in a single line.",
+    error_has_gap: "The word has a gap.",
+    error_off_center: "The first word must cover the centre square.",
+    error_not_connected: "The word does not touch any tile on the board.",
+    error_too_short: "A word needs at least two letters.",
+    error_unknown_words: "Unknown word: {0}",
+    error_game_over: "The game is over.",
+    error_bag_too_small: "Not enough tiles left in the bag to exchange.",
+    setup_title: "New game",
+    setup_players: "Players",
+    setup_seat: "Seat {0}",
+    setup_add_player: "Add a player",
+    setup_remove_player: "Remove",
+    setup_tiles: "Tile set: ",
+    setup_lexicon: "Lexicon: ",
+    setup_layout: "Board: ",
+    setup_time: "Time per player: ",
+    setup_start: "Start",
+    seat_human: "Human",
+    seat_computer: "Computer",
+    computer_name: "Computer {0}",
+    computer_thinking: "{0} is thinking…",
+    layout_standard: "Standard",
+    layout_shuffled: "Shuffled premium squares",
+    time_unlimited: "Unlimited",
+    time_minutes: "{0} min",
+    time_out: "{0} ran out of time.",
+    lobby_title: "Lobby",
+    lobby_play: "New local game",
+    tools_title: "Tools",
+    tab_game: "Game",
+    tab_replay: "Replay",
+    error_title: "Error {0}",
+    error_not_found: "This page does not exist.",
+    error_game_in_progress: "This game is not finished, it cannot be replayed yet.",
+    error_not_signed_in: "Sign in to see this page.",
+    error_not_a_player: "You are not playing in this game.",
+    error_lexicon: "The lexicon ({0}) could not be loaded.",
+    error_storage: "The server ran into a problem, try again later.",
+    error_internal: "Something unexpected went wrong, try again later.",
+};
+
+pub fn fill(template: &str, args: &[&str]) -> String {
+    args.iter()
+        .enumerate()
+        .fold(template.to_string(), |text, (idx, arg)| {
+            text.replace(&format!("{{{idx}}}"), arg)
+        })
+}
+
+pub fn use_language() -> RwSignal<Language> {
+    expect_context::<RwSignal<Language>>()
+}
+
+/// Language of the first render: the `lang` cookie set by the toggle, then
+/// `Accept-Language`, then the default of the server configuration; the
+/// client reuses the `<html lang>` the server rendered so hydration matches.
+pub fn initial_language() -> Language {
+    #[cfg(feature = "ssr")]
+    {
+        use http::header::{ACCEPT_LANGUAGE, COOKIE};
+        use http::request::Parts;
+
+        let fallback = use_context::<crate::config::ServerConfig>()
+            .map(|config| config.default_language)
+            .unwrap_or_default();
+        let Some(parts) = use_context::<Parts>() else {
+            return fallback;
+        };
+        let from_cookie = parts
+            .headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .find_map(|pair| pair.trim().strip_prefix("lang="))
+            .and_then(Language::from_code);
+        from_cookie.unwrap_or_else(|| {
+            parts
+                .headers
+                .get(ACCEPT_LANGUAGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(Language::negotiate)
+                .unwrap_or(fallback)
+        })
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        document()
+            .document_element()
+            .and_then(|html| html.get_attribute("lang"))
+            .and_then(|code| Language::from_code(&code))
+            .unwrap_or_default()
+    }
+}
+
+#[component]
+pub fn LanguageToggle() -> impl IntoView {
+    let language = use_language();
+
+    let choose = move |chosen: Language| {
+        language.set(chosen);
+        #[cfg(feature = "hydrate")]
+        {
+            use wasm_bindgen::JsCast;
+            if let Ok(html) = document().dyn_into::<web_sys::HtmlDocument>() {
+                let _ = html.set_cookie(&format!(
+                    "lang={}; path=/; max-age=31536000; samesite=lax",
+                    chosen.code()
+                ));
+            }
+        }
+    };
+
+    let draw_languages = move || {
+        Language::ALL
+            .into_iter()
+            .map(|option| {
+                view! {
+                    <button class="px-2 text-xs dark:text-zinc-300" class=("font-bold", move || language() == option)
+                    on:click=move |_| choose(option)>{option.name()}</button>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <nav class="text-right" aria-label=move || language().messages().language>{draw_languages}</nav>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_codes_ignore_the_region() {
+        assert_eq!(Language::from_code("en-GB"), Some(Language::English));
+        assert_eq!(Language::from_code(" FR_be"), Some(Language::French));
+        assert_eq!(Language::from_code("de"), None);
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn negotiate_picks_the_heaviest_supported_range() {
+        assert_eq!(
+            Language::negotiate("de-DE, en;q=0.8, fr;q=0.9"),
+            Some(Language::French)
+        );
+        assert_eq!(
+            Language::negotiate("en-US,en;q=0.9"),
+            Some(Language::English)
+        );
+        assert_eq!(Language::negotiate("de, it;q=0.5"), None);
+        assert_eq!(Language::negotiate(""), None);
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn negotiate_skips_refused_ranges() {
+        assert_eq!(Language::negotiate("fr;q=0"), None);
+        assert_eq!(
+            Language::negotiate("fr;q=0, en;q=0.1"),
+            Some(Language::English)
+        );
+    }
+
+    #[test]
+    fn fill_replaces_numbered_slots() {
+        assert_eq!(fill("{0} / {1} / {0}", &["a", "b"]), "a / b / a");
+    }
+}
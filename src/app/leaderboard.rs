@@ -0,0 +1,120 @@
+use super::game::*;
+use super::i18n::*;
+use super::spectate::*;
+use leptos::server_fn::codec::Json;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub rating: f64,
+    pub deviation: f64,
+    pub games: usize,
+    pub average_score: f64,
+}
+
+/// Rating of a player right after a rated game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RatingPoint {
+    pub game: u64,
+    pub rating: f64,
+    pub deviation: f64,
+}
+
+/// Keeps a finished local game for review and stats. It is not rated.
+#[server(prefix = "/api", endpoint = "record_game", input = Json)]
+pub async fn record_game(
+    record: GameRecord,
+    live: Option<LiveTicket>,
+) -> Result<GameRecord, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::error_template::AppError;
+    use crate::live::LiveGames;
+    use crate::ratings::ResultStore;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    let mut record = record;
+    record.chat = match live {
+        Some(ticket) => expect_context::<LiveGames>().chat_for(&ticket).await,
+        None => None,
+    }
+    .unwrap_or_default();
+    let store = expect_context::<ResultStore>();
+    store
+        .record_local(record, &user.name)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+#[server(prefix = "/api", endpoint = "leaderboard")]
+pub async fn get_leaderboard() -> Result<Vec<LeaderboardEntry>, ServerFnError> {
+    use crate::ratings::ResultStore;
+
+    Ok(expect_context::<ResultStore>().leaderboard().await)
+}
+
+#[server(prefix = "/api", endpoint = "rating_history")]
+pub async fn get_rating_history(name: String) -> Result<Vec<RatingPoint>, ServerFnError> {
+    use crate::ratings::ResultStore;
+
+    Ok(expect_context::<ResultStore>()
+        .player(&name)
+        .await
+        .map(|player| player.history)
+        .unwrap_or_default())
+}
+
+#[component]
+pub fn LeaderboardPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let entries = create_blocking_resource(|| (), |_| get_leaderboard());
+
+    let draw_rows = move || {
+        entries.get().map(|entries| match entries {
+            Ok(entries) if !entries.is_empty() => entries
+                .into_iter()
+                .enumerate()
+                .map(|(idx, entry)| {
+                    view! {
+                        <tr>
+                            <td class="px-2">{idx + 1}</td>
+                            <td class="px-2"><A href=format!("/player/{}", entry.name) class="underline">{entry.name}</A></td>
+                            <td class="px-2 text-right" title=format!("± {:.0}", entry.deviation)>{format!("{:.0}", entry.rating)}</td>
+                            <td class="px-2 text-right">{entry.games}</td>
+                            <td class="px-2 text-right">{format!("{:.1}", entry.average_score)}</td>
+                        </tr>
+                    }
+                })
+                .collect_view(),
+            _ => view! {
+                <tr><td colspan="5" class="px-2">{messages().leaderboard_empty}</td></tr>
+            }
+            .into_view(),
+        })
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().leaderboard}</h1>
+            <table class="my-3">
+                <thead>
+                    <tr>
+                        <th scope="col" class="px-2">{move || messages().rank}</th>
+                        <th scope="col" class="px-2">{move || messages().account_name}</th>
+                        <th scope="col" class="px-2">{move || messages().rating}</th>
+                        <th scope="col" class="px-2">{move || messages().games_played}</th>
+                        <th scope="col" class="px-2">{move || messages().average_score}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <Suspense fallback=|| ()>{draw_rows}</Suspense>
+                </tbody>
+            </table>
+        </section>
+    }
+}
@@ -0,0 +1,217 @@
+use super::i18n::*;
+#[cfg(feature = "ssr")]
+use crate::error_template::AppError;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+const FRENCH_WORDS: &str = include_str!("../../lexicon/fr.txt");
+const ENGLISH_WORDS: &str = include_str!("../../lexicon/en.txt");
+/// One `WORD<TAB>definition` per line; only some words have one.
+const FRENCH_DEFINITIONS: &str = include_str!("../../lexicon/fr.definitions.txt");
+const ENGLISH_DEFINITIONS: &str = include_str!("../../lexicon/en.definitions.txt");
+
+const FRENCH_ENDINGS: [&str; 13] = [
+    "S", "X", "E", "ES", "ER", "EZ", "ENT", "AIS", "AIT", "ONS", "EE", "EES", "ERA",
+];
+const ENGLISH_ENDINGS: [&str; 8] = ["S", "ES", "D", "ED", "ING", "ER", "ERS", "EST"];
+
+/// Word lists read from the lexicon directory at startup. They replace the
+/// built-in ones on the server only: the move preview, local games and their
+/// computer opponents run in the browser and keep the built-in lists.
+#[cfg(feature = "ssr")]
+static WORD_LISTS: OnceLock<Vec<(Language, Lexicon)>> = OnceLock::new();
+/// Definitions read from the lexicon directory, like [`WORD_LISTS`].
+#[cfg(feature = "ssr")]
+static DEFINITION_LISTS: OnceLock<Vec<(Language, String)>> = OnceLock::new();
+
+/// Reads the `<code>.txt` list and the `<code>.definitions.txt` definitions
+/// of each language found in `dir`, the others keep the built-in ones. A
+/// file that cannot be read or a list holding no word is refused, the
+/// reason being logged. Only the first call has an effect.
+#[cfg(feature = "ssr")]
+pub fn load_word_lists(dir: &std::path::Path) -> Result<(), AppError> {
+    let read = |name: String, language: Language| {
+        let path = dir.join(name);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Ok(Some(text)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => {
+                tracing::error!("could not read {}: {error}", path.display());
+                Err(AppError::LexiconUnavailable(language))
+            }
+        }
+    };
+    let mut lists = Vec::new();
+    let mut definitions = Vec::new();
+    for language in Language::ALL {
+        if let Some(words) = read(format!("{}.txt", language.code()), language)? {
+            let lexicon = Lexicon::from_words(&words);
+            if lexicon.is_empty() {
+                tracing::error!(
+                    "{} has no words",
+                    dir.join(format!("{}.txt", language.code())).display()
+                );
+                return Err(AppError::LexiconUnavailable(language));
+            }
+            lists.push((language, lexicon));
+        }
+        if let Some(text) = read(format!("{}.definitions.txt", language.code()), language)? {
+            definitions.push((language, text));
+        }
+    }
+    let _ = WORD_LISTS.set(lists);
+    let _ = DEFINITION_LISTS.set(definitions);
+    Ok(())
+}
+
+/// Set of playable words, stored uppercase without accents like the tiles.
+#[derive(Clone)]
+pub struct Lexicon(HashSet<String>);
+
+impl Lexicon {
+    /// One word per line, accents being dropped.
+    pub fn from_words(words: &str) -> Self {
+        Self(
+            words
+                .lines()
+                .map(normalize_word)
+                .filter(|word| !word.is_empty())
+                .collect(),
+        )
+    }
+
+    /// The configured list of `language`, or the built-in one, each parsed
+    /// on first use.
+    pub fn for_language(language: Language) -> &'static Self {
+        static FRENCH: OnceLock<Lexicon> = OnceLock::new();
+        static ENGLISH: OnceLock<Lexicon> = OnceLock::new();
+
+        #[cfg(feature = "ssr")]
+        if let Some((_, lexicon)) = WORD_LISTS
+            .get()
+            .and_then(|lists| lists.iter().find(|(listed, _)| *listed == language))
+        {
+            return lexicon;
+        }
+        let (lexicon, words) = match language {
+            Language::French => (&FRENCH, FRENCH_WORDS),
+            Language::English => (&ENGLISH, ENGLISH_WORDS),
+        };
+        lexicon.get_or_init(|| Self::from_words(words))
+    }
+
+    /// True when the list holds no word, which a configured lexicon is refused for.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    /// Blanks count as the letter they were played as, so a word spelled
+    /// with a lowercase one is found too.
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(&word.to_uppercase())
+    }
+
+    /// Other forms of `word` in the lexicon: the word with a common ending
+    /// added, or swapped for another one.
+    pub fn inflections(&self, word: &str, language: Language) -> Vec<String> {
+        let endings = match language {
+            Language::French => &FRENCH_ENDINGS[..],
+            Language::English => &ENGLISH_ENDINGS[..],
+        };
+        let word = normalize_word(word);
+        let stems = std::iter::once(word.as_str())
+            .chain(
+                endings
+                    .iter()
+                    .filter_map(|ending| word.strip_suffix(ending)),
+            )
+            .filter(|stem| !stem.is_empty())
+            .collect::<Vec<_>>();
+        let mut forms = stems
+            .iter()
+            .flat_map(|stem| {
+                std::iter::once(stem.to_string())
+                    .chain(endings.iter().map(move |ending| format!("{stem}{ending}")))
+            })
+            .filter(|form| *form != word && self.0.contains(form))
+            .collect::<Vec<_>>();
+        forms.sort();
+        forms.dedup();
+        forms
+    }
+}
+
+/// Uppercase without accents, the way words are spelled with tiles.
+pub fn normalize_word(word: &str) -> String {
+    word.trim()
+        .to_uppercase()
+        .chars()
+        .flat_map(|c| {
+            let plain = match c {
+                'À' | 'Â' | 'Ä' => "A",
+                'Ç' => "C",
+                'É' | 'È' | 'Ê' | 'Ë' => "E",
+                'Î' | 'Ï' => "I",
+                'Ô' | 'Ö' => "O",
+                'Ù' | 'Û' | 'Ü' => "U",
+                'Ÿ' => "Y",
+                'Œ' => "OE",
+                'Æ' => "AE",
+                _ => return vec![c],
+            };
+            plain.chars().collect()
+        })
+        .collect()
+}
+
+/// The definition shipped with the lexicon of `language`, if any, from the
+/// configured definitions or the built-in ones.
+pub fn definition(word: &str, language: Language) -> Option<&'static str> {
+    #[cfg(feature = "ssr")]
+    if let Some((_, definitions)) = DEFINITION_LISTS
+        .get()
+        .and_then(|lists| lists.iter().find(|(listed, _)| *listed == language))
+    {
+        return find_definition(definitions, word);
+    }
+    let definitions = match language {
+        Language::French => FRENCH_DEFINITIONS,
+        Language::English => ENGLISH_DEFINITIONS,
+    };
+    find_definition(definitions, word)
+}
+
+/// Looks `word` up in `WORD<TAB>definition` lines, accents aside.
+fn find_definition<'a>(definitions: &'a str, word: &str) -> Option<&'a str> {
+    let word = normalize_word(word);
+    definitions.lines().find_map(|line| {
+        let (entry, text) = line.split_once('\t')?;
+        (normalize_word(entry) == word).then(|| text.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_stored_without_accents() {
+        let lexicon = Lexicon::from_words("été\n  Cœur \n\nnaïf\n");
+        assert!(lexicon.contains("ETE"));
+        assert!(lexicon.contains("COEUR"));
+        assert!(lexicon.contains("NAIF"));
+        assert!(!lexicon.contains("été"));
+    }
+
+    #[test]
+    fn definitions_are_found_accents_aside() {
+        let definitions = "ÉTÉ\tSaison chaude.\nCHAT\tPetit félin.\n";
+        assert_eq!(find_definition(definitions, "ete"), Some("Saison chaude."));
+        assert_eq!(find_definition(definitions, "chat"), Some("Petit félin."));
+        assert_eq!(find_definition(definitions, "chien"), None);
+    }
+}
@@ -0,0 +1,33 @@
+use super::correspondence::*;
+use super::i18n::*;
+use super::spectate::*;
+use leptos::*;
+use leptos_router::*;
+
+/// Where every game starts: the games waiting on the signed-in user, the
+/// ones being played right now, and the ways to start a new one.
+#[component]
+pub fn LobbyPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().lobby_title}</h1>
+            <div class="my-3 flex gap-3">
+                <A href="/play" class="p-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800">
+                    {move || messages().lobby_play}
+                </A>
+                <A href="/games" class="p-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800">
+                    {move || messages().corr_new_game}
+                </A>
+            </div>
+
+            <h2 class="font-bold">{move || messages().corr_my_games}</h2>
+            <CorrespondenceList/>
+
+            <h2 class="font-bold">{move || messages().live_games}</h2>
+            <LiveGameList/>
+        </section>
+    }
+}
@@ -1,20 +1,57 @@
 use super::game::*;
+use super::i18n::*;
 use leptos::*;
+use std::collections::BTreeMap;
 
 #[component]
-pub fn Rack(rack_signal: RwSignal<Vec<Tile>>, bag_signal: RwSignal<Vec<Tile>>) -> impl IntoView {
+pub fn Rack(
+    rack_signal: RwSignal<Vec<Tile>>,
+    #[prop(into)] bag_count: Signal<usize>,
+    #[prop(into)] unseen_signal: Signal<Vec<Tile>>,
+    #[prop(into)] on_arrange: Callback<RackAction>,
+) -> impl IntoView {
     // let global_state = use_context::<GlobalState>().unwrap();
+    let language = use_language();
+    let messages = move || language().messages();
+    // Clicking a tile selects it, clicking another one moves it there.
+    let selected_signal = RwSignal::new(None::<usize>);
+
+    let select = move |idx: usize| match selected_signal.get_untracked() {
+        Some(selected) if selected == idx => selected_signal.set(None),
+        Some(selected) => {
+            on_arrange(RackAction::Move(selected, idx));
+            selected_signal.set(None)
+        }
+        None => selected_signal.set(Some(idx)),
+    };
+
+    let nudge = move |ev: ev::KeyboardEvent, idx: usize| {
+        let len = rack_signal.with_untracked(|rack| rack.len());
+        let target = match ev.key().as_str() {
+            "ArrowLeft" if idx > 0 => idx - 1,
+            "ArrowRight" if idx + 1 < len => idx + 1,
+            _ => return,
+        };
+        ev.prevent_default();
+        on_arrange(RackAction::Move(idx, target));
+        selected_signal.set(Some(target));
+    };
 
     let draw_rack = move || {
         if !rack_signal().is_empty() {
             rack_signal()
             .into_iter()
             // .rev()
-            .map(|t| {
+            .enumerate()
+            .map(|(idx, t)| {
+                let is_selected = move || selected_signal() == Some(idx);
                 view! {
-                    <li class="inline-block"><button class="tile-rack">
+                    <li class="inline-block"><button class="tile-rack" class=("tile-rack-sel", is_selected)
+                    aria-pressed=move || is_selected().to_string()
+                    on:click=move |_| select(idx)
+                    on:keydown=move |ev| nudge(ev, idx)>
                     {t.0}
-                    <sub class="text-xs" class=("hidden", move || t.1 == 0)>{t.1}</sub></button></li>
+                    <sub class="text-xs" class=("hidden", move || t.is_blank())>{t.1}</sub></button></li>
                 }
             })
             .collect_view()
@@ -31,27 +68,56 @@ pub fn Rack(rack_signal: RwSignal<Vec<Tile>>, bag_signal: RwSignal<Vec<Tile>>) -
     //     }
     // };
 
-    let draw_bag = move || {
-        if !bag_signal().is_empty() {
-            bag_signal()
-                .into_iter()
-                .map(|t| {
-                    view! {
-                        <li class="inline-block dark:text-zinc-300">{t.0}
-                        <sub class="text-xs" class=("hidden", move || t.1 != 0)>{t.1}</sub></li>
-                    }
-                })
-                .collect_view()
-        } else {
-            view! {}.into_view()
+    // Only what tournament players may track: counts per letter, never the bag order.
+    let draw_unseen = move || {
+        let mut counts = BTreeMap::new();
+        for tile in unseen_signal() {
+            *counts.entry(tile.0).or_insert(0) += 1;
         }
+        counts
+            .into_iter()
+            .map(|(letter, count)| {
+                let letter = if letter == ' ' { '?' } else { letter };
+                view! {
+                    <li class="inline-block px-1 dark:text-zinc-300">{letter}<sub class="text-xs">{count}</sub></li>
+                }
+            })
+            .collect_view()
+    };
+
+    let vowels_consonants = move || {
+        let unseen = unseen_signal();
+        let letters = unseen.iter().filter(|tile| !tile.is_blank());
+        let vowels = letters
+            .clone()
+            .filter(|tile| VOWELS.contains(tile.0))
+            .count();
+        let consonants = letters.count() - vowels;
+        fill(
+            messages().vowels_consonants,
+            &[&vowels.to_string(), &consonants.to_string()],
+        )
+    };
+
+    let arrange = move |action: RackAction| {
+        selected_signal.set(None);
+        on_arrange(action)
     };
 
     view! {
-        <ul>{draw_rack}</ul>
+        <ul aria-label=move || messages().rack>{draw_rack}</ul>
+        <div>
+            <button class="px-2 m-1 border-2 border-purple-400 rounded-md hover:border-purple-600 dark:text-zinc-300"
+            on:click=move |_| arrange(RackAction::Shuffle)>{move || messages().shuffle}</button>
+            <button class="px-2 m-1 border-2 border-purple-400 rounded-md hover:border-purple-600 dark:text-zinc-300"
+            on:click=move |_| arrange(RackAction::Alphabetical)>{move || messages().sort_alphabetical}</button>
+            <button class="px-2 m-1 border-2 border-purple-400 rounded-md hover:border-purple-600 dark:text-zinc-300"
+            on:click=move |_| arrange(RackAction::VowelsFirst)>{move || messages().sort_vowels_first}</button>
+        </div>
 
-        <p>{move || bag_signal().len()} " lettres dans le sac."</p>
-        <ul>{draw_bag}</ul>
-        // <ul><p>Sac:</p> {draw_bag}</ul>
+        <p>{move || fill(messages().tiles_in_bag, &[&bag_count().to_string()])}</p>
+        <h3 class="dark:text-zinc-300 font-bold">{move || messages().unseen_tiles}</h3>
+        <ul>{draw_unseen}</ul>
+        <p>{vowels_consonants}</p>
     }
 }
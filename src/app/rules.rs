@@ -0,0 +1,488 @@
+use super::board::*;
+use super::game::*;
+use super::grid::*;
+use super::i18n::*;
+use super::lexicon::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const RACK_SIZE: usize = 7;
+pub const BINGO_BONUS: usize = 50;
+pub const WRONG_CHALLENGE_BONUS: usize = 5;
+const CENTER: (usize, usize) = (8, 8);
+
+/// How words that are not in the lexicon are handled once a play is submitted.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum ChallengeRule {
+    /// Unknown words are refused before the play is committed.
+    #[default]
+    Void,
+    /// The opponent may challenge: an invalid play is withdrawn, a wrong
+    /// challenge costs the challenger their next turn.
+    Double,
+    /// The opponent may challenge: an invalid play is withdrawn, a wrong
+    /// challenge gives the player a 5 point bonus.
+    Single,
+}
+
+impl ChallengeRule {
+    pub const ALL: [ChallengeRule; 3] = [
+        ChallengeRule::Void,
+        ChallengeRule::Double,
+        ChallengeRule::Single,
+    ];
+
+    pub fn label(&self, language: Language) -> &'static str {
+        let messages = language.messages();
+        match self {
+            ChallengeRule::Void => messages.rule_void,
+            ChallengeRule::Double => messages.rule_double,
+            ChallengeRule::Single => messages.rule_single,
+        }
+    }
+
+    pub fn allows_challenge(&self) -> bool {
+        !matches!(self, ChallengeRule::Void)
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Serialize, Deserialize)]
+pub enum PlayError {
+    #[error("Aucune lettre n'a été posée.")]
+    NoTiles,
+    #[error("Une case est déjà occupée.")]
+    Occupied,
+    #[error("Deux lettres ont été posées sur la même case.")]
+    SameSquare,
+    #[error("Les lettres ne sont pas dans le chevalet.")]
+    NotInRack,
+    #[error("Un joker a été posé sans lettre.")]
+    BlankWithoutLetter,
+    #[error("Les lettres ne sont pas alignées.")]
+    NotInLine,
+    #[error("Le mot contient un trou.")]
+    HasGap,
+    #[error("Le premier mot doit passer par la case centrale.")]
+    OffCenter,
+    #[error("Le mot ne touche aucune lettre déjà posée.")]
+    NotConnected,
+    #[error("Un mot doit contenir au moins deux lettres.")]
+    TooShort,
+    #[error("Mot inconnu : {}", .0.join(", "))]
+    UnknownWords(Vec<String>),
+    #[error("La partie est terminée.")]
+    GameOver,
+    #[error("Il ne reste pas assez de lettres dans le sac pour échanger.")]
+    BagTooSmall,
+}
+
+impl PlayError {
+    pub fn message(&self, language: Language) -> String {
+        let messages = language.messages();
+        match self {
+            PlayError::NoTiles => messages.error_no_tiles.to_string(),
+            PlayError::Occupied => messages.error_occupied.to_string(),
+            PlayError::SameSquare => messages.error_same_square.to_string(),
+            PlayError::NotInRack => messages.error_not_in_rack.to_string(),
+            PlayError::BlankWithoutLetter => messages.error_blank_without_letter.to_string(),
+            PlayError::NotInLine => messages.error_not_in_line.to_string(),
+            PlayError::HasGap => messages.error_has_gap.to_string(),
+            PlayError::OffCenter => messages.error_off_center.to_string(),
+            PlayError::NotConnected => messages.error_not_connected.to_string(),
+            PlayError::TooShort => messages.error_too_short.to_string(),
+            PlayError::UnknownWords(words) => {
+                fill(messages.error_unknown_words, &[&words.join(", ")])
+            }
+            PlayError::GameOver => messages.error_game_over.to_string(),
+            PlayError::BagTooSmall => messages.error_bag_too_small.to_string(),
+        }
+    }
+
+    /// The kebab-case code the error travels as from a server function.
+    pub fn code(&self) -> String {
+        match self {
+            PlayError::NoTiles => "no-tiles".to_string(),
+            PlayError::Occupied => "occupied".to_string(),
+            PlayError::SameSquare => "same-square".to_string(),
+            PlayError::NotInRack => "not-in-rack".to_string(),
+            PlayError::BlankWithoutLetter => "blank-without-letter".to_string(),
+            PlayError::NotInLine => "not-in-line".to_string(),
+            PlayError::HasGap => "has-gap".to_string(),
+            PlayError::OffCenter => "off-center".to_string(),
+            PlayError::NotConnected => "not-connected".to_string(),
+            PlayError::TooShort => "too-short".to_string(),
+            PlayError::UnknownWords(words) => format!("unknown-words-{}", words.join(",")),
+            PlayError::GameOver => "game-over".to_string(),
+            PlayError::BagTooSmall => "bag-too-small".to_string(),
+        }
+    }
+}
+
+impl FromStr for PlayError {
+    type Err = ();
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "no-tiles" => Ok(PlayError::NoTiles),
+            "occupied" => Ok(PlayError::Occupied),
+            "same-square" => Ok(PlayError::SameSquare),
+            "not-in-rack" => Ok(PlayError::NotInRack),
+            "blank-without-letter" => Ok(PlayError::BlankWithoutLetter),
+            "not-in-line" => Ok(PlayError::NotInLine),
+            "has-gap" => Ok(PlayError::HasGap),
+            "off-center" => Ok(PlayError::OffCenter),
+            "not-connected" => Ok(PlayError::NotConnected),
+            "too-short" => Ok(PlayError::TooShort),
+            "game-over" => Ok(PlayError::GameOver),
+            "bag-too-small" => Ok(PlayError::BagTooSmall),
+            _ => code
+                .strip_prefix("unknown-words-")
+                .map(|words| PlayError::UnknownWords(words.split(',').map(String::from).collect()))
+                .ok_or(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub coords: Vec<(usize, usize)>,
+    pub score: usize,
+}
+
+/// A legal placement of tiles, with the words it forms and their score.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Play {
+    pub placements: Vec<((usize, usize), Tile)>,
+    pub words: Vec<Word>,
+    pub score: usize,
+    pub bingo: bool,
+}
+
+impl Play {
+    pub fn unknown_words(&self, lexicon: &Lexicon) -> Vec<String> {
+        self.words
+            .iter()
+            .filter(|word| !lexicon.contains(&word.text))
+            .map(|word| word.text.clone())
+            .collect()
+    }
+
+    pub fn words_text(&self) -> String {
+        self.words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Checks the placement rules and scores a play, without looking words up.
+pub fn evaluate(
+    grid: &Grid,
+    placements: &[((usize, usize), Tile)],
+    rack: &[Tile],
+) -> Result<Play, PlayError> {
+    if placements.is_empty() {
+        return Err(PlayError::NoTiles);
+    }
+    if placements
+        .iter()
+        .any(|(coord, _)| grid.get(*coord).is_some())
+    {
+        return Err(PlayError::Occupied);
+    }
+    let squares = placements
+        .iter()
+        .map(|(coord, _)| *coord)
+        .collect::<HashSet<_>>();
+    if squares.len() != placements.len() {
+        return Err(PlayError::SameSquare);
+    }
+
+    let mut rack_tiles = rack.to_vec();
+    for (_, tile) in placements {
+        if *tile == Tile::BLANK {
+            return Err(PlayError::BlankWithoutLetter);
+        }
+        if tile.is_blank() && Tile::blank_as(tile.0) != Some(*tile) {
+            return Err(PlayError::NotInRack);
+        }
+        match rack_tiles.iter().position(|t| *t == tile.drawn()) {
+            Some(idx) => {
+                rack_tiles.remove(idx);
+            }
+            None => return Err(PlayError::NotInRack),
+        }
+    }
+
+    let (first_row, first_col) = placements[0].0;
+    let same_row = placements.iter().all(|(coord, _)| coord.0 == first_row);
+    let same_col = placements.iter().all(|(coord, _)| coord.1 == first_col);
+    if !same_row && !same_col {
+        return Err(PlayError::NotInLine);
+    }
+
+    let mut staged = *grid;
+    for (coord, tile) in placements {
+        staged.set(*coord, Some(*tile));
+    }
+
+    // A single tile forms a word in whichever direction it has neighbours.
+    let direction = if same_row && (placements.len() > 1 || !same_col) {
+        (0, 1)
+    } else if placements.len() > 1 {
+        (1, 0)
+    } else if has_neighbour(&staged, placements[0].0, (0, 1)) {
+        (0, 1)
+    } else {
+        (1, 0)
+    };
+
+    let main = word_coords(&staged, placements[0].0, direction);
+    if placements.iter().any(|(coord, _)| !main.contains(coord)) {
+        return Err(PlayError::HasGap);
+    }
+
+    if grid.is_empty() {
+        if !placements.iter().any(|(coord, _)| *coord == CENTER) {
+            return Err(PlayError::OffCenter);
+        }
+    } else if !placements.iter().any(|(coord, _)| {
+        neighbours(*coord)
+            .into_iter()
+            .any(|n| grid.get(n).is_some())
+    }) {
+        return Err(PlayError::NotConnected);
+    }
+
+    let cross = (direction.1, direction.0);
+    let kinds = grid.layout().kinds();
+    let mut words = Vec::new();
+    if main.len() > 1 {
+        words.push(score_word(&staged, &kinds, main, placements));
+    }
+    for (coord, _) in placements {
+        let coords = word_coords(&staged, *coord, cross);
+        if coords.len() > 1 {
+            words.push(score_word(&staged, &kinds, coords, placements));
+        }
+    }
+    if words.is_empty() {
+        return Err(PlayError::TooShort);
+    }
+
+    let bingo = squares.len() == RACK_SIZE;
+    let mut score = words.iter().map(|word| word.score).sum();
+    if bingo {
+        score += BINGO_BONUS;
+    }
+
+    Ok(Play {
+        placements: placements.to_vec(),
+        words,
+        score,
+        bingo,
+    })
+}
+
+fn neighbours(coord: (usize, usize)) -> Vec<(usize, usize)> {
+    let (row, col) = coord;
+    [
+        (row.wrapping_sub(1), col),
+        (row + 1, col),
+        (row, col.wrapping_sub(1)),
+        (row, col + 1),
+    ]
+    .into_iter()
+    .filter(|n| Grid::in_bounds(*n))
+    .collect()
+}
+
+fn has_neighbour(grid: &Grid, coord: (usize, usize), direction: (usize, usize)) -> bool {
+    let before = (
+        coord.0.wrapping_sub(direction.0),
+        coord.1.wrapping_sub(direction.1),
+    );
+    let after = (coord.0 + direction.0, coord.1 + direction.1);
+    grid.get(before).is_some() || grid.get(after).is_some()
+}
+
+/// Every occupied coordinate of the word running through `coord` in `direction`.
+fn word_coords(
+    grid: &Grid,
+    coord: (usize, usize),
+    direction: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut start = coord;
+    loop {
+        let previous = (
+            start.0.wrapping_sub(direction.0),
+            start.1.wrapping_sub(direction.1),
+        );
+        if grid.get(previous).is_none() {
+            break;
+        }
+        start = previous;
+    }
+
+    let mut coords = Vec::new();
+    let mut current = start;
+    while grid.get(current).is_some() {
+        coords.push(current);
+        current = (current.0 + direction.0, current.1 + direction.1);
+    }
+    coords
+}
+
+/// Premium squares only count for the tiles placed during this play.
+fn score_word(
+    grid: &Grid,
+    kinds: &[[CellKind; BOARD_SIZE]; BOARD_SIZE],
+    coords: Vec<(usize, usize)>,
+    placements: &[((usize, usize), Tile)],
+) -> Word {
+    let mut text = String::new();
+    let mut score = 0;
+    let mut multiplier = 1;
+    for coord in &coords {
+        let tile = grid.get(*coord).unwrap();
+        text.push(tile.letter());
+        if placements.iter().any(|(c, _)| c == coord) {
+            match kinds[coord.0 - 1][coord.1 - 1] {
+                CellKind::DoubleLetter => score += tile.1 * 2,
+                CellKind::TripleLetter => score += tile.1 * 3,
+                CellKind::DoubleWord => {
+                    score += tile.1;
+                    multiplier *= 2
+                }
+                CellKind::TripleWord => {
+                    score += tile.1;
+                    multiplier *= 3
+                }
+                _ => score += tile.1,
+            }
+        } else {
+            score += tile.1;
+        }
+    }
+    Word {
+        text,
+        coords,
+        score: score * multiplier,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(word: &str, first_col: usize) -> Vec<((usize, usize), Tile)> {
+        word.chars()
+            .enumerate()
+            .map(|(idx, c)| ((8, first_col + idx), Tile(c, 1)))
+            .collect()
+    }
+
+    fn rack_of(placements: &[((usize, usize), Tile)]) -> Vec<Tile> {
+        placements.iter().map(|(_, tile)| tile.drawn()).collect()
+    }
+
+    #[test]
+    fn first_word_doubles_on_the_centre() {
+        let placements = vec![
+            ((8, 7), Tile('C', 3)),
+            ((8, 8), Tile('A', 1)),
+            ((8, 9), Tile('T', 1)),
+        ];
+        let play = evaluate(&Grid::new(), &placements, &rack_of(&placements)).unwrap();
+        assert_eq!(play.words_text(), "CAT");
+        assert_eq!(play.score, 10);
+        assert!(!play.bingo);
+    }
+
+    #[test]
+    fn blank_scores_nothing_but_spells_its_letter() {
+        let placements = vec![
+            ((8, 7), Tile::blank_as('c').unwrap()),
+            ((8, 8), Tile('A', 1)),
+            ((8, 9), Tile('T', 1)),
+        ];
+        let play = evaluate(&Grid::new(), &placements, &rack_of(&placements)).unwrap();
+        assert_eq!(play.words_text(), "CAT");
+        assert_eq!(play.score, 4);
+    }
+
+    #[test]
+    fn seven_tiles_earn_the_bingo_bonus() {
+        let placements = row("RETAINS", 5);
+        let play = evaluate(&Grid::new(), &placements, &rack_of(&placements)).unwrap();
+        assert!(play.bingo);
+        assert_eq!(play.score, 7 * 2 + BINGO_BONUS);
+    }
+
+    #[test]
+    fn a_square_placed_twice_is_refused() {
+        let placements = vec![((8, 8), Tile('A', 1)); RACK_SIZE];
+        let rack = vec![Tile('A', 1); RACK_SIZE];
+        assert_eq!(
+            evaluate(&Grid::new(), &placements, &rack),
+            Err(PlayError::SameSquare)
+        );
+    }
+
+    #[test]
+    fn tiles_must_come_from_the_rack() {
+        let placements = row("CAT", 7);
+        let rack = vec![Tile('C', 1), Tile('A', 1)];
+        assert_eq!(
+            evaluate(&Grid::new(), &placements, &rack),
+            Err(PlayError::NotInRack)
+        );
+        let blank = vec![((8, 8), Tile::BLANK), ((8, 9), Tile('A', 1))];
+        assert_eq!(
+            evaluate(&Grid::new(), &blank, &rack_of(&blank)),
+            Err(PlayError::BlankWithoutLetter)
+        );
+    }
+
+    #[test]
+    fn placement_rules() {
+        let grid = Grid::new();
+        let off_centre = row("CAT", 1);
+        assert_eq!(
+            evaluate(&grid, &off_centre, &rack_of(&off_centre)),
+            Err(PlayError::OffCenter)
+        );
+        let bent = vec![((8, 8), Tile('A', 1)), ((9, 9), Tile('T', 1))];
+        assert_eq!(
+            evaluate(&grid, &bent, &rack_of(&bent)),
+            Err(PlayError::NotInLine)
+        );
+        let gap = vec![((8, 8), Tile('A', 1)), ((8, 10), Tile('T', 1))];
+        assert_eq!(
+            evaluate(&grid, &gap, &rack_of(&gap)),
+            Err(PlayError::HasGap)
+        );
+    }
+
+    #[test]
+    fn later_words_must_touch_the_board() {
+        let mut grid = Grid::new();
+        for (coord, tile) in row("CAT", 7) {
+            grid.set(coord, Some(tile));
+        }
+        let apart = row("DOG", 1);
+        assert_eq!(
+            evaluate(&grid, &apart, &rack_of(&apart)),
+            Err(PlayError::NotConnected)
+        );
+        // S hooks CAT into CATS, the premiums under CAT no longer count.
+        let hook = vec![((8, 10), Tile('S', 1))];
+        let play = evaluate(&grid, &hook, &rack_of(&hook)).unwrap();
+        assert_eq!(play.words_text(), "CATS");
+        assert_eq!(play.score, 4);
+    }
+}
@@ -0,0 +1,269 @@
+use super::board::*;
+use super::i18n::*;
+use super::rules::*;
+use leptos::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_SEATS: usize = 2;
+pub const MAX_SEATS: usize = 4;
+
+/// Who plays a seat of a local game.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum SeatKind {
+    #[default]
+    Human,
+    /// Plays the best scoring move it finds.
+    Computer,
+}
+
+impl SeatKind {
+    pub const ALL: [SeatKind; 2] = [SeatKind::Human, SeatKind::Computer];
+
+    pub fn label(&self, language: Language) -> &'static str {
+        let messages = language.messages();
+        match self {
+            SeatKind::Human => messages.seat_human,
+            SeatKind::Computer => messages.seat_computer,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeatConfig {
+    pub name: String,
+    pub kind: SeatKind,
+}
+
+/// Thinking time of each player for the whole game.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    Minutes(u64),
+}
+
+impl TimeControl {
+    pub const ALL: [TimeControl; 4] = [
+        TimeControl::Unlimited,
+        TimeControl::Minutes(10),
+        TimeControl::Minutes(25),
+        TimeControl::Minutes(50),
+    ];
+
+    pub fn seconds(&self) -> Option<u64> {
+        match self {
+            TimeControl::Unlimited => None,
+            TimeControl::Minutes(minutes) => Some(minutes * 60),
+        }
+    }
+
+    pub fn label(&self, language: Language) -> String {
+        let messages = language.messages();
+        match self {
+            TimeControl::Unlimited => messages.time_unlimited.to_string(),
+            TimeControl::Minutes(minutes) => fill(messages.time_minutes, &[&minutes.to_string()]),
+        }
+    }
+}
+
+/// Everything chosen before a local game starts. The game is created from
+/// it and keeps it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub seats: Vec<SeatConfig>,
+    /// Decides the tile distribution.
+    pub language: Language,
+    /// Language of the words allowed, the one of the tiles unless chosen otherwise.
+    pub lexicon: Language,
+    pub layout: BoardLayout,
+    pub time: TimeControl,
+    pub rule: ChallengeRule,
+}
+
+impl GameConfig {
+    /// Two human players on the standard board, without a clock.
+    pub fn new(language: Language) -> Self {
+        Self {
+            seats: (0..MIN_SEATS)
+                .map(|_| SeatConfig {
+                    name: String::new(),
+                    kind: SeatKind::Human,
+                })
+                .collect(),
+            language,
+            lexicon: language,
+            layout: BoardLayout::Standard,
+            time: TimeControl::Unlimited,
+            rule: ChallengeRule::default(),
+        }
+    }
+
+    pub fn has_computer(&self) -> bool {
+        self.seats
+            .iter()
+            .any(|seat| seat.kind == SeatKind::Computer)
+    }
+
+    /// Name of the seat at `idx` when none is typed in.
+    pub fn default_name(&self, idx: usize, language: Language) -> String {
+        let messages = language.messages();
+        let template = match self.seats.get(idx).map(|seat| seat.kind) {
+            Some(SeatKind::Computer) => messages.computer_name,
+            _ => messages.player_name,
+        };
+        fill(template, &[&(idx + 1).to_string()])
+    }
+
+    /// The same setup with blank names replaced by the default ones.
+    pub fn named(mut self, language: Language) -> Self {
+        for idx in 0..self.seats.len() {
+            let name = self.seats[idx].name.trim().to_string();
+            self.seats[idx].name = if name.is_empty() {
+                self.default_name(idx, language)
+            } else {
+                name
+            };
+        }
+        self
+    }
+}
+
+/// Form choosing the players and rules of a local game.
+#[component]
+pub fn GameSetup(#[prop(into)] on_start: Callback<GameConfig>) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let config = RwSignal::new(GameConfig::new(language.get_untracked()));
+    let seat_count = create_memo(move |_| config.with(|config| config.seats.len()));
+
+    let draw_seats = move || {
+        (0..seat_count())
+            .map(|idx| {
+                let kind = move || config.with(|config| config.seats.get(idx).map(|seat| seat.kind).unwrap_or_default());
+                let kind_options = SeatKind::ALL
+                    .into_iter()
+                    .enumerate()
+                    .map(|(value, option)| {
+                        view! { <option value=value selected=move || kind() == option>{move || option.label(language())}</option> }
+                    })
+                    .collect_view();
+                view! {
+                    <li class="flex gap-2 my-1 items-center">
+                        <select class="text-zinc-800" aria-label=move || fill(messages().setup_seat, &[&(idx + 1).to_string()])
+                            on:change=move |ev| {
+                                if let Some(kind) = event_target_value(&ev).parse::<usize>().ok().and_then(|value| SeatKind::ALL.get(value)) {
+                                    config.update(|config| config.seats[idx].kind = *kind);
+                                }
+                            }>{kind_options}</select>
+                        <input type="text" class="p-1 text-zinc-800" maxlength="20"
+                            placeholder=move || config.with(|config| config.default_name(idx, language()))
+                            prop:value=move || config.with(|config| config.seats.get(idx).map(|seat| seat.name.clone()).unwrap_or_default())
+                            on:input=move |ev| config.update(|config| config.seats[idx].name = event_target_value(&ev))/>
+                        <button class="underline" class=("hidden", move || seat_count() <= MIN_SEATS)
+                            on:click=move |_| config.update(|config| {
+                                config.seats.remove(idx);
+                            })>{move || messages().setup_remove_player}</button>
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    let language_options = move |selected: fn(&GameConfig) -> Language| {
+        Language::ALL
+            .into_iter()
+            .map(|option| {
+                view! { <option value=option.code() selected=move || config.with(|config| selected(config) == option)>{option.name()}</option> }
+            })
+            .collect_view()
+    };
+
+    let layout_options = move || {
+        let shuffled =
+            move || config.with(|config| matches!(config.layout, BoardLayout::Shuffled(_)));
+        view! {
+            <option value="standard" selected=move || !shuffled()>{move || messages().layout_standard}</option>
+            <option value="shuffled" selected=shuffled>{move || messages().layout_shuffled}</option>
+        }
+    };
+
+    let time_options = move || {
+        TimeControl::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(idx, time)| {
+                view! { <option value=idx selected=move || config.with(|config| config.time == time)>{move || time.label(language())}</option> }
+            })
+            .collect_view()
+    };
+
+    let rule_options = move || {
+        ChallengeRule::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(idx, rule)| {
+                view! { <option value=idx selected=move || config.with(|config| config.rule == rule)>{move || rule.label(language())}</option> }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().setup_title}</h1>
+
+            <h2 class="font-bold mt-3">{move || messages().setup_players}</h2>
+            <ol>{draw_seats}</ol>
+            <button class="underline" class=("hidden", move || seat_count() >= MAX_SEATS)
+                on:click=move |_| config.update(|config| config.seats.push(SeatConfig { name: String::new(), kind: SeatKind::Computer }))>
+                {move || messages().setup_add_player}
+            </button>
+
+            <label class="block my-2">{move || messages().setup_tiles}
+                <select class="text-zinc-800" on:change=move |ev| {
+                    if let Some(language) = Language::from_code(&event_target_value(&ev)) {
+                        config.update(|config| {
+                            config.language = language;
+                            config.lexicon = language;
+                        });
+                    }
+                }>{language_options(|config| config.language)}</select>
+            </label>
+            <label class="block my-2">{move || messages().setup_lexicon}
+                <select class="text-zinc-800" on:change=move |ev| {
+                    if let Some(language) = Language::from_code(&event_target_value(&ev)) {
+                        config.update(|config| config.lexicon = language);
+                    }
+                }>{language_options(|config| config.lexicon)}</select>
+            </label>
+            <label class="block my-2">{move || messages().setup_layout}
+                <select class="text-zinc-800" on:change=move |ev| {
+                    let layout = match event_target_value(&ev).as_str() {
+                        "shuffled" => BoardLayout::Shuffled(thread_rng().gen()),
+                        _ => BoardLayout::Standard,
+                    };
+                    config.update(|config| config.layout = layout);
+                }>{layout_options}</select>
+            </label>
+            <label class="block my-2">{move || messages().setup_time}
+                <select class="text-zinc-800" on:change=move |ev| {
+                    if let Some(time) = event_target_value(&ev).parse::<usize>().ok().and_then(|idx| TimeControl::ALL.get(idx)) {
+                        config.update(|config| config.time = *time);
+                    }
+                }>{time_options}</select>
+            </label>
+            <label class="block my-2">{move || messages().challenge_rule}
+                <select class="text-zinc-800" on:change=move |ev| {
+                    if let Some(rule) = event_target_value(&ev).parse::<usize>().ok().and_then(|idx| ChallengeRule::ALL.get(idx)) {
+                        config.update(|config| config.rule = *rule);
+                    }
+                }>{rule_options}</select>
+            </label>
+
+            <button class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800"
+                on:click=move |_| on_start(config.get_untracked().named(language.get_untracked()))>
+                {move || messages().setup_start}
+            </button>
+        </section>
+    }
+}
@@ -0,0 +1,289 @@
+use super::board::*;
+use super::chat::*;
+use super::game::*;
+use super::grid::*;
+use super::i18n::*;
+use leptos::server_fn::codec::Json;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// Milliseconds between two refreshes of a spectated game.
+#[cfg(feature = "hydrate")]
+const REFRESH_INTERVAL: u64 = 2000;
+/// Delays offered to commentators, in rounds of moves. Racks are never shown
+/// live, whatever the page asks for.
+const DELAYS: [usize; 3] = [1, 2, MAX_DELAY_ROUNDS];
+pub const MAX_DELAY_ROUNDS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeatView {
+    pub name: String,
+    pub score: usize,
+    pub rack: Vec<Tile>,
+}
+
+/// What spectators see of a live game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub grid: Grid,
+    pub seats: Vec<SeatView>,
+    pub current: usize,
+    pub bag: usize,
+    pub turns: usize,
+    pub finished: bool,
+    #[serde(default)]
+    pub origins: Vec<TileOrigin>,
+}
+
+impl GameSnapshot {
+    pub fn without_racks(&self) -> Self {
+        let mut snapshot = self.clone();
+        for seat in &mut snapshot.seats {
+            seat.rack.clear();
+        }
+        snapshot
+    }
+}
+
+impl From<&GameState> for GameSnapshot {
+    fn from(state: &GameState) -> Self {
+        Self {
+            grid: state.grid,
+            seats: state
+                .players
+                .iter()
+                .map(|player| SeatView {
+                    name: player.name.clone(),
+                    score: player.score,
+                    rack: player.rack.clone(),
+                })
+                .collect(),
+            current: state.current,
+            bag: state.bag.len(),
+            turns: state.history.len(),
+            finished: state.finished,
+            origins: tile_origins(
+                &state.history,
+                &state
+                    .players
+                    .iter()
+                    .map(|player| player.name.clone())
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+}
+
+/// Returned when a game is first published, needed to publish its next moves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiveTicket {
+    pub id: u64,
+    pub token: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiveSummary {
+    pub id: u64,
+    pub seats: Vec<SeatView>,
+    pub turns: usize,
+}
+
+/// Publishes a move of a game played on the page of the signed-in user.
+#[server(prefix = "/api", endpoint = "publish_game", input = Json)]
+pub async fn publish_game(
+    ticket: Option<LiveTicket>,
+    snapshot: GameSnapshot,
+) -> Result<LiveTicket, ServerFnError> {
+    use crate::auth::current_user;
+    use crate::error_template::AppError;
+    use crate::live::LiveGames;
+
+    let user = current_user()
+        .ok_or(AppError::NotSignedIn)
+        .map_err(AppError::into_server)?;
+    expect_context::<LiveGames>()
+        .publish(&user.name, ticket, snapshot)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+#[server(prefix = "/api", endpoint = "live_games")]
+pub async fn get_live_games() -> Result<Vec<LiveSummary>, ServerFnError> {
+    use crate::live::LiveGames;
+
+    Ok(expect_context::<LiveGames>().list().await)
+}
+
+#[server(prefix = "/api", endpoint = "spectate")]
+pub async fn spectate_game(
+    id: u64,
+    show_racks: bool,
+    rounds: usize,
+) -> Result<Option<GameSnapshot>, ServerFnError> {
+    use crate::live::LiveGames;
+
+    Ok(expect_context::<LiveGames>()
+        .spectate(id, show_racks, rounds)
+        .await)
+}
+
+/// Bumps the returned signal every [`REFRESH_INTERVAL`] once hydrated.
+pub fn refresh_ticker() -> RwSignal<usize> {
+    let tick = RwSignal::new(0);
+    #[cfg(feature = "hydrate")]
+    {
+        let interval = set_interval_with_handle(
+            move || tick.update(|tick| *tick += 1),
+            std::time::Duration::from_millis(REFRESH_INTERVAL),
+        );
+        if let Ok(interval) = interval {
+            on_cleanup(move || interval.clear());
+        }
+    }
+    tick
+}
+
+/// Games being played right now, kept up to date.
+#[component]
+pub fn LiveGameList() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let tick = refresh_ticker();
+    let games = create_resource(tick, |_| get_live_games());
+
+    let draw_games = move || {
+        games.get().map(|games| match games {
+            Ok(games) if !games.is_empty() => games
+                .into_iter()
+                .map(|game| {
+                    let players = game
+                        .seats
+                        .iter()
+                        .map(|seat| fill(messages().player_score, &[&seat.name, &seat.score.to_string()]))
+                        .collect::<Vec<_>>()
+                        .join(" — ");
+                    view! {
+                        <li><A href=format!("/live/{}", game.id) class="underline">{players}</A></li>
+                    }
+                })
+                .collect_view(),
+            _ => view! { <li>{messages().live_empty}</li> }.into_view(),
+        })
+    };
+
+    view! {
+        <ul class="my-3"><Transition fallback=|| ()>{draw_games}</Transition></ul>
+    }
+}
+
+#[component]
+pub fn LiveGamesPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().live_games}</h1>
+            <LiveGameList/>
+        </section>
+    }
+}
+
+/// Read-only view of a live game: every square is locked and nothing is
+/// ever placed from the (empty) rack.
+#[component]
+pub fn SpectatePage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let params = use_params_map();
+    let id = move || params.with(|params| params.get("id").and_then(|id| id.parse::<u64>().ok()));
+
+    let show_racks = RwSignal::new(false);
+    let delay = RwSignal::new(DELAYS[0]);
+    let tick = refresh_ticker();
+    let snapshot = create_resource(
+        move || (id(), show_racks(), delay(), tick()),
+        |(id, show_racks, delay, _)| async move {
+            match id {
+                Some(id) => spectate_game(id, show_racks, delay).await.ok().flatten(),
+                None => None,
+            }
+        },
+    );
+
+    let board = BoardStore::new();
+    let coord_signal = RwSignal::new((0, 0));
+    let rack_signal = RwSignal::new(Vec::new());
+
+    create_effect(move |_| {
+        let Some(snapshot) = snapshot.get().flatten() else {
+            return;
+        };
+        board.show(&snapshot.grid, None, &snapshot.origins);
+        board.lock();
+    });
+
+    let draw_seats = move || {
+        snapshot.get().flatten().map(|snapshot| {
+            let messages = messages();
+            let seats = snapshot
+                .seats
+                .iter()
+                .enumerate()
+                .map(|(idx, seat)| {
+                    let rack = seat.rack.iter().map(|tile| if tile.is_blank() { '?' } else { tile.0 }).collect::<String>();
+                    view! {
+                        <li class=("font-bold", idx == snapshot.current)>
+                            {fill(messages.player_score, &[&seat.name, &seat.score.to_string()])}
+                            <span class="px-2 font-mono" class=("hidden", rack.is_empty())>{rack}</span>
+                        </li>
+                    }
+                })
+                .collect_view();
+            view! {
+                <ul>{seats}</ul>
+                <p>{fill(messages.tiles_in_bag, &[&snapshot.bag.to_string()])}</p>
+                <p class=("hidden", !snapshot.finished)>{messages.game_over}</p>
+            }
+        })
+    };
+
+    let delay_options = move || {
+        DELAYS
+            .into_iter()
+            .map(|option| {
+                view! {
+                    <option value=option selected=move || delay() == option>{move || fill(messages().live_delay_rounds, &[&option.to_string()])}</option>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <main class="container mx-auto pt-2 lg:p-5 grid grid-rows-2 lg:grid-rows-1 lg:grid-cols-2">
+            <div class="flex justify-center">
+                <Board board coord_signal rack_signal/>
+            </div>
+
+            <div class="pl-5 dark:text-zinc-300">
+                <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().spectating}</h1>
+
+                <label class="block my-2">
+                    <input type="checkbox" prop:checked=show_racks on:change=move |ev| show_racks.set(event_target_checked(&ev))/>
+                    " "{move || messages().live_show_racks}
+                </label>
+                <label class="block my-2" class=("hidden", move || !show_racks())>{move || messages().live_delay}
+                    <select on:change=move |ev| {
+                        if let Ok(chosen) = event_target_value(&ev).parse::<usize>() {
+                            delay.set(chosen);
+                        }
+                    }>{delay_options}</select>
+                </label>
+
+                <Transition fallback=|| ()>{draw_seats}</Transition>
+                <ChatPanel room=Signal::derive(move || id().map(ChatRoom::Live))/>
+            </div>
+        </main>
+    }
+}
@@ -0,0 +1,291 @@
+use super::board::*;
+use super::game::Action;
+use super::game::*;
+use super::i18n::*;
+use super::leaderboard::*;
+use super::rules::*;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const CHART_WIDTH: f64 = 320.0;
+const CHART_HEIGHT: f64 = 120.0;
+/// Premium squares shown in the usage chart.
+const TOP_PREMIUMS: usize = 8;
+
+/// Figures computed from a player's recorded games.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    /// Plays that stayed on the board.
+    pub moves: usize,
+    pub move_points: usize,
+    pub bingos: usize,
+    pub best_word: Option<Word>,
+    /// Words and score of the highest-scoring play.
+    pub best_move: Option<(String, usize)>,
+    pub exchanges: usize,
+    pub tiles_exchanged: usize,
+    /// Times each premium square was covered, most used first.
+    pub premium_squares: Vec<((usize, usize), usize)>,
+    /// Final score of every game, oldest first.
+    pub game_scores: Vec<usize>,
+    pub rating_history: Vec<RatingPoint>,
+}
+
+impl PlayerStats {
+    /// Gathers the seats played by the account `name` in `games`.
+    pub fn from_games(name: &str, games: &[GameRecord]) -> Self {
+        let key = name.to_lowercase();
+        let mut stats = PlayerStats {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut premiums = HashMap::new();
+
+        for game in games {
+            let Some(seat) = game.seats.iter().position(|seat| {
+                seat.account
+                    .as_ref()
+                    .is_some_and(|account| account.to_lowercase() == key)
+            }) else {
+                continue;
+            };
+            let score = game.seats[seat].score;
+            stats.games += 1;
+            stats.game_scores.push(score);
+            if (0..game.seats.len()).all(|other| other == seat || game.outcome(seat, other) == 1.0)
+            {
+                stats.wins += 1;
+            }
+
+            for turn in game.history.iter().filter(|turn| turn.player == seat) {
+                match &turn.action {
+                    Action::Play(play) => {
+                        stats.moves += 1;
+                        stats.move_points += turn.score;
+                        if play.bingo {
+                            stats.bingos += 1;
+                        }
+                        if stats
+                            .best_move
+                            .as_ref()
+                            .is_none_or(|(_, best)| turn.score > *best)
+                        {
+                            stats.best_move = Some((play.words_text(), turn.score));
+                        }
+                        for word in &play.words {
+                            if stats
+                                .best_word
+                                .as_ref()
+                                .is_none_or(|best| word.score > best.score)
+                            {
+                                stats.best_word = Some(word.clone());
+                            }
+                        }
+                        for (coord, _) in &play.placements {
+                            if game.layout().kind(*coord).is_premium() {
+                                *premiums.entry(*coord).or_insert(0) += 1;
+                            }
+                        }
+                    }
+                    Action::Exchange(count) => {
+                        stats.exchanges += 1;
+                        stats.tiles_exchanged += count;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        stats.premium_squares = premiums.into_iter().collect();
+        stats
+            .premium_squares
+            .sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        stats
+    }
+
+    pub fn average_move(&self) -> f64 {
+        self.move_points as f64 / self.moves.max(1) as f64
+    }
+
+    pub fn bingo_rate(&self) -> f64 {
+        self.bingos as f64 / self.moves.max(1) as f64
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    /// Tiles swapped per exchange.
+    pub fn average_exchanged(&self) -> f64 {
+        self.tiles_exchanged as f64 / self.exchanges.max(1) as f64
+    }
+}
+
+#[server(prefix = "/api", endpoint = "player_stats")]
+pub async fn get_player_stats(name: String) -> Result<Option<PlayerStats>, ServerFnError> {
+    use crate::ratings::ResultStore;
+
+    let store = expect_context::<ResultStore>();
+    let Some(player) = store.player(&name).await else {
+        return Ok(None);
+    };
+    let mut stats = PlayerStats::from_games(&player.name, &store.games_of(&name).await);
+    stats.rating_history = player.history;
+    Ok(Some(stats))
+}
+
+/// The `:name` of the player routes.
+#[derive(Params, Clone, PartialEq)]
+pub struct PlayerParams {
+    pub name: String,
+}
+
+#[component]
+pub fn StatsPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let params = use_params::<PlayerParams>();
+    let name = move || {
+        params.with(|params| {
+            params
+                .as_ref()
+                .map(|params| params.name.clone())
+                .unwrap_or_default()
+        })
+    };
+    let stats = create_blocking_resource(name, get_player_stats);
+
+    let draw_stats = move || {
+        stats.get().map(|stats| match stats {
+            Ok(Some(stats)) => view! { <StatsView stats/> }.into_view(),
+            _ => view! { <p>{messages().stats_unknown_player}</p> }.into_view(),
+        })
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || fill(messages().stats_title, &[&name()])}</h1>
+            <Suspense fallback=|| ()>{draw_stats}</Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn StatsView(stats: PlayerStats) -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let percent = |rate: f64| format!("{:.0} %", rate * 100.0);
+
+    let best_word = stats
+        .best_word
+        .as_ref()
+        .map(|word| {
+            fill(
+                messages().stats_points,
+                &[&word.text, &word.score.to_string()],
+            )
+        })
+        .unwrap_or_else(|| "—".to_string());
+    let best_move = stats
+        .best_move
+        .as_ref()
+        .map(|(words, score)| fill(messages().stats_points, &[words, &score.to_string()]))
+        .unwrap_or_else(|| "—".to_string());
+    let scores = stats
+        .game_scores
+        .iter()
+        .map(|score| *score as f64)
+        .collect::<Vec<_>>();
+    let ratings = stats
+        .rating_history
+        .iter()
+        .map(|point| point.rating)
+        .collect::<Vec<_>>();
+    let premiums = stats
+        .premium_squares
+        .iter()
+        .take(TOP_PREMIUMS)
+        .map(|(coord, count)| (square_name(*coord), *count as f64))
+        .collect::<Vec<_>>();
+
+    view! {
+        <dl class="grid grid-cols-2 gap-x-4 my-3 max-w-md">
+            <dt>{move || messages().games_played}</dt><dd>{stats.games}</dd>
+            <dt>{move || messages().stats_win_rate}</dt><dd>{percent(stats.win_rate())}</dd>
+            <dt>{move || messages().stats_average_move}</dt><dd>{format!("{:.1}", stats.average_move())}</dd>
+            <dt>{move || messages().stats_bingo_rate}</dt><dd>{percent(stats.bingo_rate())}</dd>
+            <dt>{move || messages().stats_best_word}</dt><dd>{best_word}</dd>
+            <dt>{move || messages().stats_best_move}</dt><dd>{best_move}</dd>
+            <dt>{move || messages().stats_average_exchanged}</dt><dd>{format!("{:.1}", stats.average_exchanged())}</dd>
+        </dl>
+        <h2 class="font-bold">{move || messages().stats_scores_chart}</h2>
+        <LineChart values=scores label=messages().stats_scores_chart/>
+        <h2 class="font-bold">{move || messages().stats_rating_chart}</h2>
+        <LineChart values=ratings label=messages().stats_rating_chart/>
+        <h2 class="font-bold">{move || messages().stats_premiums_chart}</h2>
+        <BarChart bars=premiums label=messages().stats_premiums_chart/>
+    }
+}
+
+/// Values plotted left to right and scaled to the chart height.
+#[component]
+fn LineChart(values: Vec<f64>, label: &'static str) -> impl IntoView {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(1.0);
+    let step = CHART_WIDTH / (values.len().max(2) - 1) as f64;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            let y = CHART_HEIGHT - (value - min) / range * (CHART_HEIGHT - 10.0) - 5.0;
+            format!("{:.1},{:.1}", idx as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let summary = match values.is_empty() {
+        true => label.to_string(),
+        false => format!("{label}: {min:.0} – {max:.0}"),
+    };
+
+    view! {
+        <svg viewBox=format!("0 0 {CHART_WIDTH} {CHART_HEIGHT}") class="w-full max-w-md my-2 stroke-purple-500" role="img" aria-label=summary>
+            <line x1="0" y1=CHART_HEIGHT x2=CHART_WIDTH y2=CHART_HEIGHT class="stroke-zinc-400"/>
+            <polyline points=points fill="none" stroke-width="2"/>
+        </svg>
+    }
+}
+
+#[component]
+fn BarChart(bars: Vec<(String, f64)>, label: &'static str) -> impl IntoView {
+    let max = bars.iter().map(|(_, value)| *value).fold(1.0, f64::max);
+    let width = CHART_WIDTH / bars.len().max(1) as f64;
+    let summary = bars
+        .iter()
+        .map(|(name, value)| format!("{name} {value}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let draw_bars = bars
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (name, value))| {
+            let height = value / max * (CHART_HEIGHT - 20.0);
+            let x = idx as f64 * width;
+            view! {
+                <rect x=x + 2.0 y=CHART_HEIGHT - 15.0 - height width=width - 4.0 height=height class="fill-purple-400"/>
+                <text x=x + width / 2.0 y=CHART_HEIGHT - 2.0 text-anchor="middle" font-size="10" class="fill-current">{name}</text>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <svg viewBox=format!("0 0 {CHART_WIDTH} {CHART_HEIGHT}") class="w-full max-w-md my-2" role="img" aria-label=format!("{label}: {summary}")>
+            {draw_bars}
+        </svg>
+    }
+}
@@ -0,0 +1,245 @@
+use super::game::*;
+use super::i18n::*;
+use super::lexicon::*;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Results returned by one search, best first.
+pub const MAX_STUDY_RESULTS: usize = 200;
+/// Racks and patterns longer than this are cut.
+const MAX_QUERY_LEN: usize = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum StudyMode {
+    /// Words using every tile of the rack.
+    #[default]
+    Anagrams,
+    /// Words using some of the tiles of the rack.
+    Subwords,
+    /// Words matching a pattern where `?` stands for any letter.
+    Pattern,
+}
+
+impl StudyMode {
+    pub const ALL: [StudyMode; 3] = [StudyMode::Anagrams, StudyMode::Subwords, StudyMode::Pattern];
+
+    pub fn label(&self, language: Language) -> &'static str {
+        let messages = language.messages();
+        match self {
+            StudyMode::Anagrams => messages.study_anagrams,
+            StudyMode::Subwords => messages.study_subwords,
+            StudyMode::Pattern => messages.study_pattern,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StudyWord {
+    pub word: String,
+    /// Face value of the tiles, blanks counting zero.
+    pub score: usize,
+    /// Positions in `word` played with a blank.
+    pub blanks: Vec<usize>,
+}
+
+impl StudyWord {
+    /// Letters played with a blank are shown in lowercase, as on score sheets.
+    pub fn display(&self) -> String {
+        self.word
+            .chars()
+            .enumerate()
+            .map(|(idx, c)| {
+                if self.blanks.contains(&idx) {
+                    c.to_ascii_lowercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+}
+
+/// Spells `word` with `rack`, real tiles first so blanks cover as few
+/// points as possible; `None` when the rack is too short of letters.
+fn spell(word: &str, rack: &HashMap<char, usize>, blanks: usize) -> Option<Vec<usize>> {
+    let mut rack = rack.clone();
+    let mut blanks_left = blanks;
+    let mut used = Vec::new();
+    for (idx, c) in word.chars().enumerate() {
+        match rack.get_mut(&c).filter(|count| **count > 0) {
+            Some(count) => *count -= 1,
+            None if blanks_left > 0 => {
+                blanks_left -= 1;
+                used.push(idx);
+            }
+            None => return None,
+        }
+    }
+    Some(used)
+}
+
+/// Searches the lexicon of `language` for a rack (`?` for blanks) or a
+/// pattern, longest and best scoring words first.
+pub fn find_words(query: &str, mode: StudyMode, language: Language) -> Vec<StudyWord> {
+    let query = normalize_word(query)
+        .chars()
+        .filter(|c| c.is_ascii_uppercase() || *c == '?')
+        .take(MAX_QUERY_LEN)
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let values = Bag::values(language);
+    let score = |word: &str, blanks: &[usize]| {
+        word.chars()
+            .enumerate()
+            .filter(|(idx, _)| !blanks.contains(idx))
+            .map(|(_, c)| values.get(&c).copied().unwrap_or_default())
+            .sum::<usize>()
+    };
+
+    let mut rack = HashMap::new();
+    for c in query.iter().filter(|c| **c != '?') {
+        *rack.entry(*c).or_insert(0) += 1;
+    }
+    let blanks = query.iter().filter(|c| **c == '?').count();
+
+    let lexicon = Lexicon::for_language(language);
+    let mut words = lexicon
+        .words()
+        .filter_map(|word| {
+            let length = word.chars().count();
+            let blanks = match mode {
+                StudyMode::Anagrams if length == query.len() => spell(word, &rack, blanks)?,
+                StudyMode::Subwords if (2..=query.len()).contains(&length) => {
+                    spell(word, &rack, blanks)?
+                }
+                StudyMode::Pattern if length == query.len() => {
+                    let matches = word
+                        .chars()
+                        .zip(&query)
+                        .all(|(c, wanted)| *wanted == '?' || c == *wanted);
+                    if !matches {
+                        return None;
+                    }
+                    Vec::new()
+                }
+                _ => return None,
+            };
+            Some(StudyWord {
+                word: word.to_string(),
+                score: score(word, &blanks),
+                blanks,
+            })
+        })
+        .collect::<Vec<_>>();
+    words.sort_by(|a, b| {
+        b.word
+            .len()
+            .cmp(&a.word.len())
+            .then(b.score.cmp(&a.score))
+            .then(a.word.cmp(&b.word))
+    });
+    words.truncate(MAX_STUDY_RESULTS);
+    words
+}
+
+#[server(prefix = "/api", endpoint = "study")]
+pub async fn get_study_words(
+    query: String,
+    mode: StudyMode,
+    language: String,
+) -> Result<Vec<StudyWord>, ServerFnError> {
+    let language = Language::from_code(&language).unwrap_or_default();
+    Ok(find_words(&query, mode, language))
+}
+
+#[component]
+pub fn StudyPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let query = RwSignal::new(String::new());
+    let mode = RwSignal::new(StudyMode::default());
+    let search = create_action(
+        move |(query, mode, language): &(String, StudyMode, Language)| {
+            get_study_words(query.clone(), *mode, language.code().to_string())
+        },
+    );
+
+    let submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        search.dispatch((
+            query.get_untracked(),
+            mode.get_untracked(),
+            language.get_untracked(),
+        ));
+    };
+
+    let draw_results = move || {
+        search.value().get().map(|words| match words {
+            Ok(words) if !words.is_empty() => {
+                let messages = messages();
+                let count = fill(messages.study_count, &[&words.len().to_string()]);
+                let rows = words
+                    .into_iter()
+                    .map(|word| {
+                        view! {
+                            <tr>
+                                <td class="px-2 font-mono">{word.display()}</td>
+                                <td class="px-2 text-right">{word.word.chars().count()}</td>
+                                <td class="px-2 text-right">{word.score}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view();
+                view! {
+                    <p>{count}</p>
+                    <table class="my-3">
+                        <thead>
+                            <tr>
+                                <th scope="col" class="px-2">{messages.study_word}</th>
+                                <th scope="col" class="px-2">{messages.study_length}</th>
+                                <th scope="col" class="px-2">{messages.study_score}</th>
+                            </tr>
+                        </thead>
+                        <tbody>{rows}</tbody>
+                    </table>
+                }
+                .into_view()
+            }
+            Ok(_) => view! { <p>{messages().study_nothing}</p> }.into_view(),
+            Err(_) => view! { <p>{messages().check_failed}</p> }.into_view(),
+        })
+    };
+
+    let mode_options = move || {
+        StudyMode::ALL
+            .into_iter()
+            .map(|option| {
+                view! {
+                    <label class="px-2">
+                        <input type="radio" name="mode" prop:checked=move || mode() == option
+                        on:change=move |_| mode.set(option)/>
+                        " "{move || option.label(language())}
+                    </label>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().study_title}</h1>
+            <p>{move || messages().study_help}</p>
+            <form on:submit=submit class="my-3">
+                <fieldset class="my-2">{mode_options}</fieldset>
+                <input type="text" class="p-1 text-zinc-800 font-mono uppercase" required maxlength=MAX_QUERY_LEN
+                aria-label=move || messages().study_query placeholder=move || messages().study_query
+                prop:value=query on:input=move |ev| query.set(event_target_value(&ev))/>
+                <button type="submit" class="px-2 underline">{move || messages().study_search}</button>
+            </form>
+            <div aria-live="polite">{draw_results}</div>
+        </section>
+    }
+}
@@ -0,0 +1,488 @@
+use super::account::*;
+use super::i18n::*;
+use crate::error_template::{error_codes, AppError};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+/// Most rounds a Swiss tournament can be planned for.
+pub const MAX_ROUNDS: usize = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum PairingSystem {
+    /// Players on the same score meet, never twice if it can be helped.
+    #[default]
+    Swiss,
+    /// Everyone meets everyone once.
+    RoundRobin,
+}
+
+impl PairingSystem {
+    pub const ALL: [PairingSystem; 2] = [PairingSystem::Swiss, PairingSystem::RoundRobin];
+
+    pub fn label(&self, language: Language) -> &'static str {
+        let messages = language.messages();
+        match self {
+            PairingSystem::Swiss => messages.tournament_swiss,
+            PairingSystem::RoundRobin => messages.tournament_round_robin,
+        }
+    }
+}
+
+/// One table of a round; `second` is `None` for a bye.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub first: String,
+    pub second: Option<String>,
+    /// The correspondence game played for this pairing.
+    pub game: Option<u64>,
+    /// Final scores, in seat order.
+    pub scores: Option<(usize, usize)>,
+    /// Seat that lost on time, whatever the scores.
+    pub forfeited: Option<usize>,
+    /// The scores were entered by hand rather than taken from the game.
+    #[serde(default)]
+    pub entered: bool,
+}
+
+impl Pairing {
+    /// The result came from the game played for the table and stays as it is.
+    pub fn synced(&self) -> bool {
+        self.game.is_some() && self.scores.is_some() && !self.entered
+    }
+
+    /// Match points of each seat once the result is in.
+    pub fn points(&self) -> Option<(f64, f64)> {
+        if self.second.is_none() {
+            return Some((1.0, 0.0));
+        }
+        let (first, second) = self.scores?;
+        Some(match self.forfeited {
+            Some(0) => (0.0, 1.0),
+            Some(_) => (1.0, 0.0),
+            None if first > second => (1.0, 0.0),
+            None if first < second => (0.0, 1.0),
+            None => (0.5, 0.5),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    /// One per win, half per draw; a bye counts as a win.
+    pub points: f64,
+    /// Points scored minus points conceded.
+    pub spread: i64,
+    /// Sum of the opponents' points, the first tiebreaker after spread.
+    pub buchholz: f64,
+    pub played: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TournamentSummary {
+    pub id: u64,
+    pub name: String,
+    pub system: PairingSystem,
+    pub players: usize,
+    pub round: usize,
+    pub rounds: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TournamentView {
+    pub id: u64,
+    pub name: String,
+    pub system: PairingSystem,
+    pub organizer: String,
+    /// Planned rounds; a round robin plans them when the first is paired.
+    pub rounds: usize,
+    pub players: Vec<String>,
+    /// Pairings of each round, first round first.
+    pub pairings: Vec<Vec<Pairing>>,
+    /// Best first.
+    pub standings: Vec<Standing>,
+    pub finished: bool,
+}
+
+error_codes! {
+    /// Errors sent back by the tournament server functions, as their `Display` code.
+    pub enum TournamentError {
+        NotSignedIn = "not-signed-in" => chat_not_signed_in,
+        UnknownTournament = "unknown-tournament" => tournament_unknown,
+        InvalidName = "invalid-name" => tournament_invalid_name,
+        InvalidRounds = "invalid-rounds" => tournament_invalid_rounds,
+        NotOrganizer = "not-organizer" => tournament_not_organizer,
+        UnknownPlayer = "unknown-player" => corr_unknown_opponent,
+        Started = "started" => tournament_started,
+        NotEnoughPlayers = "not-enough-players" => tournament_not_enough_players,
+        RoundInProgress = "round-in-progress" => tournament_round_in_progress,
+        Finished = "finished" => tournament_finished,
+        UnknownPairing = "unknown-pairing" => tournament_unknown_pairing,
+        ResultSynced = "result-synced" => tournament_result_synced,
+        Storage = "storage" => corr_storage,
+    }
+}
+
+#[server(prefix = "/api", endpoint = "create_tournament")]
+pub async fn create_tournament(
+    name: String,
+    system: usize,
+    rounds: usize,
+) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::tournaments::TournamentStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(TournamentError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    let system = PairingSystem::ALL.get(system).copied().unwrap_or_default();
+    let id = expect_context::<TournamentStore>()
+        .create(&user.name, &name, system, rounds)
+        .await
+        .map_err(|error| AppError::from(error).into_server())?;
+    leptos_axum::redirect(&format!("/tournaments/{id}"));
+    Ok(())
+}
+
+#[server(prefix = "/api", endpoint = "tournaments")]
+pub async fn list_tournaments() -> Result<Vec<TournamentSummary>, ServerFnError> {
+    use crate::tournaments::TournamentStore;
+
+    Ok(expect_context::<TournamentStore>().list().await)
+}
+
+#[server(prefix = "/api", endpoint = "tournament")]
+pub async fn get_tournament(id: u64) -> Result<TournamentView, ServerFnError> {
+    use crate::tournaments::TournamentStore;
+
+    expect_context::<TournamentStore>()
+        .view(id)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Registers `name`, or the signed-in user when it is empty. Only the
+/// organizer may register someone else.
+#[server(prefix = "/api", endpoint = "join_tournament")]
+pub async fn join_tournament(id: u64, name: String) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::tournaments::TournamentStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(TournamentError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    let name = if name.trim().is_empty() {
+        user.name.clone()
+    } else {
+        name
+    };
+    expect_context::<TournamentStore>()
+        .register(id, &user.name, &name)
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+#[server(prefix = "/api", endpoint = "pair_round")]
+pub async fn pair_round(id: u64) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::tournaments::TournamentStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(TournamentError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    expect_context::<TournamentStore>()
+        .pair(id, &user.name, initial_language())
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+/// Enters the result of a table played outside the site. Only the organizer
+/// may correct a result or settle a table that has a game.
+#[server(prefix = "/api", endpoint = "record_pairing")]
+pub async fn record_pairing(
+    id: u64,
+    round: usize,
+    table: usize,
+    first: usize,
+    second: usize,
+) -> Result<(), ServerFnError> {
+    use crate::auth::current_user;
+    use crate::tournaments::TournamentStore;
+
+    let user = current_user()
+        .ok_or(AppError::from(TournamentError::NotSignedIn))
+        .map_err(AppError::into_server)?;
+    expect_context::<TournamentStore>()
+        .record(id, &user.name, round, table, (first, second))
+        .await
+        .map_err(|error| AppError::from(error).into_server())
+}
+
+#[component]
+pub fn TournamentsPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let create = create_server_action::<CreateTournament>();
+    let tournaments = create_resource(|| (), |_| list_tournaments());
+
+    let error = move || {
+        create
+            .value()
+            .get()
+            .and_then(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    let draw_rows = move || {
+        tournaments.get().map(|tournaments| match tournaments {
+            Ok(tournaments) if !tournaments.is_empty() => tournaments
+                .into_iter()
+                .map(|tournament| {
+                    view! {
+                        <tr>
+                            <td class="px-2"><A href=format!("/tournaments/{}", tournament.id) class="underline">{tournament.name}</A></td>
+                            <td class="px-2">{tournament.system.label(language())}</td>
+                            <td class="px-2 text-right">{tournament.players}</td>
+                            <td class="px-2 text-right">{format!("{} / {}", tournament.round, tournament.rounds)}</td>
+                        </tr>
+                    }
+                })
+                .collect_view(),
+            _ => view! {
+                <tr><td colspan="4" class="px-2">{messages().tournament_none}</td></tr>
+            }
+            .into_view(),
+        })
+    };
+
+    let system_options = move || {
+        PairingSystem::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(idx, system)| view! { <option value=idx>{move || system.label(language())}</option> })
+            .collect_view()
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().tournaments}</h1>
+            <table class="my-3">
+                <thead>
+                    <tr>
+                        <th scope="col" class="px-2">{move || messages().tournament_name}</th>
+                        <th scope="col" class="px-2">{move || messages().tournament_system}</th>
+                        <th scope="col" class="px-2">{move || messages().tournament_players}</th>
+                        <th scope="col" class="px-2">{move || messages().tournament_round}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <Transition fallback=|| ()>{draw_rows}</Transition>
+                </tbody>
+            </table>
+
+            <h2 class="font-bold">{move || messages().tournament_new}</h2>
+            <ActionForm action=create>
+                <label class="block my-2">{move || messages().tournament_name}
+                    <input type="text" name="name" required maxlength="60" class="block p-1 text-zinc-800"/>
+                </label>
+                <label class="block my-2">{move || messages().tournament_system}
+                    <select name="system" class="text-zinc-800">{system_options}</select>
+                </label>
+                <label class="block my-2">{move || messages().tournament_swiss_rounds}
+                    <input type="number" name="rounds" min="1" max=MAX_ROUNDS value="5" class="block w-20 p-1 text-zinc-800"/>
+                </label>
+                <button type="submit" class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800">
+                    {move || messages().tournament_create}
+                </button>
+            </ActionForm>
+            <p role="alert">{error}</p>
+        </section>
+    }
+}
+
+#[component]
+pub fn TournamentPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let current_user = use_current_user();
+    let params = use_params_map();
+    let id = move || params.with(|params| params.get("id").and_then(|id| id.parse::<u64>().ok()));
+
+    let join = create_server_action::<JoinTournament>();
+    let pair = create_server_action::<PairRound>();
+    let record = create_server_action::<RecordPairing>();
+    let tournament = create_resource(
+        move || (id(), join.version()(), pair.version()(), record.version()()),
+        |(id, _, _, _)| async move {
+            match id {
+                Some(id) => get_tournament(id).await,
+                None => Err(ServerFnError::ServerError(
+                    AppError::from(TournamentError::UnknownTournament).to_string(),
+                )),
+            }
+        },
+    );
+
+    let error = move || {
+        [join.value().get(), pair.value().get(), record.value().get()]
+            .into_iter()
+            .flatten()
+            .find_map(|result| result.err())
+            .map(|error| AppError::from_server(&error).message(language()))
+    };
+
+    let draw_tournament = move || {
+        tournament.get().map(|tournament| match tournament {
+            Ok(tournament) => {
+                let messages = messages();
+                let user = current_user().map(|user| user.name.to_lowercase());
+                let is_organizer = user.as_ref() == Some(&tournament.organizer.to_lowercase());
+                let registered = tournament
+                    .players
+                    .iter()
+                    .any(|player| Some(player.to_lowercase()) == user);
+                let started = !tournament.pairings.is_empty();
+                let tournament_id = tournament.id;
+
+                let standings = tournament
+                    .standings
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, standing)| {
+                        view! {
+                            <tr>
+                                <td class="px-2">{idx + 1}</td>
+                                <td class="px-2">{standing.name.clone()}</td>
+                                <td class="px-2 text-right">{standing.points}</td>
+                                <td class="px-2 text-right">{format!("{:+}", standing.spread)}</td>
+                                <td class="px-2 text-right">{standing.buchholz}</td>
+                                <td class="px-2 text-right">{standing.played}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view();
+
+                let rounds = tournament
+                    .pairings
+                    .clone()
+                    .into_iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(round, pairings)| {
+                        let tables = pairings
+                            .into_iter()
+                            .enumerate()
+                            .map(|(table, pairing)| {
+                                let is_player = [Some(&pairing.first), pairing.second.as_ref()]
+                                    .into_iter()
+                                    .flatten()
+                                    .any(|name| Some(name.to_lowercase()) == user);
+                                let result = match (&pairing.second, pairing.scores) {
+                                    (None, _) => messages.tournament_bye.to_string(),
+                                    (Some(_), Some((first, second))) => format!("{first} – {second}"),
+                                    (Some(_), None) => String::new(),
+                                };
+                                let game = pairing.game.map(|game| {
+                                    view! { <A href=format!("/game/{game}") class="underline">{messages.tournament_game}</A> }
+                                });
+                                let can_record = pairing.second.is_some()
+                                    && !pairing.synced()
+                                    && (is_organizer || (is_player && pairing.game.is_none() && pairing.scores.is_none()));
+                                view! {
+                                    <tr>
+                                        <td class="px-2">{table + 1}</td>
+                                        <td class="px-2">{pairing.first.clone()}</td>
+                                        <td class="px-2">{pairing.second.clone().unwrap_or_default()}</td>
+                                        <td class="px-2">{game}</td>
+                                        <td class="px-2">{result}</td>
+                                        <td class="px-2" class=("hidden", !can_record)>
+                                            <ActionForm action=record class="flex gap-1">
+                                                <input type="hidden" name="id" value=tournament_id/>
+                                                <input type="hidden" name="round" value=round/>
+                                                <input type="hidden" name="table" value=table/>
+                                                <input type="number" name="first" min="0" required class="w-16 p-1 text-zinc-800" aria-label=pairing.first.clone()/>
+                                                <input type="number" name="second" min="0" required class="w-16 p-1 text-zinc-800" aria-label=pairing.second.clone().unwrap_or_default()/>
+                                                <button type="submit" class="px-2 underline">{messages.tournament_record}</button>
+                                            </ActionForm>
+                                        </td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view();
+                        view! {
+                            <h3 class="font-bold mt-3">{fill(messages.tournament_round_number, &[&(round + 1).to_string()])}</h3>
+                            <table>
+                                <thead>
+                                    <tr>
+                                        <th scope="col" class="px-2">{messages.tournament_table}</th>
+                                        <th scope="col" class="px-2">{messages.tournament_first}</th>
+                                        <th scope="col" class="px-2">{messages.tournament_second}</th>
+                                        <th scope="col" class="px-2">{messages.tournament_game}</th>
+                                        <th scope="col" class="px-2">{messages.tournament_result}</th>
+                                    </tr>
+                                </thead>
+                                <tbody>{tables}</tbody>
+                            </table>
+                        }
+                    })
+                    .collect_view();
+
+                view! {
+                    <h1 class="text-2xl font-bold dark:text-yellow-100">{tournament.name.clone()}</h1>
+                    <p>
+                        {tournament.system.label(language())}" — "
+                        {fill(messages.tournament_progress, &[&tournament.pairings.len().to_string(), &tournament.rounds.to_string()])}" — "
+                        {fill(messages.tournament_organized_by, &[&tournament.organizer])}
+                    </p>
+                    <p class=("hidden", !tournament.finished)>{messages.tournament_finished}</p>
+
+                    <h2 class="font-bold mt-3">{messages.tournament_players}</h2>
+                    <p>{tournament.players.join(", ")}</p>
+                    <div class=("hidden", started || user.is_none() || (registered && !is_organizer))>
+                        <ActionForm action=join>
+                            <input type="hidden" name="id" value=tournament_id/>
+                            <input type="text" name="name" class="p-1 text-zinc-800" class=("hidden", !is_organizer)
+                            aria-label=messages.corr_opponent placeholder=messages.corr_opponent/>
+                            <button type="submit" class="px-2 underline">{messages.tournament_join}</button>
+                        </ActionForm>
+                    </div>
+                    <div class=("hidden", !is_organizer || tournament.finished)>
+                        <ActionForm action=pair>
+                            <input type="hidden" name="id" value=tournament_id/>
+                            <button type="submit" class="p-3 my-3 border-2 border-purple-400 bg-purple-300 rounded-md hover:border-purple-600 hover:shadow-lg text-zinc-800">
+                                {messages.tournament_pair}
+                            </button>
+                        </ActionForm>
+                    </div>
+
+                    <h2 class="font-bold mt-3">{messages.tournament_standings}</h2>
+                    <table>
+                        <thead>
+                            <tr>
+                                <th scope="col" class="px-2">{messages.rank}</th>
+                                <th scope="col" class="px-2">{messages.account_name}</th>
+                                <th scope="col" class="px-2">{messages.tournament_points}</th>
+                                <th scope="col" class="px-2">{messages.tournament_spread}</th>
+                                <th scope="col" class="px-2">{messages.tournament_buchholz}</th>
+                                <th scope="col" class="px-2">{messages.games_played}</th>
+                            </tr>
+                        </thead>
+                        <tbody>{standings}</tbody>
+                    </table>
+
+                    {rounds}
+                }
+                .into_view()
+            }
+            Err(error) => view! { <p>{AppError::from_server(&error).message(language())}</p> }.into_view(),
+        })
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <Transition fallback=|| ()>{draw_tournament}</Transition>
+            <p role="alert">{error}</p>
+        </section>
+    }
+}
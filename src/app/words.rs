@@ -0,0 +1,110 @@
+use super::i18n::*;
+use super::lexicon::*;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Words looked up by one request, the rest is ignored.
+pub const MAX_CHECKED_WORDS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordCheck {
+    /// As spelled with tiles: uppercase, without accents.
+    pub word: String,
+    pub valid: bool,
+    pub inflections: Vec<String>,
+    pub definition: Option<String>,
+}
+
+/// Looks up every word of `text`, separated by spaces or commas, in the
+/// lexicon of `language`.
+pub fn check_words(text: &str, language: Language) -> Vec<WordCheck> {
+    let lexicon = Lexicon::for_language(language);
+    let mut seen = HashSet::new();
+    let mut words = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    words.retain(|word| seen.insert(word.clone()));
+    words
+        .into_iter()
+        .take(MAX_CHECKED_WORDS)
+        .map(|word| WordCheck {
+            valid: lexicon.contains(&word),
+            inflections: lexicon.inflections(&word, language),
+            definition: definition(&word, language).map(str::to_string),
+            word,
+        })
+        .collect()
+}
+
+#[server(prefix = "/api", endpoint = "check_words")]
+pub async fn get_word_checks(
+    text: String,
+    language: String,
+) -> Result<Vec<WordCheck>, ServerFnError> {
+    use crate::telemetry::Metrics;
+
+    let language = Language::from_code(&language).unwrap_or_default();
+    Ok(expect_context::<Metrics>().time_lookup(|| check_words(&text, language)))
+}
+
+/// Settles arguments over a word: validity in the lexicon of the chosen
+/// language, related forms and the definition when one ships.
+#[component]
+pub fn CheckWordPage() -> impl IntoView {
+    let language = use_language();
+    let messages = move || language().messages();
+    let text = RwSignal::new(String::new());
+    let check = create_action(move |(text, language): &(String, Language)| {
+        get_word_checks(text.clone(), language.code().to_string())
+    });
+
+    let submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        check.dispatch((text.get_untracked(), language.get_untracked()));
+    };
+
+    let draw_results = move || {
+        check.value().get().map(|checks| match checks {
+            Ok(checks) => checks
+                .into_iter()
+                .map(|check| {
+                    let messages = messages();
+                    let inflections = if check.inflections.is_empty() {
+                        String::new()
+                    } else {
+                        fill(messages.check_inflections, &[&check.inflections.join(", ")])
+                    };
+                    view! {
+                        <li class="my-2">
+                            <span class="font-mono font-bold">{check.word}</span>" "
+                            {if check.valid {
+                                view! { <span class="text-green-600">{format!("✅ {}", messages.check_valid)}</span> }
+                            } else {
+                                view! { <span class="text-red-600">{format!("❌ {}", messages.check_invalid)}</span> }
+                            }}
+                            <p class="text-sm">{inflections}</p>
+                            <p class="text-sm italic">{check.definition}</p>
+                        </li>
+                    }
+                })
+                .collect_view(),
+            Err(_) => view! { <li>{messages().check_failed}</li> }.into_view(),
+        })
+    };
+
+    view! {
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || messages().check_title}</h1>
+            <p>{move || fill(messages().check_help, &[language().name()])}</p>
+            <form on:submit=submit class="my-3">
+                <input type="text" class="p-1 text-zinc-800 w-80" required aria-label=move || messages().check_words
+                placeholder=move || messages().check_words prop:value=text on:input=move |ev| text.set(event_target_value(&ev))/>
+                <button type="submit" class="px-2 underline">{move || messages().check_submit}</button>
+            </form>
+            <ul aria-live="polite">{draw_results}</ul>
+        </section>
+    }
+}
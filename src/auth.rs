@@ -0,0 +1,376 @@
+use crate::app::{AuthError, User};
+use crate::config::ServerConfig;
+use crate::json_file::JsonFile;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use blake2::digest::Mac;
+use blake2::{Blake2b512, Blake2bMac512, Digest};
+use rand::{thread_rng, Rng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+pub const SESSION_COOKIE: &str = "krabbels_session";
+const SESSION_MAX_AGE: u32 = 30 * 24 * 60 * 60;
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// The user resolved from the session cookie, stored in the request extensions.
+#[derive(Clone)]
+pub struct CurrentUser(pub Option<User>);
+
+#[derive(Serialize, Deserialize)]
+struct Account {
+    name: String,
+    password_hash: String,
+    /// Where correspondence notifications are sent, if given.
+    #[serde(default)]
+    email: Option<String>,
+}
+
+/// Keyed by lowercase name so names are unique regardless of case, and
+/// stored as a plain list.
+#[derive(Default)]
+struct Users(HashMap<String, Account>);
+
+impl Serialize for Users {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.values())
+    }
+}
+
+impl<'de> Deserialize<'de> for Users {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let accounts = Vec::<Account>::deserialize(deserializer)?;
+        Ok(Users(
+            accounts
+                .into_iter()
+                .map(|account| (account.name.to_lowercase(), account))
+                .collect(),
+        ))
+    }
+}
+
+/// Tokens signed out before they expired, with their expiry. Kept on disk so
+/// that they stay refused after a restart, and dropped once expired.
+type Revoked = HashMap<String, u64>;
+
+struct Accounts {
+    users: JsonFile<Users>,
+    /// Signs the session tokens, which name the account and when they expire.
+    session_key: [u8; 64],
+    revoked: JsonFile<Revoked>,
+}
+
+impl Accounts {
+    fn signature(&self, payload: &str) -> Blake2bMac512 {
+        let mut mac = Blake2bMac512::new_from_slice(&self.session_key).expect("64 byte key");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// `<hex name>.<expiry>.<hex signature>`, the name being the account key.
+    fn open_session(&self, key: &str) -> String {
+        let expires = now() + u64::from(SESSION_MAX_AGE);
+        let payload = format!("{}.{expires}", to_hex(key.as_bytes()));
+        let signature = self.signature(&payload).finalize().into_bytes();
+        format!("{payload}.{}", to_hex(&signature))
+    }
+
+    /// The account key and expiry of a token signed here, still valid and
+    /// not revoked.
+    fn session(&self, token: &str) -> Option<(String, u64)> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        self.signature(payload)
+            .verify_slice(&from_hex(signature)?)
+            .ok()?;
+        let (key, expires) = payload.split_once('.')?;
+        let expires = expires.parse::<u64>().ok()?;
+        if expires <= now() || self.revoked.contains_key(token) {
+            return None;
+        }
+        Some((String::from_utf8(from_hex(key)?).ok()?, expires))
+    }
+}
+
+/// Registered accounts and open sessions, shared by the middleware and the
+/// account server functions.
+#[derive(Clone)]
+pub struct AuthState(Arc<Mutex<Accounts>>);
+
+impl AuthState {
+    /// Sessions survive a restart when signed with the same `session_secret`.
+    /// Errors name the file that could not be read.
+    pub fn load(
+        path: impl Into<PathBuf>,
+        revoked_path: impl Into<PathBuf>,
+        session_secret: Option<&str>,
+    ) -> std::io::Result<Self> {
+        let users = load_file(path.into())?;
+        let mut revoked = load_file::<Revoked>(revoked_path.into())?;
+        let now = now();
+        revoked.retain(|_, expires| *expires > now);
+        // Drawn now so that the first unknown name is not slower to answer.
+        dummy_hash();
+        let session_key = match session_secret {
+            Some(secret) => Blake2b512::digest(secret.as_bytes()).into(),
+            None => {
+                let mut key = [0; 64];
+                thread_rng().fill(&mut key[..]);
+                key
+            }
+        };
+        Ok(Self(Arc::new(Mutex::new(Accounts {
+            users,
+            session_key,
+            revoked,
+        }))))
+    }
+
+    /// Creates the account and returns a new session token.
+    pub async fn register(
+        &self,
+        name: &str,
+        password: &str,
+        email: Option<&str>,
+    ) -> Result<String, AuthError> {
+        let name = name.trim();
+        let valid_name = (3..=20).contains(&name.chars().count())
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(AuthError::InvalidName);
+        }
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(AuthError::WeakPassword);
+        }
+        let email = email.map(str::trim).filter(|email| !email.is_empty());
+        if email.is_some_and(|email| {
+            !email.contains('@') || email.contains(char::is_whitespace) || email.len() > 254
+        }) {
+            return Err(AuthError::InvalidEmail);
+        }
+
+        let password_hash = hash_password(password).await.ok_or(AuthError::Storage)?;
+
+        let mut accounts = self.0.lock().await;
+        let key = name.to_lowercase();
+        if accounts.users.0.contains_key(&key) {
+            return Err(AuthError::NameTaken);
+        }
+        accounts.users.0.insert(
+            key.clone(),
+            Account {
+                name: name.to_string(),
+                password_hash,
+                email: email.map(str::to_string),
+            },
+        );
+        if let Err(error) = accounts.users.save(AuthError::Storage).await {
+            accounts.users.0.remove(&key);
+            return Err(error);
+        }
+        Ok(accounts.open_session(&key))
+    }
+
+    /// Checks the password and returns a new session token. The hash is
+    /// verified without holding the accounts, and unknown names are checked
+    /// against a dummy hash so that they take as long to answer.
+    pub async fn login(&self, name: &str, password: &str) -> Result<String, AuthError> {
+        let key = name.trim().to_lowercase();
+        let stored = self
+            .0
+            .lock()
+            .await
+            .users
+            .0
+            .get(&key)
+            .map(|account| account.password_hash.clone());
+        let is_known = stored.is_some();
+        let verified = verify_password(password, stored).await;
+        if !verified || !is_known {
+            return Err(AuthError::InvalidCredentials);
+        }
+        Ok(self.0.lock().await.open_session(&key))
+    }
+
+    /// Revokes the token until it expires. It stays revoked in memory when it
+    /// cannot be saved, until the next restart.
+    pub async fn logout(&self, token: &str) -> Result<(), AuthError> {
+        let mut accounts = self.0.lock().await;
+        let Some((_, expires)) = accounts.session(token) else {
+            return Ok(());
+        };
+        let now = now();
+        accounts.revoked.retain(|_, expires| *expires > now);
+        accounts.revoked.insert(token.to_string(), expires);
+        accounts.revoked.save(AuthError::Storage).await
+    }
+
+    /// The registered spelling of an account name, `None` if it does not exist.
+    pub async fn account_name(&self, name: &str) -> Option<String> {
+        let accounts = self.0.lock().await;
+        accounts
+            .users
+            .0
+            .get(&name.trim().to_lowercase())
+            .map(|account| account.name.clone())
+    }
+
+    pub async fn email(&self, name: &str) -> Option<String> {
+        let accounts = self.0.lock().await;
+        accounts
+            .users
+            .0
+            .get(&name.trim().to_lowercase())
+            .and_then(|account| account.email.clone())
+    }
+
+    pub async fn user(&self, token: &str) -> Option<User> {
+        let accounts = self.0.lock().await;
+        let (key, _) = accounts.session(token)?;
+        accounts.users.0.get(&key).map(|account| User {
+            name: account.name.clone(),
+        })
+    }
+}
+
+fn load_file<T: Serialize + DeserializeOwned + Default>(
+    path: PathBuf,
+) -> std::io::Result<JsonFile<T>> {
+    JsonFile::load(&path)
+        .map_err(|error| std::io::Error::new(error.kind(), format!("{}: {error}", path.display())))
+}
+
+/// Argon2 is slow on purpose, so it runs on the blocking threads. `None`
+/// when the password could not be hashed.
+async fn hash_password(password: &str) -> Option<String> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        Argon2::default()
+            .hash_password(
+                password.as_bytes(),
+                &SaltString::generate(&mut thread_rng()),
+            )
+            .map(|hash| hash.to_string())
+            .ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Checks `password` against `hash`, or against [`dummy_hash`] when there is
+/// none, on the blocking threads.
+async fn verify_password(password: &str, hash: Option<String>) -> bool {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        let hash = hash.as_deref().unwrap_or_else(|| dummy_hash());
+        PasswordHash::new(hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// A hash of a random password, verified in place of a missing account's.
+fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let mut password = [0u8; 32];
+        thread_rng().fill(&mut password[..]);
+        Argon2::default()
+            .hash_password(&password, &SaltString::generate(&mut thread_rng()))
+            .map(|hash| hash.to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            pair.trim()
+                .strip_prefix(SESSION_COOKIE)
+                .and_then(|rest| rest.strip_prefix('='))
+        })
+        .map(str::to_string)
+}
+
+/// The signed-in user of the request a server function is answering.
+pub fn current_user() -> Option<User> {
+    leptos::use_context::<http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<CurrentUser>().cloned())
+        .and_then(|current| current.0)
+}
+
+/// Sets the session cookie on the current Leptos response, or clears it.
+/// It is `Secure` unless the configuration turns that off.
+pub fn set_session_cookie(token: Option<&str>) {
+    let secure = leptos::use_context::<ServerConfig>().is_none_or(|config| config.secure_cookies);
+    let attributes = if secure {
+        "Path=/; HttpOnly; Secure; SameSite=Lax"
+    } else {
+        "Path=/; HttpOnly; SameSite=Lax"
+    };
+    let cookie = match token {
+        Some(token) => format!("{SESSION_COOKIE}={token}; {attributes}; Max-Age={SESSION_MAX_AGE}"),
+        None => format!("{SESSION_COOKIE}=; {attributes}; Max-Age=0"),
+    };
+    if let (Some(response), Ok(value)) = (
+        leptos::use_context::<leptos_axum::ResponseOptions>(),
+        HeaderValue::from_str(&cookie),
+    ) {
+        response.append_header(header::SET_COOKIE, value);
+    }
+}
+
+/// Resolves the session cookie into a [`CurrentUser`] request extension.
+pub async fn session_middleware(
+    State(auth): State<AuthState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let user = match session_token(req.headers()) {
+        Some(token) => auth.user(&token).await,
+        None => None,
+    };
+    req.extensions_mut().insert(CurrentUser(user));
+    next.run(req).await
+}
@@ -0,0 +1,59 @@
+use crate::app::{ChatError, ChatMessage};
+use crate::profanity::ProfanityFilter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const CHAT_MAX_LEN: usize = 300;
+/// Messages an author may send within [`CHAT_WINDOW`], all games together.
+const CHAT_BURST: usize = 5;
+const CHAT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Turns what players type into chat messages, for live and correspondence
+/// games alike: listed words are masked and fast authors are held back.
+#[derive(Clone)]
+pub struct ChatGuard {
+    filter: ProfanityFilter,
+    /// When each author, by lowercase name, last posted within the window.
+    recent: Arc<Mutex<HashMap<String, Vec<Instant>>>>,
+}
+
+impl ChatGuard {
+    pub fn new(filter: ProfanityFilter) -> Self {
+        Self {
+            filter,
+            recent: Arc::default(),
+        }
+    }
+
+    /// The message `author` may post, or why not.
+    pub fn message(&self, author: &str, text: &str) -> Result<ChatMessage, ChatError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ChatError::Empty);
+        }
+        if text.chars().count() > CHAT_MAX_LEN {
+            return Err(ChatError::TooLong);
+        }
+
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|_, sent| {
+            sent.retain(|sent| now.duration_since(*sent) < CHAT_WINDOW);
+            !sent.is_empty()
+        });
+        let sent = recent.entry(author.to_lowercase()).or_default();
+        if sent.len() >= CHAT_BURST {
+            return Err(ChatError::TooFast);
+        }
+        sent.push(now);
+
+        Ok(ChatMessage {
+            author: author.to_string(),
+            text: self.filter.clean(text),
+            sent_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        })
+    }
+}
@@ -0,0 +1,341 @@
+use crate::app::Language;
+use serde::{Deserialize, Deserializer};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Read when `KRABBELS_CONFIG` names no other file. It may be missing.
+const DEFAULT_PATH: &str = "config/krabbels.toml";
+/// Read when `profanity_file` is not set. It may be missing.
+const DEFAULT_PROFANITY_PATH: &str = "config/profanity.txt";
+const MIN_SECRET_LEN: usize = 32;
+const MAX_EXPIRY_CHECK_SECONDS: u64 = 3600;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{} is not valid: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("{var} is not valid: {value:?}")]
+    Env { var: &'static str, value: String },
+    #[error("{field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+/// Limits on the games players start.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameLimits {
+    /// Correspondence games a player may have in progress before starting
+    /// another one. Tournament pairings are not held back.
+    pub max_active_games: usize,
+    /// How often games past their deadline are forfeited.
+    pub expiry_check_seconds: u64,
+}
+
+impl Default for GameLimits {
+    fn default() -> Self {
+        Self {
+            max_active_games: 20,
+            expiry_check_seconds: 60,
+        }
+    }
+}
+
+/// Settings of the server, from a TOML file with `KRABBELS_*` variables
+/// over it. Leptos keeps its own settings in `Cargo.toml` and `LEPTOS_*`.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Where the site listens, the Leptos `site-addr` when missing.
+    pub bind_address: Option<SocketAddr>,
+    /// Holds the JSON files of accounts, games and results, and the mail outbox.
+    pub data_dir: PathBuf,
+    /// `<code>.txt` word lists found there replace the built-in ones on the
+    /// server. The move preview, local games and computer opponents run in
+    /// the browser and keep the built-in lists.
+    pub lexicon_dir: Option<PathBuf>,
+    /// Words masked in chat, one per line. `config/profanity.txt` if there
+    /// is one when missing.
+    pub profanity_file: Option<PathBuf>,
+    /// Used when neither the `lang` cookie nor `Accept-Language` settles it.
+    #[serde(deserialize_with = "language_code")]
+    pub default_language: Language,
+    /// Signs the session cookies so they survive a restart, signed-out ones
+    /// being listed in the data directory. A random one is drawn at startup
+    /// when missing.
+    pub session_secret: Option<String>,
+    /// Sends the session cookie over HTTPS only. Turn it off to sign in
+    /// while developing over plain HTTP.
+    pub secure_cookies: bool,
+    /// Where the site is reached from outside, the start of the links sent
+    /// in notifications. `http://` and the bind address when missing.
+    pub site_url: Option<String>,
+    /// Sender of the notification emails.
+    pub mail_from: String,
+    pub games: GameLimits,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: None,
+            data_dir: PathBuf::from("data"),
+            lexicon_dir: None,
+            profanity_file: None,
+            default_language: Language::default(),
+            session_secret: None,
+            secure_cookies: true,
+            site_url: None,
+            mail_from: "krabbels@localhost".to_string(),
+            games: GameLimits::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The file named by `KRABBELS_CONFIG`, else `config/krabbels.toml` if
+    /// there is one, then the variables, checked before anything starts.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match std::env::var_os("KRABBELS_CONFIG") {
+            Some(path) => Self::read(Path::new(&path))?,
+            None if Path::new(DEFAULT_PATH).exists() => Self::read(Path::new(DEFAULT_PATH))?,
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.into(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.into(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(address) = env_var("KRABBELS_BIND_ADDRESS", |value| value.parse().ok())? {
+            self.bind_address = Some(address);
+        }
+        if let Some(dir) = env_var("KRABBELS_DATA_DIR", |value| Some(PathBuf::from(value)))? {
+            self.data_dir = dir;
+        }
+        if let Some(dir) = env_var("KRABBELS_LEXICON_DIR", |value| Some(PathBuf::from(value)))? {
+            self.lexicon_dir = Some(dir);
+        }
+        if let Some(file) = env_var("KRABBELS_PROFANITY_FILE", |value| {
+            Some(PathBuf::from(value))
+        })? {
+            self.profanity_file = Some(file);
+        }
+        if let Some(language) = env_var("KRABBELS_DEFAULT_LANGUAGE", Language::from_code)? {
+            self.default_language = language;
+        }
+        if let Some(secret) = env_var("KRABBELS_SESSION_SECRET", |value| Some(value.to_string()))? {
+            self.session_secret = Some(secret);
+        }
+        if let Some(secure) = env_var("KRABBELS_SECURE_COOKIES", |value| value.parse().ok())? {
+            self.secure_cookies = secure;
+        }
+        if let Some(url) = env_var("KRABBELS_SITE_URL", |value| Some(value.to_string()))? {
+            self.site_url = Some(url);
+        }
+        if let Some(from) = env_var("KRABBELS_MAIL_FROM", |value| Some(value.to_string()))? {
+            self.mail_from = from;
+        }
+        if let Some(count) = env_var("KRABBELS_MAX_ACTIVE_GAMES", |value| value.parse().ok())? {
+            self.games.max_active_games = count;
+        }
+        if let Some(seconds) = env_var("KRABBELS_EXPIRY_CHECK_SECONDS", |value| value.parse().ok())?
+        {
+            self.games.expiry_check_seconds = seconds;
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: String| Err(ConfigError::Invalid { field, reason });
+        if self.data_dir.as_os_str().is_empty() {
+            return invalid("data_dir", "must not be empty".to_string());
+        }
+        if self.data_dir.exists() && !self.data_dir.is_dir() {
+            return invalid(
+                "data_dir",
+                format!("{} is not a directory", self.data_dir.display()),
+            );
+        }
+        if let Some(dir) = &self.lexicon_dir {
+            if !dir.is_dir() {
+                return invalid(
+                    "lexicon_dir",
+                    format!("{} is not a directory", dir.display()),
+                );
+            }
+        }
+        if let Some(file) = &self.profanity_file {
+            if !file.is_file() {
+                return invalid(
+                    "profanity_file",
+                    format!("{} is not a file", file.display()),
+                );
+            }
+        }
+        if let Some(secret) = &self.session_secret {
+            if secret.chars().count() < MIN_SECRET_LEN {
+                return invalid(
+                    "session_secret",
+                    format!("must be at least {MIN_SECRET_LEN} characters long"),
+                );
+            }
+        }
+        if let Some(url) = &self.site_url {
+            let has_host = ["http://", "https://"]
+                .iter()
+                .find_map(|scheme| url.strip_prefix(scheme))
+                .is_some_and(|rest| !rest.is_empty());
+            if !has_host || url.contains(char::is_whitespace) {
+                return invalid(
+                    "site_url",
+                    format!("{url:?} is not an http:// or https:// URL"),
+                );
+            }
+        }
+        // It goes in a mail header as is.
+        let (local, domain) = self.mail_from.split_once('@').unwrap_or_default();
+        if local.is_empty() || domain.is_empty() || self.mail_from.contains(char::is_whitespace) {
+            return invalid(
+                "mail_from",
+                format!("{:?} is not an email address", self.mail_from),
+            );
+        }
+        if self.games.max_active_games == 0 {
+            return invalid("games.max_active_games", "must be at least 1".to_string());
+        }
+        if !(1..=MAX_EXPIRY_CHECK_SECONDS).contains(&self.games.expiry_check_seconds) {
+            return invalid(
+                "games.expiry_check_seconds",
+                format!("must be between 1 and {MAX_EXPIRY_CHECK_SECONDS}"),
+            );
+        }
+        Ok(())
+    }
+
+    /// The list of words masked in chat, `None` when there is none.
+    pub fn profanity_file(&self) -> Option<&Path> {
+        match &self.profanity_file {
+            Some(file) => Some(file),
+            None => Some(Path::new(DEFAULT_PROFANITY_PATH)).filter(|file| file.exists()),
+        }
+    }
+
+    /// A file kept in the data directory.
+    pub fn data_file(&self, name: &str) -> PathBuf {
+        self.data_dir.join(name)
+    }
+}
+
+/// The variable parsed with `parse`, `None` when it is not set.
+fn env_var<T>(
+    var: &'static str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>, ConfigError> {
+    match std::env::var(var) {
+        Ok(value) => parse(&value)
+            .map(Some)
+            .ok_or(ConfigError::Env { var, value }),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(value)) => Err(ConfigError::Env {
+            var,
+            value: value.to_string_lossy().into_owned(),
+        }),
+    }
+}
+
+fn language_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Language, D::Error> {
+    let code = String::deserialize(deserializer)?;
+    Language::from_code(&code).ok_or_else(|| {
+        let known = Language::ALL.map(|language| language.code()).join(", ");
+        serde::de::Error::custom(format!(
+            "unknown language {code:?}, expected one of {known}"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refused(config: ServerConfig) -> &'static str {
+        match config.validate() {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected an invalid field, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(ServerConfig::default().validate().is_ok());
+        let config = ServerConfig {
+            site_url: Some("https://krabbels.example.org/".to_string()),
+            session_secret: Some("x".repeat(MIN_SECRET_LEN)),
+            ..ServerConfig::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_names_the_field() {
+        let config = ServerConfig {
+            data_dir: PathBuf::new(),
+            ..ServerConfig::default()
+        };
+        assert_eq!(refused(config), "data_dir");
+        let config = ServerConfig {
+            lexicon_dir: Some(PathBuf::from("Cargo.toml")),
+            ..ServerConfig::default()
+        };
+        assert_eq!(refused(config), "lexicon_dir");
+        let config = ServerConfig {
+            profanity_file: Some(PathBuf::from("config")),
+            ..ServerConfig::default()
+        };
+        assert_eq!(refused(config), "profanity_file");
+        let config = ServerConfig {
+            session_secret: Some("x".repeat(MIN_SECRET_LEN - 1)),
+            ..ServerConfig::default()
+        };
+        assert_eq!(refused(config), "session_secret");
+        for url in ["krabbels.example.org", "https://", "http://a b"] {
+            let config = ServerConfig {
+                site_url: Some(url.to_string()),
+                ..ServerConfig::default()
+            };
+            assert_eq!(refused(config), "site_url", "{url}");
+        }
+        for from in ["krabbels", "@localhost", "krabbels@", "a@b\r\nBcc: c@d"] {
+            let config = ServerConfig {
+                mail_from: from.to_string(),
+                ..ServerConfig::default()
+            };
+            assert_eq!(refused(config), "mail_from", "{from:?}");
+        }
+        let mut config = ServerConfig::default();
+        config.games.max_active_games = 0;
+        assert_eq!(refused(config), "games.max_active_games");
+        let mut config = ServerConfig::default();
+        config.games.expiry_check_seconds = MAX_EXPIRY_CHECK_SECONDS + 1;
+        assert_eq!(refused(config), "games.expiry_check_seconds");
+    }
+}
@@ -0,0 +1,620 @@
+use crate::app::{
+    fill, ChallengeRule, ChatError, ChatMessage, CorrespondenceError, CorrespondenceView,
+    GameListing, GameRecord, GameState, Language, Lexicon, Move, PlayError, SeatView, Tile, User,
+    TURN_LIMITS,
+};
+use crate::auth::AuthState;
+use crate::error_template::AppError;
+use crate::json_file::JsonFile;
+use crate::notify::{Notification, Notifier};
+use crate::ratings::ResultStore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredGame {
+    state: GameState,
+    /// Hours the seat we wait on has to move.
+    turn_limit: u64,
+    /// Seconds since the Unix epoch when we started waiting on that seat.
+    turn_started: u64,
+    /// The seat that ran out of time, if any.
+    forfeited: Option<usize>,
+    /// The opponent has not accepted yet: nobody moves, and the time limit
+    /// is theirs to answer. Still set once finished, the invitation was
+    /// declined or let expire and the game is not rated.
+    #[serde(default)]
+    invited: bool,
+    /// Between the players, kept with the result.
+    #[serde(default)]
+    chat: Vec<ChatMessage>,
+}
+
+impl StoredGame {
+    fn seat_of(&self, name: &str) -> Option<usize> {
+        let key = name.to_lowercase();
+        self.state.players.iter().position(|player| {
+            player
+                .account
+                .as_ref()
+                .is_some_and(|account| account.to_lowercase() == key)
+        })
+    }
+
+    /// The seat that has to move, or to answer the invitation.
+    fn waiting_on(&self) -> usize {
+        if self.invited {
+            1
+        } else {
+            self.state.waiting_on()
+        }
+    }
+
+    fn deadline(&self) -> u64 {
+        self.turn_started + self.turn_limit * 3600
+    }
+
+    fn record(&self) -> GameRecord {
+        let mut record = self.state.record();
+        record.forfeited = self.forfeited;
+        record.chat = self.chat.clone();
+        record
+    }
+
+    fn seats(&self) -> Vec<SeatView> {
+        self.state
+            .players
+            .iter()
+            .map(|player| SeatView {
+                name: player.name.clone(),
+                score: player.score,
+                rack: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Correspondence games between two accounts, played one move at a time and
+/// kept on disk. Finished games go to the [`ResultStore`] like live ones.
+#[derive(Clone)]
+pub struct CorrespondenceStore {
+    games: Arc<Mutex<JsonFile<BTreeMap<u64, StoredGame>>>>,
+    results: ResultStore,
+    auth: AuthState,
+    notifier: Arc<dyn Notifier>,
+    /// Prefix of the links sent in notifications, e.g. `http://127.0.0.1:3000`.
+    site_url: String,
+    /// Games in progress past which a player cannot invite anyone else.
+    max_active_games: usize,
+}
+
+impl CorrespondenceStore {
+    pub fn load(
+        path: impl Into<PathBuf>,
+        results: ResultStore,
+        auth: AuthState,
+        notifier: Arc<dyn Notifier>,
+        site_url: &str,
+        max_active_games: usize,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            games: Arc::new(Mutex::new(JsonFile::load(path)?)),
+            results,
+            auth,
+            notifier,
+            site_url: site_url.trim_end_matches('/').to_string(),
+            max_active_games,
+        })
+    }
+
+    /// Invites the account `opponent` to a game where `creator` moves first.
+    /// The clock starts once the opponent accepts. Refused while `creator`
+    /// has as many games in progress as allowed.
+    pub async fn invite(
+        &self,
+        creator: &str,
+        opponent: &str,
+        rule: ChallengeRule,
+        hours: u64,
+        language: Language,
+    ) -> Result<u64, CorrespondenceError> {
+        self.insert(creator, opponent, rule, hours, language, true)
+            .await
+    }
+
+    /// Starts a game at once, for pairings both players already agreed to.
+    pub async fn create(
+        &self,
+        creator: &str,
+        opponent: &str,
+        rule: ChallengeRule,
+        hours: u64,
+        language: Language,
+    ) -> Result<u64, CorrespondenceError> {
+        self.insert(creator, opponent, rule, hours, language, false)
+            .await
+    }
+
+    async fn insert(
+        &self,
+        creator: &str,
+        opponent: &str,
+        rule: ChallengeRule,
+        hours: u64,
+        language: Language,
+        invited: bool,
+    ) -> Result<u64, CorrespondenceError> {
+        let opponent = self
+            .auth
+            .account_name(opponent)
+            .await
+            .filter(|opponent| opponent.to_lowercase() != creator.to_lowercase())
+            .ok_or(CorrespondenceError::UnknownOpponent)?;
+        let turn_limit = if TURN_LIMITS.contains(&hours) {
+            hours
+        } else {
+            TURN_LIMITS[0]
+        };
+
+        let mut state = GameState::new(&[creator, &opponent], rule, language);
+        for (seat, name) in [creator, opponent.as_str()].into_iter().enumerate() {
+            state.seat_account(
+                seat,
+                Some(User {
+                    name: name.to_string(),
+                }),
+            );
+            state.current = seat;
+            state.refill_rack();
+        }
+        state.current = 0;
+
+        let mut games = self.games.lock().await;
+        // Tournament pairings, started at once, are not held back.
+        let active = games
+            .values()
+            .filter(|game| !game.state.finished && game.seat_of(creator).is_some())
+            .count();
+        if invited && active >= self.max_active_games {
+            return Err(CorrespondenceError::TooManyGames);
+        }
+        let id = games.last_key_value().map_or(1, |(id, _)| id + 1);
+        games.insert(
+            id,
+            StoredGame {
+                state,
+                turn_limit,
+                turn_started: now(),
+                forfeited: None,
+                invited,
+                chat: Vec::new(),
+            },
+        );
+        if let Err(error) = games.save(CorrespondenceError::Storage).await {
+            games.remove(&id);
+            return Err(error);
+        }
+        drop(games);
+
+        let messages = language.messages();
+        self.send(vec![self.notification(
+            &opponent,
+            fill(messages.notify_new_game_subject, &[creator]),
+            self.link(id),
+        )])
+        .await;
+        Ok(id)
+    }
+
+    /// Games `user` plays in, newest first.
+    pub async fn list(&self, user: &str) -> Vec<GameListing> {
+        let now = now();
+        let games = self.games.lock().await;
+        games
+            .iter()
+            .rev()
+            .filter_map(|(id, game)| {
+                let seat = game.seat_of(user)?;
+                let waiting_on = game.waiting_on();
+                Some(GameListing {
+                    id: *id,
+                    players: game.seats(),
+                    invited: game.invited,
+                    your_turn: waiting_on == seat,
+                    waiting_on: game.state.players[waiting_on].name.clone(),
+                    finished: game.state.finished,
+                    time_left: game.deadline().saturating_sub(now),
+                })
+            })
+            .collect()
+    }
+
+    /// Games being played, invitations left out.
+    pub async fn active(&self) -> usize {
+        let games = self.games.lock().await;
+        games
+            .values()
+            .filter(|game| !game.state.finished && !game.invited)
+            .count()
+    }
+
+    /// The game as seen from the seat of `user`; other players get nothing.
+    pub async fn view(
+        &self,
+        id: u64,
+        user: &str,
+    ) -> Result<CorrespondenceView, CorrespondenceError> {
+        let games = self.games.lock().await;
+        let game = games.get(&id).ok_or(CorrespondenceError::UnknownGame)?;
+        let seat = game.seat_of(user).ok_or(CorrespondenceError::UnknownGame)?;
+        let state = &game.state;
+        // Past racks give away tiles the opponent may still hold.
+        let mut history = state.history.clone();
+        if !state.finished {
+            for turn in history.iter_mut().filter(|turn| turn.player != seat) {
+                turn.rack.clear();
+            }
+        }
+        Ok(CorrespondenceView {
+            id,
+            seat,
+            players: game.seats(),
+            rack: state.players[seat].rack.clone(),
+            grid: state.grid,
+            current: state.current,
+            waiting_on: game.waiting_on(),
+            pending: state.pending.clone(),
+            history,
+            bag: state.bag.len(),
+            unseen: state.unseen_tiles_for(seat),
+            rule: state.rule,
+            language: state.language,
+            finished: state.finished,
+            forfeited: game.forfeited,
+            invited: game.invited,
+            time_left: game.deadline().saturating_sub(now()),
+        })
+    }
+
+    /// A finished game with every rack, for anyone to replay.
+    pub async fn record(&self, id: u64) -> Result<GameRecord, AppError> {
+        let games = self.games.lock().await;
+        match games.get(&id) {
+            Some(game) if game.state.finished => Ok(game.record()),
+            Some(_) => Err(AppError::GameInProgress),
+            None => Err(AppError::GameNotFound),
+        }
+    }
+
+    /// Final scores of a finished two-player game and the seat that forfeited, if any.
+    pub async fn result(&self, id: u64) -> Option<((usize, usize), Option<usize>)> {
+        let games = self.games.lock().await;
+        let game = games
+            .get(&id)
+            .filter(|game| game.state.finished && !game.invited)?;
+        let [first, second] = game.state.players.as_slice() else {
+            return None;
+        };
+        Some(((first.score, second.score), game.forfeited))
+    }
+
+    /// Applies the move of `user`. A move breaking the rules leaves the game
+    /// untouched and comes back as the inner error.
+    pub async fn play(
+        &self,
+        id: u64,
+        user: &str,
+        play: Move,
+    ) -> Result<Result<(), PlayError>, CorrespondenceError> {
+        self.apply(id, user, play)
+            .instrument(tracing::info_span!("game", id, player = user))
+            .await
+    }
+
+    async fn apply(
+        &self,
+        id: u64,
+        user: &str,
+        play: Move,
+    ) -> Result<Result<(), PlayError>, CorrespondenceError> {
+        let mut games = self.games.lock().await;
+        let game = games.get_mut(&id).ok_or(CorrespondenceError::UnknownGame)?;
+        let seat = game.seat_of(user).ok_or(CorrespondenceError::UnknownGame)?;
+        if game.state.finished || game.invited || game.state.waiting_on() != seat {
+            tracing::debug!("move out of turn");
+            return Err(CorrespondenceError::NotYourTurn);
+        }
+
+        let before = game.clone();
+        let state = &mut game.state;
+        let lexicon = Lexicon::for_language(state.lexicon_language());
+        let outcome = match (play, state.pending.is_some()) {
+            (Move::Play(placements), false) => state.submit(&placements, lexicon).map(|_| ()),
+            (Move::Exchange(tiles), false) => state.exchange(&tiles),
+            (Move::Pass, false) => {
+                state.pass();
+                Ok(())
+            }
+            (Move::Accept, true) => {
+                state.accept();
+                Ok(())
+            }
+            (Move::Challenge, true) => {
+                state.challenge(lexicon);
+                Ok(())
+            }
+            _ => return Err(CorrespondenceError::NotYourTurn),
+        };
+        if let Err(error) = &outcome {
+            tracing::debug!("move rejected: {error:?}");
+            return Ok(outcome);
+        }
+        tracing::debug!("move played");
+
+        game.turn_started = now();
+        let game = game.clone();
+        if let Err(error) = games.save(CorrespondenceError::Storage).await {
+            games.insert(id, before);
+            return Err(error);
+        }
+        drop(games);
+
+        if game.state.finished {
+            self.finish(id, &game).await;
+        } else {
+            let waiting_on = &game.state.players[game.state.waiting_on()];
+            let mover = &game.state.players[seat].name;
+            let messages = game.state.language.messages();
+            self.send(vec![self.notification(
+                &waiting_on.name,
+                fill(messages.notify_turn_subject, &[mover]),
+                fill(
+                    messages.notify_turn_body,
+                    &[mover, &game.turn_limit.to_string(), &self.link(id)],
+                ),
+            )])
+            .await;
+        }
+        Ok(Ok(()))
+    }
+
+    /// The answer of the invited player: accepting starts the clock of the
+    /// first mover, declining ends the game unrated.
+    pub async fn answer(
+        &self,
+        id: u64,
+        user: &str,
+        accept: bool,
+    ) -> Result<(), CorrespondenceError> {
+        let mut games = self.games.lock().await;
+        let game = games.get_mut(&id).ok_or(CorrespondenceError::UnknownGame)?;
+        let seat = game.seat_of(user).ok_or(CorrespondenceError::UnknownGame)?;
+        if !game.invited || game.state.finished || game.waiting_on() != seat {
+            return Err(CorrespondenceError::NotYourTurn);
+        }
+
+        let before = game.clone();
+        if accept {
+            game.invited = false;
+        } else {
+            game.state.finished = true;
+        }
+        game.turn_started = now();
+        let game = game.clone();
+        if let Err(error) = games.save(CorrespondenceError::Storage).await {
+            games.insert(id, before);
+            return Err(error);
+        }
+        drop(games);
+
+        tracing::info!(id, accept, "invitation answered");
+        let creator = &game.state.players[0].name;
+        let opponent = &game.state.players[seat].name;
+        let messages = game.state.language.messages();
+        let notification = if accept {
+            self.notification(
+                creator,
+                fill(messages.notify_turn_subject, &[opponent]),
+                fill(
+                    messages.notify_turn_body,
+                    &[opponent, &game.turn_limit.to_string(), &self.link(id)],
+                ),
+            )
+        } else {
+            self.notification(
+                creator,
+                fill(messages.notify_declined_subject, &[opponent]),
+                self.link(id),
+            )
+        };
+        self.send(vec![notification]).await;
+        Ok(())
+    }
+
+    /// Puts the rack of `user` in the order of `tiles`, which need not hold
+    /// all of it: the tiles left out follow in their current order. Only
+    /// the order changes, so it can be done out of turn.
+    pub async fn arrange(
+        &self,
+        id: u64,
+        user: &str,
+        tiles: &[Tile],
+    ) -> Result<(), CorrespondenceError> {
+        let mut games = self.games.lock().await;
+        let game = games.get_mut(&id).ok_or(CorrespondenceError::UnknownGame)?;
+        let seat = game.seat_of(user).ok_or(CorrespondenceError::UnknownGame)?;
+        let rack = &mut game.state.players[seat].rack;
+        let mut rest = rack.clone();
+        let mut arranged = Vec::with_capacity(rest.len());
+        for tile in tiles {
+            if let Some(idx) = rest.iter().position(|held| held == tile) {
+                arranged.push(rest.remove(idx));
+            }
+        }
+        arranged.extend(rest);
+        if *rack == arranged {
+            return Ok(());
+        }
+        let before = std::mem::replace(rack, arranged);
+        if let Err(error) = games.save(CorrespondenceError::Storage).await {
+            if let Some(game) = games.get_mut(&id) {
+                game.state.players[seat].rack = before;
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Messages of the game, for its players only.
+    pub async fn chat(&self, id: u64, user: &str) -> Result<Vec<ChatMessage>, ChatError> {
+        let games = self.games.lock().await;
+        games
+            .get(&id)
+            .filter(|game| game.seat_of(user).is_some())
+            .map(|game| game.chat.clone())
+            .ok_or(ChatError::UnknownGame)
+    }
+
+    pub async fn post_chat(
+        &self,
+        id: u64,
+        user: &str,
+        message: ChatMessage,
+    ) -> Result<(), ChatError> {
+        let mut games = self.games.lock().await;
+        let game = games
+            .get_mut(&id)
+            .filter(|game| game.seat_of(user).is_some())
+            .ok_or(ChatError::UnknownGame)?;
+        game.chat.push(message);
+        if let Err(error) = games.save(ChatError::Storage).await {
+            if let Some(game) = games.get_mut(&id) {
+                game.chat.pop();
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Ends every game whose deadline has passed: the seat we were waiting
+    /// on forfeits, or the invitation lapses without a result.
+    pub async fn expire(&self) {
+        let now = now();
+        let mut games = self.games.lock().await;
+        let before = games.clone();
+        let mut expired = Vec::new();
+        for (id, game) in games.iter_mut() {
+            if game.state.finished || game.deadline() > now {
+                continue;
+            }
+            if !game.invited {
+                game.forfeited = Some(game.state.waiting_on());
+            }
+            game.state.pending = None;
+            game.state.finished = true;
+            expired.push((*id, game.clone()));
+        }
+        if expired.is_empty() {
+            return;
+        }
+        if games.save(CorrespondenceError::Storage).await.is_err() {
+            **games = before;
+            return;
+        }
+        drop(games);
+
+        for (id, game) in &expired {
+            if game.invited {
+                tracing::info!(id, "invitation expired");
+                let messages = game.state.language.messages();
+                self.send(vec![self.notification(
+                    &game.state.players[0].name,
+                    fill(
+                        messages.notify_declined_subject,
+                        &[&game.state.players[1].name],
+                    ),
+                    self.link(*id),
+                )])
+                .await;
+            } else {
+                tracing::info!(id, "forfeited on time");
+                self.finish(*id, game).await;
+            }
+        }
+    }
+
+    /// Records the result and tells both players.
+    async fn finish(&self, id: u64, game: &StoredGame) {
+        if let Err(error) = self.results.record(game.record()).await {
+            tracing::error!("could not record correspondence game {id}: {error}");
+        }
+
+        let messages = game.state.language.messages();
+        let scores = game
+            .state
+            .players
+            .iter()
+            .map(|player| {
+                fill(
+                    messages.player_score,
+                    &[&player.name, &player.score.to_string()],
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" — ");
+        let mut body = fill(messages.notify_over_body, &[&scores, &self.link(id)]);
+        if let Some(seat) = game.forfeited {
+            let forfeit = fill(messages.corr_forfeited, &[&game.state.players[seat].name]);
+            body = format!("{forfeit}\n{body}");
+        }
+        let notifications = game
+            .state
+            .players
+            .iter()
+            .enumerate()
+            .map(|(seat, player)| {
+                let other = &game.state.players[(seat + 1) % game.state.players.len()];
+                self.notification(
+                    &player.name,
+                    fill(messages.notify_over_subject, &[&other.name]),
+                    body.clone(),
+                )
+            })
+            .collect();
+        self.send(notifications).await;
+    }
+
+    fn link(&self, id: u64) -> String {
+        format!("{}/game/{id}", self.site_url)
+    }
+
+    fn notification(&self, account: &str, subject: String, body: String) -> Notification {
+        Notification {
+            account: account.to_string(),
+            email: None,
+            subject,
+            body,
+        }
+    }
+
+    /// Looks up the address of each account and hands the notifications over.
+    async fn send(&self, notifications: Vec<Notification>) {
+        for mut notification in notifications {
+            notification.email = self.auth.email(&notification.account).await;
+            if let Err(error) = self.notifier.notify(&notification) {
+                tracing::warn!("could not notify {}: {error}", notification.account);
+            }
+        }
+    }
+}
@@ -1,72 +1,393 @@
+use crate::app::{
+    fill, use_language, AuthError, ChatError, CorrespondenceError, FlashcardError, Language,
+    PlayError, TournamentError,
+};
 use http::status::StatusCode;
 use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Clone, Debug, Error)]
+/// Declares the error enum of a group of server functions. Each variant
+/// travels inside an [`AppError`] as its kebab-case code, parses back from it
+/// and has a localized message in the catalogs.
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $code:literal => $message:ident,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, ::thiserror::Error, ::serde::Serialize, ::serde::Deserialize)]
+        $vis enum $name {
+            $($(#[$variant_meta])* #[error($code)] $variant,)+
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = ();
+
+            fn from_str(code: &str) -> Result<Self, Self::Err> {
+                match code {
+                    $($code => Ok($name::$variant),)+
+                    _ => Err(()),
+                }
+            }
+        }
+
+        impl $name {
+            pub fn message(&self, language: $crate::app::Language) -> &'static str {
+                let messages = language.messages();
+                match self {
+                    $($name::$variant => messages.$message,)+
+                }
+            }
+        }
+    };
+}
+pub(crate) use error_codes;
+
+/// Every error a server function sends back, each with its HTTP status. The
+/// `Display` code is what travels, and parses back on the page.
+#[derive(Clone, Debug, Error, PartialEq, Serialize, Deserialize)]
 pub enum AppError {
-    #[error("Not Found")]
+    #[error("not-found")]
     NotFound,
+    #[error("game-not-found")]
+    GameNotFound,
+    /// The game is still being played, so its racks stay hidden.
+    #[error("game-in-progress")]
+    GameInProgress,
+    #[error("player-not-found")]
+    PlayerNotFound,
+    #[error("not-signed-in")]
+    NotSignedIn,
+    /// The signed-in user does not sit at the game.
+    #[error("not-a-player")]
+    NotAPlayer,
+    #[error("not-your-turn")]
+    NotYourTurn,
+    #[error("too-many-games")]
+    TooManyGames,
+    #[error("illegal-move-{}", .0.code())]
+    IllegalMove(PlayError),
+    /// The word list of the language could not be loaded.
+    #[error("lexicon-unavailable-{}", .0.code())]
+    LexiconUnavailable(Language),
+    #[error("auth-{0}")]
+    Auth(AuthError),
+    #[error("chat-{0}")]
+    Chat(ChatError),
+    #[error("tournament-{0}")]
+    Tournament(TournamentError),
+    #[error("flashcard-{0}")]
+    Flashcard(FlashcardError),
+    #[error("storage")]
+    Storage,
+    /// Anything that is not one of ours, such as a request that could not be
+    /// sent or decoded.
+    #[error("internal")]
+    Internal,
+}
+
+impl FromStr for AppError {
+    type Err = ();
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "not-found" => Ok(AppError::NotFound),
+            "game-not-found" => Ok(AppError::GameNotFound),
+            "game-in-progress" => Ok(AppError::GameInProgress),
+            "player-not-found" => Ok(AppError::PlayerNotFound),
+            "not-signed-in" => Ok(AppError::NotSignedIn),
+            "not-a-player" => Ok(AppError::NotAPlayer),
+            "not-your-turn" => Ok(AppError::NotYourTurn),
+            "too-many-games" => Ok(AppError::TooManyGames),
+            "storage" => Ok(AppError::Storage),
+            "internal" => Ok(AppError::Internal),
+            _ => {
+                let (group, code) = [
+                    "illegal-move-",
+                    "lexicon-unavailable-",
+                    "auth-",
+                    "chat-",
+                    "tournament-",
+                    "flashcard-",
+                ]
+                .into_iter()
+                .find_map(|group| Some((group, code.strip_prefix(group)?)))
+                .ok_or(())?;
+                match group {
+                    "illegal-move-" => code.parse().map(AppError::IllegalMove),
+                    "lexicon-unavailable-" => Language::from_code(code)
+                        .map(AppError::LexiconUnavailable)
+                        .ok_or(()),
+                    "auth-" => code.parse().map(AppError::Auth),
+                    "chat-" => code.parse().map(AppError::Chat),
+                    "tournament-" => code.parse().map(AppError::Tournament),
+                    _ => code.parse().map(AppError::Flashcard),
+                }
+            }
+        }
+    }
 }
 
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
-            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::NotFound
+            | AppError::GameNotFound
+            | AppError::PlayerNotFound
+            | AppError::Chat(ChatError::UnknownGame)
+            | AppError::Tournament(
+                TournamentError::UnknownTournament
+                | TournamentError::UnknownPairing
+                | TournamentError::UnknownPlayer,
+            )
+            | AppError::Flashcard(FlashcardError::UnknownCard) => StatusCode::NOT_FOUND,
+            AppError::GameInProgress
+            | AppError::NotYourTurn
+            | AppError::TooManyGames
+            | AppError::Tournament(
+                TournamentError::Started
+                | TournamentError::NotEnoughPlayers
+                | TournamentError::RoundInProgress
+                | TournamentError::Finished
+                | TournamentError::ResultSynced,
+            ) => StatusCode::CONFLICT,
+            AppError::NotSignedIn
+            | AppError::Auth(AuthError::InvalidCredentials)
+            | AppError::Chat(ChatError::NotSignedIn)
+            | AppError::Tournament(TournamentError::NotSignedIn)
+            | AppError::Flashcard(FlashcardError::NotSignedIn) => StatusCode::UNAUTHORIZED,
+            AppError::NotAPlayer | AppError::Tournament(TournamentError::NotOrganizer) => {
+                StatusCode::FORBIDDEN
+            }
+            AppError::Chat(ChatError::TooFast) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::LexiconUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Storage
+            | AppError::Internal
+            | AppError::Auth(AuthError::Storage)
+            | AppError::Chat(ChatError::Storage)
+            | AppError::Tournament(TournamentError::Storage)
+            | AppError::Flashcard(FlashcardError::Storage) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::IllegalMove(_)
+            | AppError::Auth(_)
+            | AppError::Chat(_)
+            | AppError::Tournament(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub fn message(&self, language: Language) -> String {
+        let messages = language.messages();
+        match self {
+            AppError::NotFound => messages.error_not_found.to_string(),
+            AppError::GameNotFound => messages.corr_unknown_game.to_string(),
+            AppError::GameInProgress => messages.error_game_in_progress.to_string(),
+            AppError::PlayerNotFound => messages.corr_unknown_opponent.to_string(),
+            AppError::NotSignedIn => messages.error_not_signed_in.to_string(),
+            AppError::NotAPlayer => messages.error_not_a_player.to_string(),
+            AppError::NotYourTurn => messages.corr_not_your_turn.to_string(),
+            AppError::TooManyGames => messages.corr_too_many_games.to_string(),
+            AppError::IllegalMove(error) => error.message(language),
+            AppError::LexiconUnavailable(lexicon) => {
+                fill(messages.error_lexicon, &[lexicon.name()])
+            }
+            AppError::Auth(error) => error.message(language).to_string(),
+            AppError::Chat(error) => error.message(language).to_string(),
+            AppError::Tournament(error) => error.message(language).to_string(),
+            AppError::Flashcard(error) => error.message(language).to_string(),
+            AppError::Storage => messages.error_storage.to_string(),
+            AppError::Internal => messages.error_internal.to_string(),
+        }
+    }
+
+    /// The error a server function failed with, an internal one when it is
+    /// not one of ours.
+    pub fn from_server(error: &ServerFnError) -> Self {
+        match error {
+            ServerFnError::ServerError(code) => code.parse().unwrap_or(AppError::Internal),
+            _ => AppError::Internal,
         }
     }
+
+    /// Gives the response being served the status of the error. Server
+    /// failures are logged, the others are the visitor's doing.
+    #[cfg(feature = "ssr")]
+    pub fn respond(&self) {
+        let status = self.status_code();
+        if status.is_server_error() {
+            tracing::error!(%status, "{self:?}");
+        } else {
+            tracing::debug!(%status, "{self:?}");
+        }
+        if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+            response.set_status(status);
+        }
+    }
+
+    /// What a server function fails with: the code of the error, sent under
+    /// its status.
+    #[cfg(feature = "ssr")]
+    pub fn into_server(self) -> ServerFnError {
+        self.respond();
+        ServerFnError::ServerError(self.to_string())
+    }
 }
 
-// A basic function to display errors served by the error boundaries.
-// Feel free to do more complicated things here than just displaying the error.
+impl From<CorrespondenceError> for AppError {
+    fn from(error: CorrespondenceError) -> Self {
+        match error {
+            CorrespondenceError::UnknownGame => AppError::GameNotFound,
+            CorrespondenceError::UnknownOpponent => AppError::PlayerNotFound,
+            CorrespondenceError::NotYourTurn => AppError::NotYourTurn,
+            CorrespondenceError::TooManyGames => AppError::TooManyGames,
+            CorrespondenceError::Storage => AppError::Storage,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::ratings::ResultError> for AppError {
+    fn from(error: crate::ratings::ResultError) -> Self {
+        match error {
+            crate::ratings::ResultError::NotAPlayer => AppError::NotAPlayer,
+            crate::ratings::ResultError::Storage => AppError::Storage,
+        }
+    }
+}
+
+impl From<PlayError> for AppError {
+    fn from(error: PlayError) -> Self {
+        AppError::IllegalMove(error)
+    }
+}
+
+impl From<AuthError> for AppError {
+    fn from(error: AuthError) -> Self {
+        AppError::Auth(error)
+    }
+}
+
+impl From<ChatError> for AppError {
+    fn from(error: ChatError) -> Self {
+        AppError::Chat(error)
+    }
+}
+
+impl From<TournamentError> for AppError {
+    fn from(error: TournamentError) -> Self {
+        AppError::Tournament(error)
+    }
+}
+
+impl From<FlashcardError> for AppError {
+    fn from(error: FlashcardError) -> Self {
+        AppError::Flashcard(error)
+    }
+}
+
+/// Page shown for the errors caught by an error boundary, or for the ones
+/// given directly. Errors that are not an [`AppError`] count as internal ones.
 #[component]
 pub fn ErrorTemplate(
     #[prop(optional)] outside_errors: Option<Errors>,
     #[prop(optional)] errors: Option<RwSignal<Errors>>,
 ) -> impl IntoView {
-    let errors = match outside_errors {
-        Some(e) => create_rw_signal(e),
-        None => match errors {
-            Some(e) => e,
-            None => panic!("No Errors found and we expected errors!"),
-        },
-    };
-    // Get Errors from Signal
-    let errors = errors.get_untracked();
+    let language = use_language();
+    let messages = move || language().messages();
+    let errors = outside_errors
+        .or_else(|| errors.map(|errors| errors.get_untracked()))
+        .unwrap_or_default();
 
-    // Downcast lets us take a type that implements `std::error::Error`
-    let errors: Vec<AppError> = errors
+    let mut errors: Vec<AppError> = errors
         .into_iter()
-        .filter_map(|(_k, v)| v.downcast_ref::<AppError>().cloned())
+        .map(|(_key, error)| match error.downcast_ref::<AppError>() {
+            Some(error) => error.clone(),
+            None => {
+                #[cfg(feature = "ssr")]
+                tracing::error!("unexpected error: {error}");
+                AppError::Internal
+            }
+        })
         .collect();
-    println!("Errors: {errors:#?}");
+    if errors.is_empty() {
+        errors.push(AppError::Internal);
+    }
 
-    // Only the response code for the first error is actually sent from the server
-    // this may be customized by the specific application
+    // Only the status of the first error can be sent.
     #[cfg(feature = "ssr")]
     {
-        use leptos_axum::ResponseOptions;
-        let response = use_context::<ResponseOptions>();
-        if let Some(response) = response {
-            response.set_status(errors[0].status_code());
+        errors[0].respond();
+        for error in &errors[1..] {
+            tracing::debug!(status = %error.status_code(), "{error:?}");
         }
     }
 
+    let status = errors[0].status_code();
+    let draw_errors = move || {
+        errors
+            .iter()
+            .map(|error| view! { <p>{error.message(language())}</p> })
+            .collect_view()
+    };
+
     view! {
-        <h1>{if errors.len() > 1 {"Errors"} else {"Error"}}</h1>
-        <For
-            // a function that returns the items we're iterating over; a signal is fine
-            each= move || {errors.clone().into_iter().enumerate()}
-            // a unique key for each item as a reference
-            key=|(index, _error)| *index
-            // renders each item to a view
-            children=move |error| {
-                let error_string = error.1.to_string();
-                let error_code= error.1.status_code();
-                view! {
-                    <h2>{error_code.to_string()}</h2>
-                    <p>"Error: " {error_string}</p>
-                }
-            }
-        />
+        <section class="container mx-auto p-5 dark:text-zinc-300">
+            <h1 class="text-2xl font-bold dark:text-yellow-100">{move || fill(messages().error_title, &[status.as_str()])}</h1>
+            {draw_errors}
+        </section>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_parse_back() {
+        let errors = [
+            AppError::NotFound,
+            AppError::GameNotFound,
+            AppError::GameInProgress,
+            AppError::PlayerNotFound,
+            AppError::NotSignedIn,
+            AppError::NotAPlayer,
+            AppError::NotYourTurn,
+            AppError::TooManyGames,
+            AppError::IllegalMove(PlayError::SameSquare),
+            AppError::IllegalMove(PlayError::UnknownWords(vec!["ZZZ".into(), "QI".into()])),
+            AppError::LexiconUnavailable(Language::French),
+            AppError::LexiconUnavailable(Language::English),
+            AppError::Auth(AuthError::InvalidCredentials),
+            AppError::Chat(ChatError::TooFast),
+            AppError::Tournament(TournamentError::NotOrganizer),
+            AppError::Flashcard(FlashcardError::UnknownCard),
+            AppError::Storage,
+            AppError::Internal,
+        ];
+        for error in errors {
+            assert_eq!(error.to_string().parse(), Ok(error.clone()));
+            let sent = ServerFnError::ServerError(error.to_string());
+            assert_eq!(AppError::from_server(&sent), error);
+        }
+    }
+
+    #[test]
+    fn unknown_codes_are_internal() {
+        assert_eq!("lexicon-unavailable-de".parse::<AppError>(), Err(()));
+        assert_eq!("auth-nope".parse::<AppError>(), Err(()));
+        let sent = ServerFnError::ServerError("nope".into());
+        assert_eq!(AppError::from_server(&sent), AppError::Internal);
+    }
+
+    #[test]
+    fn lexicon_message_names_the_language() {
+        let error = AppError::LexiconUnavailable(Language::French);
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            error.message(Language::English),
+            "The lexicon (Français) could not be loaded."
+        );
     }
 }
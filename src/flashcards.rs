@@ -0,0 +1,170 @@
+use crate::app::{
+    bingo_deck, normalize_word, Flashcard, FlashcardAnswer, FlashcardDeal, FlashcardError,
+    FlashcardStats, Language, RETRY_AFTER, REVIEW_HOURS,
+};
+use crate::json_file::JsonFile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CardProgress {
+    /// Index in [`REVIEW_HOURS`], raised by each right answer.
+    level: usize,
+    /// Seconds since the Unix epoch.
+    due: u64,
+    seen: usize,
+    correct: usize,
+}
+
+/// Keyed by lowercase account name, then by `language:alphagram`.
+type Progress = BTreeMap<String, BTreeMap<String, CardProgress>>;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn card_key(language: Language, alphagram: &str) -> String {
+    format!("{}:{alphagram}", language.code())
+}
+
+/// Flashcard progress of every account, a Leitner box per card.
+#[derive(Clone)]
+pub struct FlashcardStore(Arc<Mutex<JsonFile<Progress>>>);
+
+impl FlashcardStore {
+    pub fn load(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        Ok(Self(Arc::new(Mutex::new(JsonFile::load(path)?))))
+    }
+
+    /// The card due the longest, or else the most probable one never seen.
+    pub async fn deal(&self, user: &str, language: Language, length: usize) -> FlashcardDeal {
+        let now = now();
+        let deck = bingo_deck(language, length);
+        let file = self.0.lock().await;
+        let empty = BTreeMap::new();
+        let progress = file.get(&user.to_lowercase()).unwrap_or(&empty);
+        let progress_of = |alphagram: &str| progress.get(&card_key(language, alphagram));
+
+        let mut stats = FlashcardStats {
+            levels: vec![0; REVIEW_HOURS.len() - 1],
+            ..FlashcardStats::default()
+        };
+        for card in deck {
+            match progress_of(&card.alphagram) {
+                Some(progress) => {
+                    if let Some(count) = stats.levels.get_mut(progress.level.saturating_sub(1)) {
+                        *count += 1;
+                    }
+                    if progress.due <= now {
+                        stats.due += 1;
+                    }
+                }
+                None => stats.unseen += 1,
+            }
+        }
+
+        let due = deck
+            .iter()
+            .filter_map(|card| Some((card, progress_of(&card.alphagram)?)))
+            .filter(|(_, progress)| progress.due <= now)
+            .min_by_key(|(_, progress)| progress.due);
+        let card = match due {
+            Some((card, progress)) => Some((card, progress.level)),
+            None => deck
+                .iter()
+                .find(|card| progress_of(&card.alphagram).is_none())
+                .map(|card| (card, 0)),
+        };
+        FlashcardDeal {
+            card: card.map(|(card, level)| Flashcard {
+                alphagram: card.alphagram.clone(),
+                solutions: card.words.len(),
+                probability: card.probability,
+                level,
+            }),
+            stats,
+        }
+    }
+
+    /// Checks the words typed for a card: all of its anagrams and nothing
+    /// else moves it up a level, anything less sends it back to level 1.
+    pub async fn answer(
+        &self,
+        user: &str,
+        language: Language,
+        alphagram: &str,
+        answer: &str,
+    ) -> Result<FlashcardAnswer, FlashcardError> {
+        let alphagram = normalize_word(alphagram);
+        let length = alphagram.chars().count();
+        let card = bingo_deck(language, length)
+            .iter()
+            .find(|card| card.alphagram == alphagram)
+            .ok_or(FlashcardError::UnknownCard)?;
+        let mut typed = answer
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(normalize_word)
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        typed.sort();
+        typed.dedup();
+        let missed = card
+            .words
+            .iter()
+            .filter(|word| !typed.contains(word))
+            .cloned()
+            .collect::<Vec<_>>();
+        let wrong = typed
+            .into_iter()
+            .filter(|word| !card.words.contains(word))
+            .collect::<Vec<_>>();
+        let correct = missed.is_empty() && wrong.is_empty();
+
+        let now = now();
+        let mut file = self.0.lock().await;
+        let progress = file
+            .entry(user.to_lowercase())
+            .or_default()
+            .entry(card_key(language, &card.alphagram))
+            .or_insert(CardProgress {
+                level: 0,
+                due: now,
+                seen: 0,
+                correct: 0,
+            });
+        let before = progress.clone();
+        progress.seen += 1;
+        if correct {
+            progress.correct += 1;
+            progress.level = (progress.level + 1).min(REVIEW_HOURS.len() - 1);
+            progress.due = now + REVIEW_HOURS[progress.level] * 3600;
+        } else {
+            progress.level = 1;
+            progress.due = now + RETRY_AFTER;
+        }
+        let level = progress.level;
+        if let Err(error) = file.save(FlashcardError::Storage).await {
+            if let Some(progress) = file
+                .get_mut(&user.to_lowercase())
+                .and_then(|cards| cards.get_mut(&card_key(language, &card.alphagram)))
+            {
+                *progress = before;
+            }
+            return Err(error);
+        }
+
+        Ok(FlashcardAnswer {
+            correct,
+            words: card.words.clone(),
+            missed,
+            wrong,
+            level,
+        })
+    }
+}
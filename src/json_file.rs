@@ -0,0 +1,70 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+/// A value kept on disk as one pretty-printed JSON file, read at startup and
+/// written whole after each change.
+pub struct JsonFile<T> {
+    path: PathBuf,
+    value: T,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonFile<T> {
+    /// The default value when the file does not exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let value = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => T::default(),
+            Err(error) => return Err(error),
+        };
+        Ok(Self { path, value })
+    }
+
+    /// Writes the value on the blocking threads, creating the directory if
+    /// needed. A failure is logged and reported as `failed`, the storage
+    /// error of the caller.
+    pub async fn save<E>(&self, failed: E) -> Result<(), E> {
+        let path = self.path.clone();
+        let written = match serde_json::to_vec_pretty(&self.value) {
+            Ok(bytes) => tokio::task::spawn_blocking(move || replace(&path, &bytes))
+                .await
+                .unwrap_or_else(|error| Err(std::io::Error::other(error))),
+            Err(error) => Err(error.into()),
+        };
+        written.map_err(|error| {
+            tracing::error!("could not save {}: {error}", self.path.display());
+            failed
+        })
+    }
+}
+
+/// Writes a temporary file beside `path` and renames it over, so that a
+/// crash mid-write leaves the previous content whole.
+fn replace(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = std::fs::File::create(&temporary)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)
+}
+
+impl<T> Deref for JsonFile<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for JsonFile<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,203 @@
+use crate::app::{ChatError, ChatMessage, GameSnapshot, LiveSummary, LiveTicket, MAX_DELAY_ROUNDS};
+use crate::error_template::AppError;
+use crate::json_file::JsonFile;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+/// Publications kept per game, enough for the longest delay with four seats.
+const MAX_SNAPSHOTS: usize = 32;
+/// Games kept in memory, finished or not.
+const MAX_GAMES: usize = 200;
+/// Games in progress one account may publish at once.
+const MAX_GAMES_PER_OWNER: usize = 3;
+/// A game not published to for that long is taken as abandoned.
+const ABANDONED_AFTER: Duration = Duration::from_secs(2 * 60 * 60);
+
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+pub enum LiveError {
+    /// Also returned for a wrong token or owner, so ids cannot be probed.
+    #[error("unknown-game")]
+    UnknownGame,
+    #[error("too-many-games")]
+    TooManyGames,
+    #[error("storage")]
+    Storage,
+}
+
+impl From<LiveError> for AppError {
+    fn from(error: LiveError) -> Self {
+        match error {
+            LiveError::UnknownGame => AppError::GameNotFound,
+            LiveError::TooManyGames => AppError::TooManyGames,
+            LiveError::Storage => AppError::Storage,
+        }
+    }
+}
+
+/// The positions stay in memory; the ticket and the chat are saved, so that
+/// the players' pages carry on publishing after a restart.
+#[derive(Serialize, Deserialize)]
+struct LiveGame {
+    token: String,
+    /// Lowercase name of the account publishing it.
+    owner: String,
+    /// The last [`MAX_SNAPSHOTS`] publications, oldest first.
+    #[serde(skip)]
+    snapshots: VecDeque<GameSnapshot>,
+    #[serde(skip, default = "Instant::now")]
+    updated: Instant,
+    chat: Vec<ChatMessage>,
+}
+
+/// Games in progress published by the players' pages.
+#[derive(Clone)]
+pub struct LiveGames(Arc<Mutex<JsonFile<BTreeMap<u64, LiveGame>>>>);
+
+impl LiveGames {
+    pub fn load(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        Ok(Self(Arc::new(Mutex::new(JsonFile::load(path)?))))
+    }
+
+    /// Starts a new live game of `owner` without a ticket, otherwise appends
+    /// to theirs. Finished and abandoned games make room for new ones.
+    pub async fn publish(
+        &self,
+        owner: &str,
+        ticket: Option<LiveTicket>,
+        snapshot: GameSnapshot,
+    ) -> Result<LiveTicket, LiveError> {
+        let owner = owner.to_lowercase();
+        let mut games = self.0.lock().await;
+        let now = Instant::now();
+        match ticket {
+            Some(ticket) => {
+                let game = games
+                    .get_mut(&ticket.id)
+                    .filter(|game| game.token == ticket.token && game.owner == owner)
+                    .ok_or(LiveError::UnknownGame)?;
+                if game.snapshots.len() == MAX_SNAPSHOTS {
+                    game.snapshots.pop_front();
+                }
+                game.snapshots.push_back(snapshot);
+                game.updated = now;
+                Ok(ticket)
+            }
+            None => {
+                let playing = |game: &LiveGame| {
+                    now.duration_since(game.updated) < ABANDONED_AFTER
+                        && game.snapshots.back().is_some_and(|latest| !latest.finished)
+                };
+                let owned = games
+                    .values()
+                    .filter(|game| game.owner == owner && playing(game))
+                    .count();
+                if owned >= MAX_GAMES_PER_OWNER {
+                    return Err(LiveError::TooManyGames);
+                }
+                if games.len() >= MAX_GAMES {
+                    let over = games
+                        .iter()
+                        .find(|(_, game)| !playing(game))
+                        .map(|(id, _)| *id)
+                        .ok_or(LiveError::TooManyGames)?;
+                    games.remove(&over);
+                }
+
+                let id = games.last_key_value().map_or(1, |(id, _)| id + 1);
+                let token = thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .map(char::from)
+                    .collect::<String>();
+                games.insert(
+                    id,
+                    LiveGame {
+                        token: token.clone(),
+                        owner,
+                        snapshots: VecDeque::from([snapshot]),
+                        updated: now,
+                        chat: Vec::new(),
+                    },
+                );
+                if let Err(error) = games.save(LiveError::Storage).await {
+                    games.remove(&id);
+                    return Err(error);
+                }
+                Ok(LiveTicket { id, token })
+            }
+        }
+    }
+
+    /// Games still being played, newest first.
+    pub async fn list(&self) -> Vec<LiveSummary> {
+        let games = self.0.lock().await;
+        games
+            .iter()
+            .rev()
+            .filter_map(|(id, game)| {
+                let latest = game.snapshots.back()?;
+                (!latest.finished).then(|| LiveSummary {
+                    id: *id,
+                    seats: latest.without_racks().seats,
+                    turns: latest.turns,
+                })
+            })
+            .collect()
+    }
+
+    /// The latest position with every rack hidden, or the position `rounds`
+    /// rounds ago with the racks shown, so that they cannot be passed on to
+    /// a player. The final racks are shown once the game is over.
+    pub async fn spectate(&self, id: u64, show_racks: bool, rounds: usize) -> Option<GameSnapshot> {
+        let games = self.0.lock().await;
+        let snapshots = &games.get(&id)?.snapshots;
+        let latest = snapshots.back()?;
+        if latest.finished {
+            return Some(latest.clone());
+        }
+        if !show_racks {
+            return Some(latest.without_racks());
+        }
+        let delay = rounds.clamp(1, MAX_DELAY_ROUNDS) * latest.seats.len();
+        match snapshots.len().checked_sub(delay + 1) {
+            Some(idx) => snapshots.get(idx).cloned(),
+            None => Some(latest.without_racks()),
+        }
+    }
+
+    pub async fn post(&self, id: u64, message: ChatMessage) -> Result<(), ChatError> {
+        let mut games = self.0.lock().await;
+        let game = games.get_mut(&id).ok_or(ChatError::UnknownGame)?;
+        game.chat.push(message);
+        if let Err(error) = games.save(ChatError::Storage).await {
+            if let Some(game) = games.get_mut(&id) {
+                game.chat.pop();
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    pub async fn chat(&self, id: u64) -> Result<Vec<ChatMessage>, ChatError> {
+        let games = self.0.lock().await;
+        games
+            .get(&id)
+            .map(|game| game.chat.clone())
+            .ok_or(ChatError::UnknownGame)
+    }
+
+    /// The chat of the game the ticket was issued for, to store with its result.
+    pub async fn chat_for(&self, ticket: &LiveTicket) -> Option<Vec<ChatMessage>> {
+        let games = self.0.lock().await;
+        games
+            .get(&ticket.id)
+            .filter(|game| game.token == ticket.token)
+            .map(|game| game.chat.clone())
+    }
+}
@@ -1,33 +1,170 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
-    use axum::Router;
+    leptos_krabbels_6::telemetry::init_tracing();
+    if let Err(error) = serve().await {
+        tracing::error!("{error}");
+        std::process::exit(1);
+    }
+}
+
+/// Loads the configuration and every store, then serves the site. Whatever
+/// is missing or invalid stops the startup with a message saying what.
+#[cfg(feature = "ssr")]
+async fn serve() -> Result<(), String> {
+    use axum::{middleware, routing::get, Extension, Router};
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
+    use leptos_krabbels_6::analysis::Analyses;
+    use leptos_krabbels_6::api::words_handler;
     use leptos_krabbels_6::app::*;
+    use leptos_krabbels_6::auth::{session_middleware, AuthState};
+    use leptos_krabbels_6::chat::ChatGuard;
+    use leptos_krabbels_6::config::ServerConfig;
+    use leptos_krabbels_6::correspondence::CorrespondenceStore;
     use leptos_krabbels_6::fileserv::file_and_error_handler;
+    use leptos_krabbels_6::flashcards::FlashcardStore;
+    use leptos_krabbels_6::live::LiveGames;
+    use leptos_krabbels_6::notify::OutboxNotifier;
+    use leptos_krabbels_6::profanity::ProfanityFilter;
+    use leptos_krabbels_6::ratings::ResultStore;
+    use leptos_krabbels_6::telemetry::Metrics;
+    use leptos_krabbels_6::tournaments::TournamentStore;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+    use tracing::Level;
+
+    let cannot_load =
+        |path: &Path, error: std::io::Error| format!("could not load {}: {error}", path.display());
 
+    let config = ServerConfig::load().map_err(|error| format!("invalid configuration: {error}"))?;
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
     // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
     // Alternately a file can be specified such as Some("Cargo.toml")
     // The file would need to be included with the executable when moved to deployment
-    let conf = get_configuration(None).await.unwrap();
-    let leptos_options = conf.leptos_options;
+    let conf = get_configuration(None)
+        .await
+        .map_err(|error| format!("invalid Leptos configuration: {error}"))?;
+    let mut leptos_options = conf.leptos_options;
+    if let Some(address) = config.bind_address {
+        leptos_options.site_addr = address;
+    }
     let addr = leptos_options.site_addr;
+    if let Some(dir) = &config.lexicon_dir {
+        load_word_lists(dir).map_err(|error| error.message(Language::English))?;
+    }
     let routes = generate_route_list(App);
 
+    let auth = AuthState::load(
+        config.data_file("users.json"),
+        config.data_file("revoked_sessions.json"),
+        config.session_secret.as_deref(),
+    )
+    .map_err(|error| format!("could not load the accounts: {error}"))?;
+    let results_file = config.data_file("results.json");
+    let results =
+        ResultStore::load(&results_file).map_err(|error| cannot_load(&results_file, error))?;
+    let live_file = config.data_file("live.json");
+    let live = LiveGames::load(&live_file).map_err(|error| cannot_load(&live_file, error))?;
+    let metrics = Metrics::default();
+    let analyses = Analyses::default();
+    let profanity = match config.profanity_file() {
+        Some(file) => ProfanityFilter::load(file).map_err(|error| cannot_load(file, error))?,
+        None => ProfanityFilter::default(),
+    };
+    let chat = ChatGuard::new(profanity);
+    let games_file = config.data_file("games.json");
+    let correspondence = CorrespondenceStore::load(
+        &games_file,
+        results.clone(),
+        auth.clone(),
+        Arc::new(OutboxNotifier::new(
+            config.data_file("outbox"),
+            &config.mail_from,
+        )),
+        &config
+            .site_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{addr}")),
+        config.games.max_active_games,
+    )
+    .map_err(|error| cannot_load(&games_file, error))?;
+    let flashcards_file = config.data_file("flashcards.json");
+    let flashcards = FlashcardStore::load(&flashcards_file)
+        .map_err(|error| cannot_load(&flashcards_file, error))?;
+    let tournaments_file = config.data_file("tournaments.json");
+    let tournaments =
+        TournamentStore::load(&tournaments_file, auth.clone(), correspondence.clone())
+            .map_err(|error| cannot_load(&tournaments_file, error))?;
+
+    // Forfeits correspondence games whose player ran out of time.
+    tokio::spawn({
+        let correspondence = correspondence.clone();
+        let period = Duration::from_secs(config.games.expiry_check_seconds);
+        async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                correspondence.expire().await;
+            }
+        }
+    });
+
     // build our application with a route
     let app = Router::new()
-        .leptos_routes(&leptos_options, routes, App)
+        .route("/api/words", get(words_handler))
+        .route(
+            "/metrics",
+            get({
+                let metrics = metrics.clone();
+                let correspondence = correspondence.clone();
+                let live = live.clone();
+                move || async move { metrics.response(&correspondence, &live).await }
+            }),
+        )
+        .leptos_routes_with_context(
+            &leptos_options,
+            routes,
+            {
+                let auth = auth.clone();
+                let config = config.clone();
+                let metrics = metrics.clone();
+                move || {
+                    provide_context(config.clone());
+                    provide_context(auth.clone());
+                    provide_context(results.clone());
+                    provide_context(live.clone());
+                    provide_context(chat.clone());
+                    provide_context(correspondence.clone());
+                    provide_context(tournaments.clone());
+                    provide_context(flashcards.clone());
+                    provide_context(metrics.clone());
+                    provide_context(analyses.clone());
+                }
+            },
+            App,
+        )
         .fallback(file_and_error_handler)
+        .layer(middleware::from_fn_with_state(auth, session_middleware))
+        .layer(Extension(config))
+        .layer(Extension(metrics))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
         .with_state(leptos_options);
 
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    logging::log!("listening on http://{}", &addr);
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .map_err(|error| format!("could not listen on {addr}: {error}"))?;
+    tracing::info!("listening on http://{addr}");
     axum::serve(listener, app.into_make_service())
         .await
-        .unwrap();
+        .map_err(|error| format!("server stopped: {error}"))
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A message for one account, already written in the game's language.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub account: String,
+    pub email: Option<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers correspondence notifications. Implementations must not block for
+/// long: they are called while a move is being answered.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, notification: &Notification) -> std::io::Result<()>;
+}
+
+/// Stand-in for an SMTP relay: every notification is written as an RFC 5322
+/// message (`.eml`) in a directory, ready to be inspected or piped to
+/// `sendmail`. Accounts without an email address are skipped.
+pub struct OutboxNotifier {
+    dir: PathBuf,
+    from: String,
+    sent: AtomicU64,
+}
+
+impl OutboxNotifier {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            from: from.to_string(),
+            sent: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Notifier for OutboxNotifier {
+    fn notify(&self, notification: &Notification) -> std::io::Result<()> {
+        let Some(email) = &notification.email else {
+            tracing::debug!(
+                "no email for {}, notification dropped",
+                notification.account
+            );
+            return Ok(());
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let count = self.sent.fetch_add(1, Ordering::Relaxed);
+        let message = format!(
+            "From: {}\r\nTo: {} <{}>\r\nSubject: {}\r\nMessage-ID: <{now}.{count}@krabbels>\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+            self.from,
+            notification.account,
+            email,
+            encode_header(&notification.subject),
+            notification.body.replace('\n', "\r\n"),
+        );
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.dir.join(format!("{now}-{count}.eml")), message)
+    }
+}
+
+/// RFC 2047 `Q` encoding, so accented subjects survive SMTP relays.
+fn encode_header(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    let encoded = text
+        .bytes()
+        .map(|byte| match byte {
+            b' ' => "_".to_string(),
+            b if b.is_ascii_alphanumeric() => (b as char).to_string(),
+            b => format!("={b:02X}"),
+        })
+        .collect::<String>();
+    format!("=?utf-8?Q?{encoded}?=")
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Words masked in chat messages. Operators keep them in a plain text file,
+/// one word per line, `#` starting a comment.
+#[derive(Clone, Default)]
+pub struct ProfanityFilter(Arc<HashSet<String>>);
+
+impl ProfanityFilter {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(words) => Ok(Self::from_words(&words)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn from_words(words: &str) -> Self {
+        Self(Arc::new(
+            words
+                .lines()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|word| !word.is_empty() && !word.starts_with('#'))
+                .collect(),
+        ))
+    }
+
+    /// Replaces every letter of a listed word with `*`, keeping the rest as typed.
+    pub fn clean(&self, text: &str) -> String {
+        let mut cleaned = String::with_capacity(text.len());
+        let mut word = String::new();
+        let flush = |word: &mut String, cleaned: &mut String| {
+            if self.0.contains(&word.to_lowercase()) {
+                cleaned.extend(word.chars().map(|_| '*'));
+            } else {
+                cleaned.push_str(word);
+            }
+            word.clear();
+        };
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else {
+                flush(&mut word, &mut cleaned);
+                cleaned.push(c);
+            }
+        }
+        flush(&mut word, &mut cleaned);
+        cleaned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_masks_listed_words_in_any_case() {
+        let filter = ProfanityFilter::from_words("# masked in chat\nzut\n  Flute \n");
+        assert_eq!(filter.clean("Zut, FLUTE et flûte!"), "***, ***** et flûte!");
+        assert_eq!(filter.clean("zutalors"), "zutalors");
+        assert_eq!(filter.clean("# masked in chat"), "# masked in chat");
+    }
+
+    #[test]
+    fn a_missing_list_masks_nothing() {
+        let filter = ProfanityFilter::load("config/no-such-list.txt").unwrap();
+        assert_eq!(filter.clean("zut"), "zut");
+    }
+}
@@ -0,0 +1,366 @@
+use crate::app::{GameRecord, LeaderboardEntry, RatingPoint};
+use crate::json_file::JsonFile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+/// Glicko-2 system constant, constrains the volatility change per period.
+const TAU: f64 = 0.5;
+/// Converts ratings between the Glicko and the Glicko-2 scale.
+const SCALE: f64 = 173.7178;
+const CONVERGENCE: f64 = 0.000001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+impl Rating {
+    /// Glicko-2 update for one rating period. `results` holds each opponent's
+    /// rating before the period and the score against them (1, 0.5 or 0).
+    pub fn update(&self, results: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - 1500.0) / SCALE;
+        let phi = self.deviation / SCALE;
+        if results.is_empty() {
+            let deviation = (phi * phi + self.volatility * self.volatility).sqrt() * SCALE;
+            return Rating {
+                deviation: deviation.min(350.0),
+                ..*self
+            };
+        }
+
+        let g = |phi: f64| 1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt();
+        let mut variance_inv = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / SCALE;
+            let g_j = g(opponent.deviation / SCALE);
+            let expected = 1.0 / (1.0 + (-g_j * (mu - mu_j)).exp());
+            variance_inv += g_j * g_j * expected * (1.0 - expected);
+            improvement += g_j * (score - expected);
+        }
+        let v = 1.0 / variance_inv;
+        let delta = v * improvement;
+
+        // New volatility, found with the Illinois variant of regula falsi.
+        let a = (self.volatility * self.volatility).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+                - (x - a) / (TAU * TAU)
+        };
+        let mut lower = a;
+        let mut upper = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let (mut f_lower, mut f_upper) = (f(lower), f(upper));
+        while (upper - lower).abs() > CONVERGENCE {
+            let next = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+            let f_next = f(next);
+            if f_next * f_upper <= 0.0 {
+                lower = upper;
+                f_lower = f_upper;
+            } else {
+                f_lower /= 2.0;
+            }
+            upper = next;
+            f_upper = f_next;
+        }
+        let volatility = (lower / 2.0).exp();
+
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi * new_phi * improvement;
+        Rating {
+            rating: new_mu * SCALE + 1500.0,
+            deviation: new_phi * SCALE,
+            volatility,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    pub name: String,
+    pub rating: Rating,
+    /// Games played on the server; local games are not counted.
+    pub games: usize,
+    pub total_score: usize,
+    pub history: Vec<RatingPoint>,
+}
+
+impl PlayerRecord {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rating: Rating::default(),
+            games: 0,
+            total_score: 0,
+            history: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+pub enum ResultError {
+    #[error("not-a-player")]
+    NotAPlayer,
+    #[error("storage")]
+    Storage,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Results {
+    games: Vec<GameRecord>,
+    /// Keyed by lowercase account name, like the accounts.
+    players: BTreeMap<String, PlayerRecord>,
+}
+
+/// Recorded games and player ratings, shared by the result server functions.
+#[derive(Clone)]
+pub struct ResultStore(Arc<Mutex<JsonFile<Results>>>);
+
+impl ResultStore {
+    pub fn load(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        Ok(Self(Arc::new(Mutex::new(JsonFile::load(path)?))))
+    }
+
+    /// Stores a finished game played on the server, where every move was
+    /// checked. It is rated when every seat is a distinct registered account;
+    /// each pair of players counts as one win, draw or loss in a single
+    /// rating period.
+    pub async fn record(&self, mut game: GameRecord) -> Result<GameRecord, ResultError> {
+        let keys = game
+            .seats
+            .iter()
+            .map(|seat| seat.account.as_ref().map(|name| name.to_lowercase()))
+            .collect::<Vec<_>>();
+        let accounts = keys.iter().flatten().collect::<Vec<_>>();
+        let mut distinct = accounts.clone();
+        distinct.sort();
+        distinct.dedup();
+        game.rated = game.seats.len() > 1
+            && accounts.len() == game.seats.len()
+            && distinct.len() == accounts.len();
+
+        let mut results = self.0.lock().await;
+        let previous = results.players.clone();
+        for (seat, key) in game.seats.iter().zip(&keys) {
+            let Some(key) = key else { continue };
+            let name = seat.account.as_deref().unwrap_or(&seat.name);
+            let player = results
+                .players
+                .entry(key.clone())
+                .or_insert_with(|| PlayerRecord::new(name));
+            player.games += 1;
+            player.total_score += seat.score;
+        }
+
+        game.id = results.games.last().map_or(1, |last| last.id + 1);
+        if game.rated {
+            let ratings = keys
+                .iter()
+                .flatten()
+                .map(|key| {
+                    previous
+                        .get(key)
+                        .map(|player| player.rating)
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+            for (idx, key) in keys.iter().flatten().enumerate() {
+                let outcomes = (0..game.seats.len())
+                    .filter(|other| *other != idx)
+                    .map(|other| (ratings[other], game.outcome(idx, other)))
+                    .collect::<Vec<_>>();
+                let rating = ratings[idx].update(&outcomes);
+                if let Some(player) = results.players.get_mut(key) {
+                    player.rating = rating;
+                    player.history.push(RatingPoint {
+                        game: game.id,
+                        rating: rating.rating,
+                        deviation: rating.deviation,
+                    });
+                }
+            }
+        }
+
+        Self::push(&mut results, game, previous).await
+    }
+
+    /// Stores a local game reported by one of its players, for them to look
+    /// back on. The page alone vouches for its moves, so it is never rated,
+    /// does not count on the leaderboard and a forfeit on its clock is dropped.
+    /// Only the seat of the recorder keeps its account, so that nobody else
+    /// finds a game they did not play in their history.
+    pub async fn record_local(
+        &self,
+        mut game: GameRecord,
+        recorder: &str,
+    ) -> Result<GameRecord, ResultError> {
+        let mut is_seated = false;
+        for seat in &mut game.seats {
+            let is_recorder = !is_seated
+                && seat
+                    .account
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(recorder));
+            seat.account = is_recorder.then(|| recorder.to_string());
+            is_seated |= is_recorder;
+        }
+        if !is_seated {
+            return Err(ResultError::NotAPlayer);
+        }
+        game.rated = false;
+        game.forfeited = None;
+
+        let mut results = self.0.lock().await;
+        // The page may report the same game again after a reconnect.
+        if let Some(known) = results
+            .games
+            .iter()
+            .find(|known| known.seats == game.seats && known.history == game.history)
+        {
+            return Ok(known.clone());
+        }
+
+        let previous = results.players.clone();
+        for seat in &game.seats {
+            if let Some(name) = &seat.account {
+                results
+                    .players
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| PlayerRecord::new(name));
+            }
+        }
+        game.id = results.games.last().map_or(1, |last| last.id + 1);
+        Self::push(&mut results, game, previous).await
+    }
+
+    /// Adds the game and saves, putting the players back as they were if
+    /// the file cannot be written.
+    async fn push(
+        results: &mut JsonFile<Results>,
+        mut game: GameRecord,
+        previous: BTreeMap<String, PlayerRecord>,
+    ) -> Result<GameRecord, ResultError> {
+        game.finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        results.games.push(game.clone());
+        if let Err(error) = results.save(ResultError::Storage).await {
+            results.games.pop();
+            results.players = previous;
+            return Err(error);
+        }
+        Ok(game)
+    }
+
+    /// Players with at least one rated game, best rating first.
+    pub async fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        let results = self.0.lock().await;
+        let mut entries = results
+            .players
+            .values()
+            .filter(|player| !player.history.is_empty())
+            .map(|player| LeaderboardEntry {
+                name: player.name.clone(),
+                rating: player.rating.rating,
+                deviation: player.rating.deviation,
+                games: player.games,
+                average_score: player.total_score as f64 / player.games.max(1) as f64,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        entries
+    }
+
+    /// Recorded games with `name` in one of the seats, oldest first.
+    pub async fn games_of(&self, name: &str) -> Vec<GameRecord> {
+        let key = name.to_lowercase();
+        let results = self.0.lock().await;
+        results
+            .games
+            .iter()
+            .filter(|game| {
+                game.seats.iter().any(|seat| {
+                    seat.account
+                        .as_ref()
+                        .is_some_and(|account| account.to_lowercase() == key)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub async fn game(&self, id: u64) -> Option<GameRecord> {
+        let results = self.0.lock().await;
+        results.games.iter().find(|game| game.id == id).cloned()
+    }
+
+    pub async fn player(&self, name: &str) -> Option<PlayerRecord> {
+        let results = self.0.lock().await;
+        results.players.get(&name.to_lowercase()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        }
+    }
+
+    /// The example worked through in Glickman's "Example of the Glicko-2
+    /// system", which also uses τ = 0.5.
+    #[test]
+    fn update_matches_the_published_example() {
+        let updated = rating(1500.0, 200.0).update(&[
+            (rating(1400.0, 30.0), 1.0),
+            (rating(1550.0, 100.0), 0.0),
+            (rating(1700.0, 300.0), 0.0),
+        ]);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{updated:?}");
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{updated:?}");
+        assert!(
+            (updated.volatility - 0.05999).abs() < 0.00001,
+            "{updated:?}"
+        );
+    }
+
+    #[test]
+    fn deviation_grows_without_games() {
+        let updated = rating(1500.0, 200.0).update(&[]);
+        assert_eq!(updated.rating, 1500.0);
+        assert!(updated.deviation > 200.0);
+        assert_eq!(rating(1500.0, 350.0).update(&[]).deviation, 350.0);
+    }
+}
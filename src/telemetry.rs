@@ -0,0 +1,144 @@
+use crate::correspondence::CorrespondenceStore;
+use crate::live::LiveGames;
+use axum::http::header;
+use axum::response::IntoResponse;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
+
+/// Upper bounds, in seconds, of the lexicon lookup latency buckets.
+const LOOKUP_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0];
+const MOVE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Installs the global subscriber. `RUST_LOG` sets the level, `info` when
+/// missing, and `KRABBELS_LOG_FORMAT=json` writes one JSON object per line
+/// for log collectors.
+pub fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    if std::env::var("KRABBELS_LOG_FORMAT").is_ok_and(|format| format == "json") {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    moves: AtomicU64,
+    /// When each move of the last [`MOVE_WINDOW`] was played.
+    recent_moves: Mutex<VecDeque<Instant>>,
+    /// Lookups per bucket, the last one past every bound.
+    lookups: [AtomicU64; LOOKUP_BUCKETS.len() + 1],
+    lookup_micros: AtomicU64,
+}
+
+/// Counters scraped in the Prometheus text format from `/metrics`. Games in
+/// progress are counted from the stores when scraped.
+#[derive(Clone, Default)]
+pub struct Metrics(Arc<Counters>);
+
+impl Metrics {
+    pub fn record_move(&self) {
+        self.0.moves.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut recent = self.0.recent_moves.lock().unwrap();
+        recent.push_back(now);
+        while recent
+            .front()
+            .is_some_and(|played| now - *played > MOVE_WINDOW)
+        {
+            recent.pop_front();
+        }
+    }
+
+    /// Runs a lookup in the lexicon and records how long it took.
+    pub fn time_lookup<T>(&self, lookup: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = lookup();
+        let elapsed = start.elapsed();
+        let bucket = LOOKUP_BUCKETS
+            .iter()
+            .position(|bound| elapsed.as_secs_f64() <= *bound)
+            .unwrap_or(LOOKUP_BUCKETS.len());
+        self.0.lookups[bucket].fetch_add(1, Ordering::Relaxed);
+        self.0
+            .lookup_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        result
+    }
+
+    fn moves_per_minute(&self) -> usize {
+        let now = Instant::now();
+        let recent = self.0.recent_moves.lock().unwrap();
+        recent
+            .iter()
+            .filter(|played| now - **played <= MOVE_WINDOW)
+            .count()
+    }
+
+    fn render(&self, correspondence: usize, live: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP krabbels_active_games Games being played.");
+        let _ = writeln!(out, "# TYPE krabbels_active_games gauge");
+        let _ = writeln!(
+            out,
+            "krabbels_active_games{{kind=\"correspondence\"}} {}",
+            correspondence
+        );
+        let _ = writeln!(out, "krabbels_active_games{{kind=\"live\"}} {}", live);
+
+        let _ = writeln!(
+            out,
+            "# HELP krabbels_moves_total Moves played in correspondence games."
+        );
+        let _ = writeln!(out, "# TYPE krabbels_moves_total counter");
+        let _ = writeln!(
+            out,
+            "krabbels_moves_total {}",
+            self.0.moves.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "# HELP krabbels_moves_per_minute Moves played over the last minute."
+        );
+        let _ = writeln!(out, "# TYPE krabbels_moves_per_minute gauge");
+        let _ = writeln!(out, "krabbels_moves_per_minute {}", self.moves_per_minute());
+
+        let _ = writeln!(
+            out,
+            "# HELP krabbels_lexicon_lookup_seconds Time taken to check words in a lexicon."
+        );
+        let _ = writeln!(out, "# TYPE krabbels_lexicon_lookup_seconds histogram");
+        let mut count = 0;
+        for (idx, bound) in LOOKUP_BUCKETS.iter().enumerate() {
+            count += self.0.lookups[idx].load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "krabbels_lexicon_lookup_seconds_bucket{{le=\"{bound}\"}} {count}"
+            );
+        }
+        count += self.0.lookups[LOOKUP_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "krabbels_lexicon_lookup_seconds_bucket{{le=\"+Inf\"}} {count}"
+        );
+        let seconds = self.0.lookup_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "krabbels_lexicon_lookup_seconds_sum {seconds}");
+        let _ = writeln!(out, "krabbels_lexicon_lookup_seconds_count {count}");
+        out
+    }
+
+    /// The body of `GET /metrics`.
+    pub async fn response(
+        &self,
+        correspondence: &CorrespondenceStore,
+        live: &LiveGames,
+    ) -> impl IntoResponse {
+        let body = self.render(correspondence.active().await, live.list().await.len());
+        ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+    }
+}
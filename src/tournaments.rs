@@ -0,0 +1,697 @@
+use crate::app::{
+    ChallengeRule, Language, Pairing, PairingSystem, Standing, TournamentError, TournamentSummary,
+    TournamentView, MAX_ROUNDS, TURN_LIMITS,
+};
+use crate::auth::AuthState;
+use crate::correspondence::CorrespondenceStore;
+use crate::json_file::JsonFile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Spread credited for a bye, as is customary in club play.
+const BYE_SPREAD: i64 = 50;
+const MAX_NAME_LEN: usize = 60;
+/// Tables tried while looking for a round without rematches. The search is
+/// exponential at worst and runs under the lock, so past this the players
+/// are paired with their neighbours instead.
+const MAX_PAIRING_STEPS: usize = 10_000;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Tournament {
+    name: String,
+    system: PairingSystem,
+    organizer: String,
+    /// Planned rounds; zero for a round robin until it starts.
+    rounds: usize,
+    /// Registered account names, in registration order.
+    players: Vec<String>,
+    pairings: Vec<Vec<Pairing>>,
+}
+
+impl Tournament {
+    fn is_organizer(&self, name: &str) -> bool {
+        self.organizer.to_lowercase() == name.to_lowercase()
+    }
+
+    fn round_complete(&self) -> bool {
+        self.pairings
+            .last()
+            .is_none_or(|round| round.iter().all(|pairing| pairing.points().is_some()))
+    }
+
+    fn finished(&self) -> bool {
+        !self.pairings.is_empty() && self.pairings.len() >= self.rounds && self.round_complete()
+    }
+
+    fn met(&self, first: &str, second: &str) -> bool {
+        self.pairings.iter().flatten().any(|pairing| {
+            let players = (pairing.first.as_str(), pairing.second.as_deref());
+            players == (first, Some(second)) || players == (second, Some(first))
+        })
+    }
+
+    fn had_bye(&self, name: &str) -> bool {
+        self.pairings
+            .iter()
+            .flatten()
+            .any(|pairing| pairing.second.is_none() && pairing.first == name)
+    }
+
+    /// Ranked by points, then spread, then Buchholz.
+    fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|name| Standing {
+                name: name.clone(),
+                points: 0.0,
+                spread: 0,
+                buchholz: 0.0,
+                played: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut opponents = vec![Vec::new(); self.players.len()];
+        let index = |name: &str| self.players.iter().position(|player| player == name);
+
+        for pairing in self.pairings.iter().flatten() {
+            let (Some((first_points, second_points)), Some(first)) =
+                (pairing.points(), index(&pairing.first))
+            else {
+                continue;
+            };
+            match pairing.second.as_deref().and_then(index) {
+                Some(second) => {
+                    let (first_score, second_score) = pairing.scores.unwrap_or_default();
+                    let spread = first_score as i64 - second_score as i64;
+                    standings[first].points += first_points;
+                    standings[first].spread += spread;
+                    standings[first].played += 1;
+                    standings[second].points += second_points;
+                    standings[second].spread -= spread;
+                    standings[second].played += 1;
+                    opponents[first].push(second);
+                    opponents[second].push(first);
+                }
+                None => {
+                    standings[first].points += first_points;
+                    standings[first].spread += BYE_SPREAD;
+                }
+            }
+        }
+
+        let points = standings
+            .iter()
+            .map(|standing| standing.points)
+            .collect::<Vec<_>>();
+        for (standing, opponents) in standings.iter_mut().zip(&opponents) {
+            standing.buchholz = opponents
+                .iter()
+                .fold(0.0, |sum, opponent| sum + points[*opponent]);
+        }
+        standings.sort_by(|a, b| {
+            b.points
+                .total_cmp(&a.points)
+                .then(b.spread.cmp(&a.spread))
+                .then(b.buchholz.total_cmp(&a.buchholz))
+                .then(a.name.cmp(&b.name))
+        });
+        standings
+    }
+
+    fn next_round(&self) -> Vec<(String, Option<String>)> {
+        match self.system {
+            PairingSystem::Swiss => self.swiss_round(),
+            PairingSystem::RoundRobin => round_robin(&self.players, self.pairings.len()),
+        }
+    }
+
+    /// Pairs neighbours in the standings, avoiding rematches when possible.
+    /// With an odd count, the lowest ranked player without a bye sits out.
+    fn swiss_round(&self) -> Vec<(String, Option<String>)> {
+        let mut order = self
+            .standings()
+            .into_iter()
+            .map(|standing| standing.name)
+            .collect::<Vec<_>>();
+        let bye = (order.len() % 2 == 1).then(|| {
+            let idx = order
+                .iter()
+                .rposition(|name| !self.had_bye(name))
+                .unwrap_or(order.len() - 1);
+            order.remove(idx)
+        });
+        let mut budget = MAX_PAIRING_STEPS;
+        let tables = self
+            .pair_without_rematch(&order, &mut budget)
+            .unwrap_or_else(|| {
+                order
+                    .chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect()
+            });
+        tables
+            .into_iter()
+            .map(|(first, second)| (first, Some(second)))
+            .chain(bye.map(|name| (name, None)))
+            .collect()
+    }
+
+    /// `None` when there is no such pairing, or when `budget` tables were
+    /// tried without finding one.
+    fn pair_without_rematch(
+        &self,
+        remaining: &[String],
+        budget: &mut usize,
+    ) -> Option<Vec<(String, String)>> {
+        let Some((first, rest)) = remaining.split_first() else {
+            return Some(Vec::new());
+        };
+        for (idx, opponent) in rest.iter().enumerate() {
+            if self.met(first, opponent) {
+                continue;
+            }
+            if *budget == 0 {
+                return None;
+            }
+            *budget -= 1;
+            let mut others = rest.to_vec();
+            others.remove(idx);
+            if let Some(mut tables) = self.pair_without_rematch(&others, budget) {
+                tables.insert(0, (first.clone(), opponent.clone()));
+                return Some(tables);
+            }
+        }
+        None
+    }
+
+    fn summary(&self, id: u64) -> TournamentSummary {
+        TournamentSummary {
+            id,
+            name: self.name.clone(),
+            system: self.system,
+            players: self.players.len(),
+            round: self.pairings.len(),
+            rounds: self.rounds,
+        }
+    }
+}
+
+/// Rounds needed for everyone to meet everyone once.
+fn full_rounds(players: usize) -> usize {
+    (players + players % 2).saturating_sub(1)
+}
+
+/// Circle method: the first seat stays put while the others turn one step
+/// each round; the phantom seat of an odd count is the bye.
+fn round_robin(players: &[String], round: usize) -> Vec<(String, Option<String>)> {
+    let mut seats = players.iter().map(Some).collect::<Vec<_>>();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+    let count = seats.len();
+    if count < 2 {
+        return Vec::new();
+    }
+    seats[1..].rotate_right(round % (count - 1));
+    (0..count / 2)
+        .filter_map(|idx| match (seats[idx], seats[count - 1 - idx]) {
+            (Some(first), Some(second)) => Some((first.clone(), Some(second.clone()))),
+            (Some(player), None) | (None, Some(player)) => Some((player.clone(), None)),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// Club tournaments. Every pairing is played as a correspondence game whose
+/// result is picked up once it finishes; results played over the board are
+/// entered by hand.
+#[derive(Clone)]
+pub struct TournamentStore {
+    tournaments: Arc<Mutex<JsonFile<BTreeMap<u64, Tournament>>>>,
+    auth: AuthState,
+    games: CorrespondenceStore,
+}
+
+impl TournamentStore {
+    pub fn load(
+        path: impl Into<PathBuf>,
+        auth: AuthState,
+        games: CorrespondenceStore,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            tournaments: Arc::new(Mutex::new(JsonFile::load(path)?)),
+            auth,
+            games,
+        })
+    }
+
+    pub async fn create(
+        &self,
+        organizer: &str,
+        name: &str,
+        system: PairingSystem,
+        rounds: usize,
+    ) -> Result<u64, TournamentError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(TournamentError::InvalidName);
+        }
+        let rounds = match system {
+            PairingSystem::Swiss if (1..=MAX_ROUNDS).contains(&rounds) => rounds,
+            PairingSystem::Swiss => return Err(TournamentError::InvalidRounds),
+            PairingSystem::RoundRobin => 0,
+        };
+
+        let mut tournaments = self.tournaments.lock().await;
+        let id = tournaments.last_key_value().map_or(1, |(id, _)| id + 1);
+        tournaments.insert(
+            id,
+            Tournament {
+                name: name.to_string(),
+                system,
+                organizer: organizer.to_string(),
+                rounds,
+                players: Vec::new(),
+                pairings: Vec::new(),
+            },
+        );
+        if let Err(error) = tournaments.save(TournamentError::Storage).await {
+            tournaments.remove(&id);
+            return Err(error);
+        }
+        Ok(id)
+    }
+
+    /// Newest first.
+    pub async fn list(&self) -> Vec<TournamentSummary> {
+        let tournaments = self.tournaments.lock().await;
+        tournaments
+            .iter()
+            .rev()
+            .map(|(id, tournament)| tournament.summary(*id))
+            .collect()
+    }
+
+    pub async fn view(&self, id: u64) -> Result<TournamentView, TournamentError> {
+        let mut tournaments = self.tournaments.lock().await;
+        let tournament = tournaments
+            .get_mut(&id)
+            .ok_or(TournamentError::UnknownTournament)?;
+        let tournament = if self.sync(tournament).await {
+            let tournament = tournament.clone();
+            // A failed save is logged; the results are picked up again next time.
+            let _ = tournaments.save(TournamentError::Storage).await;
+            tournament
+        } else {
+            tournament.clone()
+        };
+        Ok(TournamentView {
+            id,
+            name: tournament.name.clone(),
+            system: tournament.system,
+            organizer: tournament.organizer.clone(),
+            rounds: tournament.rounds,
+            players: tournament.players.clone(),
+            pairings: tournament.pairings.clone(),
+            standings: tournament.standings(),
+            finished: tournament.finished(),
+        })
+    }
+
+    /// Adds the account `name` before the first round. Players register
+    /// themselves; the organizer may register anyone.
+    pub async fn register(&self, id: u64, user: &str, name: &str) -> Result<(), TournamentError> {
+        let mut tournaments = self.tournaments.lock().await;
+        let tournament = tournaments
+            .get_mut(&id)
+            .ok_or(TournamentError::UnknownTournament)?;
+        if !tournament.pairings.is_empty() {
+            return Err(TournamentError::Started);
+        }
+        if name.trim().to_lowercase() != user.to_lowercase() && !tournament.is_organizer(user) {
+            return Err(TournamentError::NotOrganizer);
+        }
+        let name = self
+            .auth
+            .account_name(name)
+            .await
+            .ok_or(TournamentError::UnknownPlayer)?;
+        if tournament.players.contains(&name) {
+            return Ok(());
+        }
+        tournament.players.push(name);
+        if let Err(error) = tournaments.save(TournamentError::Storage).await {
+            if let Some(tournament) = tournaments.get_mut(&id) {
+                tournament.players.pop();
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Pairs the next round once every result of the previous one is in, and
+    /// starts a correspondence game for each table.
+    pub async fn pair(
+        &self,
+        id: u64,
+        user: &str,
+        language: Language,
+    ) -> Result<(), TournamentError> {
+        let mut tournaments = self.tournaments.lock().await;
+        let tournament = tournaments
+            .get_mut(&id)
+            .ok_or(TournamentError::UnknownTournament)?;
+        if !tournament.is_organizer(user) {
+            return Err(TournamentError::NotOrganizer);
+        }
+        self.sync(tournament).await;
+        if tournament.finished() {
+            return Err(TournamentError::Finished);
+        }
+        if !tournament.round_complete() {
+            return Err(TournamentError::RoundInProgress);
+        }
+        if tournament.players.len() < 2 {
+            return Err(TournamentError::NotEnoughPlayers);
+        }
+
+        let before = tournament.clone();
+        if tournament.pairings.is_empty() {
+            let full = full_rounds(tournament.players.len());
+            tournament.rounds = match tournament.system {
+                PairingSystem::Swiss => tournament.rounds.min(full),
+                PairingSystem::RoundRobin => full,
+            };
+        }
+        let mut round = Vec::new();
+        for (first, second) in tournament.next_round() {
+            let game = match &second {
+                Some(second) => self
+                    .games
+                    .create(
+                        &first,
+                        second,
+                        ChallengeRule::Double,
+                        TURN_LIMITS[1],
+                        language,
+                    )
+                    .await
+                    .map_err(|error| {
+                        tracing::warn!("no game for {first} against {second}: {error}");
+                    })
+                    .ok(),
+                None => None,
+            };
+            round.push(Pairing {
+                first,
+                second,
+                game,
+                scores: None,
+                forfeited: None,
+                entered: false,
+            });
+        }
+        tournament.pairings.push(round);
+        if let Err(error) = tournaments.save(TournamentError::Storage).await {
+            tournaments.insert(id, before);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Sets the scores of a table played outside the site. Players may enter
+    /// the result of a table without a game once; the organizer may also
+    /// settle a game still in progress or correct an entered result. Results
+    /// taken from a finished game are never replaced.
+    pub async fn record(
+        &self,
+        id: u64,
+        user: &str,
+        round: usize,
+        table: usize,
+        scores: (usize, usize),
+    ) -> Result<(), TournamentError> {
+        let mut tournaments = self.tournaments.lock().await;
+        let tournament = tournaments
+            .get_mut(&id)
+            .ok_or(TournamentError::UnknownTournament)?;
+        let is_organizer = tournament.is_organizer(user);
+        self.sync(tournament).await;
+        let pairing = tournament
+            .pairings
+            .get_mut(round)
+            .and_then(|round| round.get_mut(table))
+            .filter(|pairing| pairing.second.is_some())
+            .ok_or(TournamentError::UnknownPairing)?;
+        let is_player = [Some(&pairing.first), pairing.second.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|name| name.to_lowercase() == user.to_lowercase());
+        if pairing.synced() {
+            return Err(TournamentError::ResultSynced);
+        }
+        let open = pairing.game.is_none() && pairing.scores.is_none();
+        if !is_organizer && !(is_player && open) {
+            return Err(TournamentError::NotOrganizer);
+        }
+
+        let before = pairing.clone();
+        pairing.scores = Some(scores);
+        pairing.entered = true;
+        if let Err(error) = tournaments.save(TournamentError::Storage).await {
+            if let Some(pairing) = tournaments
+                .get_mut(&id)
+                .and_then(|tournament| tournament.pairings.get_mut(round))
+                .and_then(|round| round.get_mut(table))
+            {
+                *pairing = before;
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Copies the results of finished games into their pairings; returns
+    /// whether anything changed.
+    async fn sync(&self, tournament: &mut Tournament) -> bool {
+        let mut changed = false;
+        for pairing in tournament.pairings.iter_mut().flatten() {
+            if pairing.scores.is_some() {
+                continue;
+            }
+            let Some(game) = pairing.game else { continue };
+            if let Some((scores, forfeited)) = self.games.result(game).await {
+                pairing.scores = Some(scores);
+                pairing.forfeited = forfeited;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn swiss(players: &[&str], pairings: Vec<Vec<Pairing>>) -> Tournament {
+        Tournament {
+            name: "Club".to_string(),
+            system: PairingSystem::Swiss,
+            organizer: players[0].to_string(),
+            rounds: 5,
+            players: players.iter().map(|name| name.to_string()).collect(),
+            pairings,
+        }
+    }
+
+    fn played(first: &str, second: &str, scores: (usize, usize)) -> Pairing {
+        Pairing {
+            first: first.to_string(),
+            second: Some(second.to_string()),
+            game: None,
+            scores: Some(scores),
+            forfeited: None,
+            entered: true,
+        }
+    }
+
+    fn bye(name: &str) -> Pairing {
+        Pairing {
+            first: name.to_string(),
+            second: None,
+            game: None,
+            scores: None,
+            forfeited: None,
+            entered: false,
+        }
+    }
+
+    /// Tables as unordered pairs, the bye being paired with "".
+    fn tables(round: &[(String, Option<String>)]) -> HashSet<(String, String)> {
+        round
+            .iter()
+            .map(|(first, second)| {
+                let second = second.clone().unwrap_or_default();
+                if *first < second {
+                    (first.clone(), second)
+                } else {
+                    (second, first.clone())
+                }
+            })
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> HashSet<(String, String)> {
+        expected
+            .iter()
+            .map(|(first, second)| (first.to_string(), second.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn standings_rank_points_then_spread() {
+        let tournament = swiss(
+            &["ann", "bob", "cat", "dan", "eve"],
+            vec![vec![
+                played("ann", "bob", (400, 300)),
+                played("cat", "dan", (350, 340)),
+                bye("eve"),
+            ]],
+        );
+        let standings = tournament.standings();
+        let order = standings
+            .iter()
+            .map(|standing| standing.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["ann", "eve", "cat", "dan", "bob"]);
+        assert_eq!(standings[1].spread, BYE_SPREAD);
+        assert_eq!(standings[1].played, 0);
+        assert_eq!(standings[0].buchholz, 0.0);
+        assert_eq!(standings[4].buchholz, 1.0);
+    }
+
+    #[test]
+    fn swiss_pairs_neighbours_in_the_standings() {
+        let tournament = swiss(
+            &["ann", "bob", "cat", "dan"],
+            vec![vec![
+                played("ann", "bob", (400, 300)),
+                played("cat", "dan", (350, 340)),
+            ]],
+        );
+        let round = tournament.swiss_round();
+        assert_eq!(round[0], ("ann".to_string(), Some("cat".to_string())));
+        assert_eq!(tables(&round), pairs(&[("ann", "cat"), ("bob", "dan")]));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let tournament = swiss(
+            &["ann", "bob", "cat", "dan"],
+            vec![
+                vec![
+                    played("ann", "bob", (400, 300)),
+                    played("cat", "dan", (350, 340)),
+                ],
+                vec![
+                    played("ann", "cat", (380, 300)),
+                    played("bob", "dan", (360, 350)),
+                ],
+            ],
+        );
+        assert_eq!(
+            tables(&tournament.swiss_round()),
+            pairs(&[("ann", "dan"), ("bob", "cat")])
+        );
+    }
+
+    #[test]
+    fn swiss_falls_back_to_neighbours_once_everyone_met() {
+        let tournament = swiss(
+            &["ann", "bob", "cat", "dan"],
+            vec![
+                vec![
+                    played("ann", "bob", (400, 300)),
+                    played("cat", "dan", (350, 340)),
+                ],
+                vec![
+                    played("ann", "cat", (380, 300)),
+                    played("bob", "dan", (360, 350)),
+                ],
+                vec![
+                    played("ann", "dan", (390, 310)),
+                    played("bob", "cat", (330, 320)),
+                ],
+            ],
+        );
+        let order = ["ann", "bob", "cat", "dan"].map(String::from);
+        let mut budget = MAX_PAIRING_STEPS;
+        assert_eq!(tournament.pair_without_rematch(&order, &mut budget), None);
+        let round = tournament.swiss_round();
+        assert_eq!(round.len(), 2);
+        let seated = round
+            .iter()
+            .flat_map(|(first, second)| [Some(first.clone()), second.clone()])
+            .flatten()
+            .collect::<HashSet<_>>();
+        assert_eq!(seated.len(), 4);
+    }
+
+    #[test]
+    fn pairing_gives_up_when_the_budget_runs_out() {
+        let tournament = swiss(&["ann", "bob", "cat", "dan"], Vec::new());
+        let order = ["ann", "bob", "cat", "dan"].map(String::from);
+        assert_eq!(tournament.pair_without_rematch(&order, &mut 0), None);
+        assert_eq!(
+            tournament.pair_without_rematch(&order, &mut 2),
+            Some(vec![
+                ("ann".to_string(), "bob".to_string()),
+                ("cat".to_string(), "dan".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn the_bye_goes_to_the_lowest_ranked_player_without_one() {
+        let tournament = swiss(
+            &["ann", "bob", "cat"],
+            vec![vec![played("ann", "bob", (400, 300)), bye("cat")]],
+        );
+        assert!(tournament.had_bye("cat"));
+        assert!(!tournament.had_bye("bob"));
+        let round = tournament.swiss_round();
+        assert_eq!(round.last(), Some(&("bob".to_string(), None)));
+        assert_eq!(tables(&round), pairs(&[("ann", "cat"), ("", "bob")]));
+    }
+
+    #[test]
+    fn round_robin_meets_everyone_once() {
+        for count in 2..=7 {
+            let players = (0..count).map(|idx| format!("p{idx}")).collect::<Vec<_>>();
+            let mut met = HashSet::new();
+            let mut byes = HashSet::new();
+            for round in 0..full_rounds(count) {
+                let mut seated = HashSet::new();
+                for (first, second) in round_robin(&players, round) {
+                    assert!(seated.insert(first.clone()));
+                    match second {
+                        Some(second) => {
+                            assert!(seated.insert(second.clone()));
+                            let table = tables(&[(first, Some(second))]);
+                            assert!(met.insert(table.into_iter().next().unwrap()));
+                        }
+                        None => assert!(byes.insert(first)),
+                    }
+                }
+                assert_eq!(seated.len(), count);
+            }
+            assert_eq!(met.len(), count * (count - 1) / 2);
+            assert_eq!(byes.len(), count % 2 * count);
+        }
+    }
+}